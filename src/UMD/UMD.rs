@@ -33,7 +33,7 @@ is happening to the data. This is not a finished product.
 // LIPS (CLOCKWISE ALWAYS) -> TONGUE -> JAW -> OTHERS
 // LANDMARK TYPE STRUCTURE HAS BEEN MOVED TO BE DRIVER SPECIFIC
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UMD {
     pub frame: Vec<u32>,
     pub timestamp: Vec<f32>,
@@ -16,7 +16,7 @@ MOSAIC. If not, see <https://www.gnu.org/licenses/>.
 
 // Drivers
 use crate::drivers::OpenFace::openface::{parse_openface_data};
-use crate::drivers::OpenFace::defaultCommands::{curves, areas};
+use crate::drivers::OpenFace::defaultCommands::{curves, areas, measures, pairs, angles, contours};
 
 // UMD
 use crate::UMD::anchor::anchor::{AnchorProcessor};
@@ -26,17 +26,46 @@ use crate::UMD::UMD::{UMD, UMDDriver};
 use crate::UMD::metadata::{Metadata};
 
 // Core Measurements
+use crate::coreMeasurements::pairs::pairs::{PairSpec};
 use crate::coreMeasurements::euclidean::euclidean::{EuclideanCalculator, CoreEuclidean};
-use crate::coreMeasurements::angle::angle::{AngleCalculator, CoreAngle};
+use crate::coreMeasurements::angle::angle::{AngleCalculator, CoreAngle, CoreJointAngle};
 use crate::coreMeasurements::curve::curve::{CurveCalculator, CoreCurve};
+use crate::coreMeasurements::curve::descriptor::{DescriptorCalculator, CurveDescriptor};
 use crate::coreMeasurements::area::area::{AreaCalculator, CoreArea};
+use crate::coreMeasurements::area::polygon::{PolygonCalculator, PolygonModel, ProjectionPlane};
+use crate::coreMeasurements::area::sector::{SectorCalculator, SectorScheme, CoreSectorArea};
+use crate::coreMeasurements::contour::contour::{ContourCalculator, ContourModel};
+use crate::coreMeasurements::named::named::{NamedMeasureCalculator, CoreNamedMeasure};
+use crate::coreMeasurements::volume::volume::{VolumeCalculator, CoreVolume};
+
+// complex measurements
+use crate::complexMeasurements::signal::signal::{Signal, Axis};
+use crate::complexMeasurements::coordination::coordination::{CoordinationCalculator, CoordinationTrial, CoordinationOptions, CoordinationPhase, CoordinationSummary};
+use crate::complexMeasurements::ddk::ddk::{DDKCalculator, DDKOptions, DDKCycles, DDKSummary};
+use crate::complexMeasurements::closure::closure::{ClosureCalculator, ClosureOptions, ClosureIntervals, Aperture};
+use crate::complexMeasurements::workspace::workspace::{WorkspaceCalculator, CoreWorkspace};
+use crate::complexMeasurements::segment::segment::{SegmentSummaryCalculator, SegmentSummary};
 
 // praat analysis
 use crate::praatAnalysis::setup::PythonEnvironment;
 use crate::praatAnalysis::testing::test_function;
+use crate::praatAnalysis::speechUMD::speechUMD::{SpeechUMD, SpeechParameters, SpeechMetadata, VoiceParameters, VoiceQuality};
+use crate::praatAnalysis::backend::backend::{SpeechExtractor, SpeechBackend, BackendComparison};
+use crate::praatAnalysis::extraction::extraction::{AudioExtractor};
+use crate::praatAnalysis::sync::sync::{SyncCalculator, SyncOptions, SyncCorrection};
+use crate::praatAnalysis::voice::voice::{VoiceQualityExtractor};
+use crate::praatAnalysis::vowel::vowel::{VowelSpaceCalculator, VowelMap, VowelTokens, VowelSpace};
+use crate::praatAnalysis::rate::rate::{SpeechRateCalculator, SpeechRateOptions, SyllableNuclei, SpeechRateSummary};
+
+// annotation
+use crate::annotation::textgrid::textgrid::{TextGrid, TextGridFormat, Tier, PointTier};
+use crate::annotation::textgrid::join::{TierJoin};
+use crate::annotation::segments::segments::{SegmentImporter, Segments};
 
 
 use std::path::Path;
+use std::fs::File;
+use polars::prelude::{ParquetReader, ParquetWriter, SerReader};
 use pyo3::prelude::*;
 use pyo3::Python;
 
@@ -105,8 +134,15 @@ impl run {
 
         // Core measurement testing:
         
+        // Pair specs (shared by euclidean + angle)
+        let mut landmarks: Vec<&str> = umd_instance.types.iter().map(String::as_str).collect();
+        landmarks.sort_unstable();
+        landmarks.dedup();
+        let pair_specs = PairSpec::parse_all(pairs::default_pairs)?;
+        PairSpec::check_landmarks(&pair_specs, &landmarks)?;
+
         // Eulidean
-        let euclidean_results = EuclideanCalculator::euclidean(&umd_instance, &["origin".to_string(), "*".to_string()]);
+        let euclidean_results = EuclideanCalculator::euclidean(&umd_instance, &pair_specs);
         let file_name = "euclidean.parquet";
         let euclidean_output_path = format!("{output_path}{file_name}"); 
         CoreEuclidean::save_euclidean_to_parquet(&euclidean_results, &euclidean_output_path).expect("Failed to write euclidean to parquet");
@@ -115,13 +151,31 @@ impl run {
 
         // Angle
 
-        let angle_results = AngleCalculator::angle(&umd_instance, &["origin".to_string(), "*".to_string()]);
+        let angle_results = AngleCalculator::angle(&umd_instance, &pair_specs);
         let file_name = "angle.parquet";
         let angle_output_path = format!("{output_path}{file_name}"); 
         CoreAngle::save_angle_to_parquet(&angle_results, &angle_output_path).expect("Failed to write angles to parquet");
         println!("Angle worked");
 
 
+        // Joint angles
+
+        let joint_angle_results = AngleCalculator::joint_angle(&umd_instance, angles::default_joint_angles);
+        let file_name = "joint_angles.parquet";
+        let joint_angle_output_path = format!("{output_path}{file_name}"); 
+        CoreJointAngle::save_joint_angle_to_parquet(&joint_angle_results, &joint_angle_output_path).expect("Failed to write joint angles to parquet");
+        println!("Joint angles worked");
+
+
+        // Named measures (lip aperture, lip width, protrusion)
+
+        let named_results = NamedMeasureCalculator::calculate(&umd_instance, measures::default_measures);
+        let file_name = "named_measures.parquet";
+        let named_output_path = format!("{output_path}{file_name}"); 
+        CoreNamedMeasure::save_named_measure_to_parquet(&named_results, &named_output_path).expect("Failed to write named measures to parquet");
+        println!("Named measures worked");
+
+
 
         // Curves
 
@@ -168,6 +222,364 @@ impl run {
         let area_output_path = format!("{output_path}{file_name}"); 
         CoreArea::save_area_to_parquet(&area_results, &area_output_path).expect("Failed to write area to parquet");
 
+        // left/right halves for asymmetry
+        let sector_results = SectorCalculator::calculate_sectors(&curve_results, &umd_instance, basis_sets, area_sets, &SectorScheme::LeftRight);
+        let file_name = "sector_areas.parquet";
+        let sector_output_path = format!("{output_path}{file_name}"); 
+        CoreSectorArea::save_sector_area_to_parquet(&sector_results, &sector_output_path).expect("Failed to write sector area to parquet");
+
+
+        // Closed contours
+
+        let contour_loops: &[&[&str]] = &[
+            contours::outer_lip_contour,
+            contours::inner_lip_contour,
+        ];
+
+        let contour_results = ContourCalculator::fit_contour(&umd_instance, contour_loops, ContourModel::PeriodicSpline);
+        let file_name = "contours.parquet";
+        let contour_output_path = format!("{output_path}{file_name}"); 
+        CoreCurve::save_curve_to_parquet(&contour_results, &contour_output_path).expect("Failed to write contours to parquet");
+
+        let contour_area_results = AreaCalculator::calculate_contour_area(&contour_results, &umd_instance, basis_sets, contour_loops);
+        let file_name = "contour_areas.parquet";
+        let contour_area_output_path = format!("{output_path}{file_name}"); 
+        CoreArea::save_area_to_parquet(&contour_area_results, &contour_area_output_path).expect("Failed to write contour area to parquet");
+
+        println!("Contours worked");
+
+
+
+        // Polygon areas (no curves or basis needed)
+
+        let polygon_sets: &[&[&str]] = &[
+            contours::outer_lip_contour,
+            contours::inner_lip_contour,
+        ];
+
+        let polygon_results = PolygonCalculator::polygon_area(&umd_instance, polygon_sets, PolygonModel::Shoelace, ProjectionPlane::XY);
+        let file_name = "polygon_areas.parquet";
+        let polygon_output_path = format!("{output_path}{file_name}"); 
+        CoreArea::save_area_to_parquet(&polygon_results, &polygon_output_path).expect("Failed to write polygon area to parquet");
+
+        let hull_results = PolygonCalculator::polygon_area(&umd_instance, polygon_sets, PolygonModel::ConvexHull, ProjectionPlane::XY);
+        let file_name = "hull_areas.parquet";
+        let hull_output_path = format!("{output_path}{file_name}"); 
+        CoreArea::save_area_to_parquet(&hull_results, &hull_output_path).expect("Failed to write hull area to parquet");
+
+        println!("Polygon areas worked");
+
+
+
+        // 3D volume and surface (skipped for 2D data)
+
+        let volume_loops: &[&[&str]] = &[
+            contours::outer_lip_contour,
+            contours::inner_lip_contour,
+        ];
+
+        match VolumeCalculator::calculate_volume(&umd_instance, &metadata, volume_loops, basis_sets) {
+            Ok(volume_results) => {
+                let file_name = "volumes.parquet";
+                let volume_output_path = format!("{output_path}{file_name}"); 
+                CoreVolume::save_volume_to_parquet(&volume_results, &volume_output_path).expect("Failed to write volume to parquet");
+                println!("Volumes worked");
+            }
+            Err(e) => println!("Volumes skipped: {}", e),
+        }
+
+
+
+        // Curve descriptors
+
+        let descriptor_results = DescriptorCalculator::describe(&curve_results);
+        let file_name = "curve_descriptors.parquet";
+        let descriptor_output_path = format!("{output_path}{file_name}"); 
+        CurveDescriptor::save_curve_descriptor_to_parquet(&descriptor_results, &descriptor_output_path).expect("Failed to write curve descriptors to parquet");
+
+        println!("Curve descriptors worked");
+
+
+
+        // Coordination
+        // upper vs lower lip vertical motion, one trial per input file
+
+        let trial_name = Path::new(input_path).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let coordination_trials = vec![CoordinationTrial {
+            trial: trial_name.clone(),
+            a: Signal::from_landmark(&umd_instance, "OuterPhiltrum", Axis::Y),
+            b: Signal::from_landmark(&umd_instance, "OuterLowerVermillionBorder", Axis::Y),
+        }];
+        let (phase_results, summary_results) = CoordinationCalculator::analyse(&coordination_trials, &CoordinationOptions::default());
+
+        let file_name = "coordination_phase.parquet";
+        let phase_output_path = format!("{output_path}{file_name}"); 
+        CoordinationPhase::save_coordination_phase_to_parquet(&phase_results, &phase_output_path).expect("Failed to write coordination phase to parquet");
+
+        let file_name = "coordination_summary.parquet";
+        let summary_output_path = format!("{output_path}{file_name}"); 
+        CoordinationSummary::save_coordination_summary_to_parquet(&summary_results, &summary_output_path).expect("Failed to write coordination summary to parquet");
+
+        println!("Coordination worked");
+
+
+
+        // DDK cycles (lip aperture)
+
+        let aperture = Signal::from_named(&named_results, "lip_aperture");
+        let (ddk_cycles, ddk_summary) = DDKCalculator::detect(&aperture, &DDKOptions::default());
+
+        let file_name = "ddk_cycles.parquet";
+        let ddk_cycles_output_path = format!("{output_path}{file_name}"); 
+        DDKCycles::save_ddk_cycles_to_parquet(&ddk_cycles, &ddk_cycles_output_path).expect("Failed to write DDK cycles to parquet");
+
+        let file_name = "ddk_summary.parquet";
+        let ddk_summary_output_path = format!("{output_path}{file_name}"); 
+        DDKSummary::save_ddk_summary_to_parquet(&ddk_summary, &ddk_summary_output_path).expect("Failed to write DDK summary to parquet");
+
+        println!("DDK worked");
+
+
+
+        // Lip closure (inner lip aperture)
+
+        let aperture_specs = PairSpec::parse_all(pairs::inner_aperture)?;
+        PairSpec::check_landmarks(&aperture_specs, &landmarks)?;
+        let aperture_results = EuclideanCalculator::euclidean(&umd_instance, &aperture_specs);
+        let inner_aperture = Aperture::from_euclidean(&aperture_results, "InnerPhiltrum", "InnerLowerVermillionBorder");
+        let closure_results = ClosureCalculator::detect(&inner_aperture, &ClosureOptions::default());
+        let file_name = "lip_closures.parquet";
+        let closure_output_path = format!("{output_path}{file_name}"); 
+        ClosureIntervals::save_closure_to_parquet(&closure_results, &closure_output_path).expect("Failed to write lip closures to parquet");
+
+        println!("Lip closures worked");
+
+
+
+        // Working space (whole recording as one trial)
+
+        let workspace_results = WorkspaceCalculator::calculate(&umd_instance, &trial_name, None);
+        let file_name = "working_space.parquet";
+        let workspace_output_path = format!("{output_path}{file_name}"); 
+        CoreWorkspace::save_workspace_to_parquet(&workspace_results, &workspace_output_path).expect("Failed to write working space to parquet");
+
+        println!("Working space worked");
+
+
+
+        // Events (closures and DDK peaks) as a TextGrid for Praat
+
+        let end_time = aperture.timestamp.last().copied().unwrap_or(0.0);
+        let mut events = TextGrid::new(0.0, end_time);
+        events.add_tier(Tier::Point(PointTier::from_closures("closures", &closure_results, 0.0, end_time)));
+        events.add_tier(Tier::Point(PointTier::from_ddk_cycles("ddk_peaks", &ddk_cycles, 0.0, end_time)));
+        let file_name = "events.TextGrid";
+        let events_output_path = format!("{output_path}{file_name}");
+        events.write(Path::new(&events_output_path), TextGridFormat::Long).expect("Failed to write events to TextGrid");
+
+        println!("Events worked");
+
+
+
+        // Speech (only when extract_audio has put the audio in the output folder), synced on lip aperture
+
+        let file_name = "audio.wav";
+        let wav_path = format!("{output_path}{file_name}");
+        let speech_umd = if Path::new(&wav_path).exists() {
+            Some(Self::speech_synced(&wav_path, output_path, SpeechBackend::Native, Some(&aperture), Some(&ddk_cycles)).expect("Failed to analyse and sync speech"))
+        } else {
+            None
+        };
+
+
+
+        // Segment summaries (only when there is a TextGrid / BAS Partitur with the same name as the input)
+
+        let annotation_path = ["TextGrid", "par"].iter()
+            .map(|extension| Path::new(input_path).with_extension(extension))
+            .find(|path| path.exists());
+        if let Some(annotation_path) = annotation_path {
+            let segments = SegmentImporter::import(&annotation_path)?;
+            let file_name = "segments.parquet";
+            let segments_output_path = format!("{output_path}{file_name}");
+            Segments::save_segments_to_parquet(&segments, &segments_output_path).expect("Failed to write segments to parquet");
+
+            let mut segment_measures: Vec<Signal> = measures::default_measures.iter()
+                .map(|measure| Signal::from_named(&named_results, measure.name))
+                .collect();
+            if let Some(speech_umd) = &speech_umd {
+                for feature in ["f0", "intensity", "f1", "f2", "f3", "f4", "hnr"] {
+                    segment_measures.push(Signal::from_speech(speech_umd, feature));
+                }
+            }
+
+            let segment_summary = SegmentSummaryCalculator::summarise(&trial_name, &segments, None, &segment_measures);
+            let file_name = "segment_summary.parquet";
+            let segment_summary_output_path = format!("{output_path}{file_name}");
+            SegmentSummary::save_segment_summary_to_parquet(&segment_summary, &segment_summary_output_path).expect("Failed to write segment summary to parquet");
+
+            println!("Segment summary worked");
+
+            // Vowel space (phone tiers only, so words aren't read as vowels)
+
+            if let Some(speech_umd) = &speech_umd {
+                let phone_tiers: &[&str] = &["phones", "MAU"];
+                let vowel_tokens = VowelSpaceCalculator::tokens(&trial_name, &segments, speech_umd, Some(phone_tiers), &VowelMap::default());
+                let vowel_space = VowelSpaceCalculator::calculate(&vowel_tokens);
+
+                let file_name = "vowel_tokens.parquet";
+                let vowel_tokens_output_path = format!("{output_path}{file_name}");
+                VowelTokens::save_vowel_tokens_to_parquet(&vowel_tokens, &vowel_tokens_output_path).expect("Failed to write vowel tokens to parquet");
+
+                let file_name = "vowel_space.parquet";
+                let vowel_space_output_path = format!("{output_path}{file_name}");
+                VowelSpace::save_vowel_space_to_parquet(&vowel_space, &vowel_space_output_path).expect("Failed to write vowel space to parquet");
+
+                println!("Vowel space worked");
+            }
+        }
+
+        Ok(())
+    }
+
+    // labels every row of a MOSAIC parquet table with the tiers of a TextGrid
+    pub fn annotate(input_path: &str, textgrid_path: &str, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let textgrid = TextGrid::read(Path::new(textgrid_path))?;
+        let tiers: Vec<&Tier> = textgrid.tiers.iter().collect();
+
+        let table = ParquetReader::new(File::open(input_path)?).finish()?;
+        let mut annotated = TierJoin::annotate(&table, &tiers)?;
+
+        let stem = Path::new(input_path).file_stem().and_then(|s| s.to_str()).unwrap_or("table");
+        let annotated_output_path = format!("{output_path}{stem}_annotated.parquet");
+        ParquetWriter::new(File::create(&annotated_output_path)?).finish(&mut annotated)?;
+        println!("Successfully exported annotated data to: {}", annotated_output_path);
+
+        Ok(())
+    }
+
+    // forced alignment (MFA TextGrid / BAS Partitur) or hand made annotation to the segment table
+    pub fn import_segments(input_path: &str, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let segments = SegmentImporter::import(Path::new(input_path))?;
+
+        let file_name = "segments.parquet";
+        let segments_output_path = format!("{output_path}{file_name}");
+        Segments::save_segments_to_parquet(&segments, &segments_output_path)?;
+        println!("Segments worked");
+
+        Ok(())
+    }
+
+    // audio track of the recorded video to a mono WAV (+ JSON sidecar with the offset to video frame 0)
+    pub fn extract_audio(input_path: &str, output_path: &str, sample_rate: f64) -> Result<(), Box<dyn std::error::Error>> {
+        let file_name = "audio.wav";
+        let wav_output_path = format!("{output_path}{file_name}");
+        let extracted = AudioExtractor::extract(input_path, &wav_output_path, sample_rate)?;
+        println!("Audio offset to video frame 0: {:.4} s", extracted.offset);
+
+        Ok(())
+    }
+
+    // acoustic analysis of a WAV file into the SpeechUMD
+    pub fn speech(input_path: &str, output_path: &str, backend: SpeechBackend) -> Result<(), Box<dyn std::error::Error>> {
+        Self::speech_synced(input_path, output_path, backend, None, None)?;
+        Ok(())
+    }
+
+    // SpeechUMD on the video clock: the container offset from the extraction sidecar (if there is one) and the
+    // residual lag / drift against a kinematic signal (if given). Speech rate is checked against the DDK cycles
+    // when they are given
+    pub fn speech_synced(
+        input_path: &str, output_path: &str, backend: SpeechBackend, kinematic: Option<&Signal>, ddk: Option<&DDKCycles>
+    ) -> Result<SpeechUMD, Box<dyn std::error::Error>> {
+        let parameters = SpeechParameters::default();
+        let mut speech_umd = SpeechExtractor::extract(input_path, backend, &parameters)?;
+
+        let sidecar = AudioExtractor::read_sidecar(input_path).ok();
+        let container_offset = sidecar.as_ref().map(|extracted| extracted.offset);
+        let correction = SyncCalculator::synchronise(&speech_umd, kinematic, container_offset, &SyncOptions::default());
+        correction.apply(&mut speech_umd);
+        println!("Audio sync ({}): offset {:.4} s, drift {:.6} s/s", correction.method, correction.offset, correction.drift);
+
+        let file_name = "speech_umd.parquet";
+        let speech_output_path = format!("{output_path}{file_name}");
+        SpeechUMD::save_speech_umd_to_parquet(&speech_umd, &speech_output_path)?;
+
+        let mut metadata = SpeechMetadata::new(input_path.to_string(), format!("{:?}", backend), &parameters, correction.clone());
+
+        // voice quality needs Praat's voice report, so only with parselmouth
+        if backend == SpeechBackend::Python {
+            let voice_parameters = VoiceParameters::default();
+            speech_umd.voice_quality = VoiceQualityExtractor::extract(input_path, &voice_parameters, None, None, &correction)?;
+            metadata.voice_parameters = voice_parameters.to_kv_vec();
+
+            let file_name = "voice_quality.parquet";
+            let voice_output_path = format!("{output_path}{file_name}");
+            VoiceQuality::save_voice_quality_to_parquet(&speech_umd.voice_quality, &voice_output_path)?;
+        }
+
+        let file_name = "speech_metadata.json";
+        let metadata_output_path = format!("{output_path}{file_name}");
+        metadata.save_speech_metadata(&metadata_output_path)?;
+        println!("SpeechUMD worked");
+
+        // speech rate and pauses (syllable nuclei). The trial is the video the audio came from (same name as the
+        // OpenFace output) when there is a sidecar
+        let source = sidecar.as_ref().map(|extracted| extracted.source.as_str()).unwrap_or(input_path);
+        let trial_name = Path::new(source).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let (nuclei, rate) = SpeechRateCalculator::calculate(&trial_name, &speech_umd, ddk, &SpeechRateOptions::default());
+
+        let file_name = "syllable_nuclei.parquet";
+        let nuclei_output_path = format!("{output_path}{file_name}");
+        SyllableNuclei::save_syllable_nuclei_to_parquet(&nuclei, &nuclei_output_path)?;
+
+        let file_name = "speech_rate.parquet";
+        let rate_output_path = format!("{output_path}{file_name}");
+        SpeechRateSummary::save_speech_rate_to_parquet(&rate, &rate_output_path)?;
+        println!("Speech rate worked");
+
+        Ok(speech_umd)
+    }
+
+    // jitter / shimmer / HNR / CPPS for the whole file and every segment of an annotation (TextGrid, BAS Partitur).
+    // Uses the sync correction already recorded in speech_metadata.json (run speech first for synced segments)
+    pub fn voice_quality(input_path: &str, output_path: &str, annotation_path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let voice_parameters = VoiceParameters::default();
+        let segments = match annotation_path {
+            Some(path) => Some(SegmentImporter::import(Path::new(path))?),
+            None => None,
+        };
+
+        let file_name = "speech_metadata.json";
+        let metadata_output_path = format!("{output_path}{file_name}");
+        let mut metadata = SpeechMetadata::read_speech_metadata(&metadata_output_path).unwrap_or_else(|_| {
+            SpeechMetadata::new(input_path.to_string(), format!("{:?}", SpeechBackend::Python), &SpeechParameters::default(), SyncCorrection::identity())
+        });
+
+        let voice_quality = VoiceQualityExtractor::extract(input_path, &voice_parameters, segments.as_ref(), None, &metadata.sync)?;
+        let file_name = "voice_quality.parquet";
+        let voice_output_path = format!("{output_path}{file_name}");
+        VoiceQuality::save_voice_quality_to_parquet(&voice_quality, &voice_output_path)?;
+
+        metadata.voice_parameters = voice_parameters.to_kv_vec();
+        metadata.save_speech_metadata(&metadata_output_path)?;
+        println!("Voice quality worked");
+
+        Ok(())
+    }
+
+    // validates the native backend against parselmouth on the same file
+    pub fn compare_speech_backends(input_path: &str, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let parameters = SpeechParameters::default();
+        let reference = SpeechExtractor::extract(input_path, SpeechBackend::Python, &parameters)?;
+        let candidate = SpeechExtractor::extract(input_path, SpeechBackend::Native, &parameters)?;
+
+        let comparison = SpeechExtractor::compare(&reference, &candidate);
+        let file_name = "speech_backend_comparison.parquet";
+        let comparison_output_path = format!("{output_path}{file_name}");
+        BackendComparison::save_backend_comparison_to_parquet(&comparison, &comparison_output_path)?;
+        println!("Backend comparison worked");
+
         Ok(())
     }
 
@@ -0,0 +1,320 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Segments
+
+    Common segment table for annotations from any source (hand made TextGrids, MFA, WebMAUS) so kinematic and
+    acoustic measures can be summarised per phone / word in the same way:
+
+        segment_id, tier, label, start, end
+
+    `segment_id` runs from 1 over the whole table. Point tier marks become segments with start == end.
+
+    Importers:
+        - TextGrid: every tier as is (empty intervals, i.e. unlabelled gaps, are dropped)
+        - MFA TextGrid: as above, with the speaker prefix of multi speaker alignments ("spk1 - phones") removed.
+          `import` only does this when every tier follows MFA's words / phones naming
+        - BAS Partitur (.par): MAU (and WOR when present) are timed in samples, ORT / KAN are timed from the MAU
+          phones of each word
+*/
+
+use crate::annotation::textgrid::textgrid::{TextGrid, Tier};
+use crate::errors::{AnnotationError, MosaicError};
+use polars::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct Segments {
+    pub segment_id: Vec<u32>,
+    pub tier: Vec<String>,
+    pub label: Vec<String>,
+    pub start: Vec<f64>, // s
+    pub end: Vec<f64>,
+}
+
+impl Segments {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            segment_id: Vec::with_capacity(estimated_entries),
+            tier: Vec::with_capacity(estimated_entries),
+            label: Vec::with_capacity(estimated_entries),
+            start: Vec::with_capacity(estimated_entries),
+            end: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(&mut self, tier: String, label: String, start: f64, end: f64) {
+        self.segment_id.push(self.segment_id.len() as u32 + 1);
+        self.tier.push(tier);
+        self.label.push(label);
+        self.start.push(start);
+        self.end.push(end);
+    }
+
+    pub fn len(&self) -> usize {
+        self.segment_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segment_id.is_empty()
+    }
+
+    // indices of the segments of one tier
+    pub fn tier_indices(&self, tier: &str) -> Vec<usize> {
+        (0..self.len()).filter(|&k| self.tier[k] == tier).collect()
+    }
+
+    pub fn save_segments_to_parquet(data: &Segments, file_path: &str) -> PolarsResult<()> {
+        let s_id = Series::new("segment_id", &data.segment_id);
+        let s_tier = Series::new("tier", &data.tier);
+        let s_label = Series::new("label", &data.label);
+        let s_start = Series::new("start", &data.start);
+        let s_end = Series::new("end", &data.end);
+
+        let mut df = DataFrame::new(vec![
+            s_id, s_tier, s_label, s_start, s_end,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported segment data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct SegmentImporter;
+
+impl SegmentImporter {
+    // by extension: .par (BAS Partitur), anything else is read as a TextGrid. Tier names are kept as they are
+    // unless the tiers are MFA's words / phones layout
+    pub fn import(path: &Path) -> Result<Segments, MosaicError> {
+        let is_partitur = path.extension().and_then(|e| e.to_str())
+            .map(|e| e.eq_ignore_ascii_case("par"))
+            .unwrap_or(false);
+        if is_partitur { return Self::from_partitur_file(path); }
+
+        let textgrid = TextGrid::read(path)?;
+        Ok(if Self::is_mfa(&textgrid) { Self::from_mfa(&textgrid) } else { Self::from_textgrid(&textgrid) })
+    }
+
+    pub fn from_textgrid(textgrid: &TextGrid) -> Segments {
+        Self::tiers(textgrid, |name| name.to_string())
+    }
+
+    // MFA writes "words" / "phones", or "<speaker> - words" / "<speaker> - phones" for multi speaker corpora
+    pub fn from_mfa(textgrid: &TextGrid) -> Segments {
+        Self::tiers(textgrid, |name| match name.rsplit_once(" - ") {
+            Some((_, tier)) => tier.to_string(),
+            None => name.to_string(),
+        })
+    }
+
+    // every tier is an interval tier called "words" / "phones" or "<speaker> - words" / "<speaker> - phones"
+    pub fn is_mfa(textgrid: &TextGrid) -> bool {
+        !textgrid.tiers.is_empty() && textgrid.tiers.iter().all(|tier| {
+            let name = tier.name().rsplit_once(" - ").map(|(_, t)| t).unwrap_or(tier.name());
+            matches!(tier, Tier::Interval(_)) && (name == "words" || name == "phones")
+        })
+    }
+
+    fn tiers(textgrid: &TextGrid, tier_name: impl Fn(&str) -> String) -> Segments {
+        let mut segments = Segments::construction(0);
+        for tier in &textgrid.tiers {
+            let name = tier_name(tier.name());
+            match tier {
+                Tier::Interval(t) => {
+                    for interval in t.intervals.iter().filter(|i| !i.text.trim().is_empty()) {
+                        segments.add_point(name.clone(), interval.text.clone(), interval.xmin, interval.xmax);
+                    }
+                }
+                Tier::Point(t) => {
+                    for point in &t.points {
+                        segments.add_point(name.clone(), point.mark.clone(), point.time, point.time);
+                    }
+                }
+            }
+        }
+        segments
+    }
+
+    pub fn from_partitur_file(path: &Path) -> Result<Segments, MosaicError> {
+        let bytes = fs::read(path)?;
+        Self::from_partitur(&TextGrid::decode(&bytes))
+    }
+
+    pub fn from_partitur(text: &str) -> Result<Segments, MosaicError> {
+        let mut sample_rate: Option<f64> = None;
+        let mut words: BTreeMap<&str, BTreeMap<i64, String>> = BTreeMap::new(); // ORT / KAN: word index -> label
+        let mut timed: BTreeMap<&str, Vec<(f64, f64, i64, String)>> = BTreeMap::new(); // MAU / WOR: (start, end, word, label)
+
+        for (number, line) in text.lines().enumerate() {
+            let malformed = || AnnotationError::MalformedPartitur(format!("line {}: '{}'", number + 1, line));
+            let Some((key, rest)) = line.split_once(':') else { continue; };
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+
+            match key {
+                "SAM" => sample_rate = Some(fields.first().and_then(|f| f.parse().ok()).ok_or_else(malformed)?),
+                "ORT" | "KAN" => {
+                    if fields.len() < 2 { return Err(malformed().into()); }
+                    let word = fields[0].parse::<i64>().map_err(|_| malformed())?;
+                    words.entry(key).or_default().insert(word, fields[1..].join(" "));
+                }
+                "MAU" | "WOR" => {
+                    let sample_rate = sample_rate.ok_or(AnnotationError::MissingSampleRate)?;
+                    if fields.len() < 4 { return Err(malformed().into()); }
+                    let begin = fields[0].parse::<f64>().map_err(|_| malformed())?;
+                    let duration = fields[1].parse::<f64>().map_err(|_| malformed())?;
+                    // several word links ("2,3") are attributed to the first
+                    let word = fields[2].split(',').next().and_then(|w| w.parse::<i64>().ok()).ok_or_else(malformed)?;
+                    // a segment covers samples begin ..= begin + duration
+                    timed.entry(key).or_default().push((
+                        begin / sample_rate,
+                        (begin + duration + 1.0) / sample_rate,
+                        word,
+                        fields[3..].join(" "),
+                    ));
+                }
+                _ => {} // header and tiers we don't use
+            }
+        }
+
+        let mut segments = Segments::construction(0);
+
+        // word tiers take their times from the phones (pauses have word index -1)
+        let phones = timed.get("MAU");
+        for tier in ["ORT", "KAN"] {
+            let (Some(labels), Some(phones)) = (words.get(tier), phones) else { continue; };
+            for (word, label) in labels {
+                let spans: Vec<&(f64, f64, i64, String)> = phones.iter().filter(|p| p.2 == *word).collect();
+                if spans.is_empty() { continue; } // word without phones (i.e. not aligned)
+                let start = spans.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+                let end = spans.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+                segments.add_point(tier.to_string(), label.clone(), start, end);
+            }
+        }
+
+        for tier in ["WOR", "MAU"] {
+            let Some(entries) = timed.get(tier) else { continue; };
+            let mut entries = entries.clone();
+            entries.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            for (start, end, _, label) in entries {
+                segments.add_point(tier.to_string(), label, start, end);
+            }
+        }
+
+        Ok(segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotation::textgrid::textgrid::{Interval, IntervalTier, PointTier};
+
+    // 16 kHz: a pause, "good day it's so" and a pause. The /s/ is linked to words 2 and 3
+    const PARTITUR: &str = "LHD: Partitur 1.3
+SAM: 16000
+ORT: 0 good
+ORT: 1 day
+ORT: 2 it's
+ORT: 3 so
+KAN: 0 gUd
+KAN: 1 deI
+KAN: 4 unaligned
+MAU: 0 1599 -1 <p:>
+MAU: 1600 799 0 g
+MAU: 2400 1599 0 Ud
+MAU: 4000 1599 1 deI
+MAU: 5600 799 2,3 s
+MAU: 6400 1599 3 @U
+MAU: 8000 1599 -1 <p:>
+";
+
+    fn rows(segments: &Segments, tier: &str) -> Vec<(String, f64, f64)> {
+        segments.tier_indices(tier).into_iter()
+            .map(|k| (segments.label[k].clone(), segments.start[k], segments.end[k]))
+            .collect()
+    }
+
+    fn assert_rows(actual: &[(String, f64, f64)], expected: &[(&str, f64, f64)]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for ((label, start, end), (expected_label, expected_start, expected_end)) in actual.iter().zip(expected) {
+            assert_eq!(label, expected_label);
+            assert!((start - expected_start).abs() < 1e-12 && (end - expected_end).abs() < 1e-12, "{:?}", actual);
+        }
+    }
+
+    #[test]
+    fn partitur_phones_cover_begin_to_begin_plus_duration() {
+        let segments = SegmentImporter::from_partitur(PARTITUR).unwrap();
+        // (begin + duration + 1) / SAM: consecutive phones share their boundaries
+        assert_rows(&rows(&segments, "MAU"), &[
+            ("<p:>", 0.0, 0.1), ("g", 0.1, 0.15), ("Ud", 0.15, 0.25), ("deI", 0.25, 0.35),
+            ("s", 0.35, 0.4), ("@U", 0.4, 0.5), ("<p:>", 0.5, 0.6),
+        ]);
+    }
+
+    #[test]
+    fn partitur_words_are_timed_from_their_phones() {
+        let segments = SegmentImporter::from_partitur(PARTITUR).unwrap();
+        // the shared /s/ belongs to "it's" only, and the pauses (word -1) to no word
+        assert_rows(&rows(&segments, "ORT"), &[
+            ("good", 0.1, 0.25), ("day", 0.25, 0.35), ("it's", 0.35, 0.4), ("so", 0.4, 0.5),
+        ]);
+        // words without phones are dropped
+        assert_rows(&rows(&segments, "KAN"), &[("gUd", 0.1, 0.25), ("deI", 0.25, 0.35)]);
+        assert_eq!(segments.segment_id, (1..=segments.len() as u32).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn partitur_needs_the_sample_rate_before_timed_tiers() {
+        assert!(SegmentImporter::from_partitur("MAU: 0 1599 -1 <p:>\nSAM: 16000\n").is_err());
+    }
+
+    fn interval_tier(name: &str) -> Tier {
+        Tier::Interval(IntervalTier {
+            name: name.to_string(), xmin: 0.0, xmax: 1.0,
+            intervals: vec![
+                Interval { xmin: 0.0, xmax: 0.4, text: "a".to_string() },
+                Interval { xmin: 0.4, xmax: 1.0, text: String::new() },
+            ],
+        })
+    }
+
+    #[test]
+    fn mfa_speaker_prefixes_are_recognised_and_removed() {
+        let mut textgrid = TextGrid::new(0.0, 1.0);
+        textgrid.add_tier(interval_tier("spk1 - words"));
+        textgrid.add_tier(interval_tier("spk1 - phones"));
+        assert!(SegmentImporter::is_mfa(&textgrid));
+
+        let segments = SegmentImporter::from_mfa(&textgrid);
+        assert_eq!(segments.tier, vec!["words".to_string(), "phones".to_string()]); // empty intervals dropped
+        assert_eq!(SegmentImporter::from_textgrid(&textgrid).tier[0], "spk1 - words");
+
+        let mut other = textgrid.clone();
+        other.add_tier(interval_tier("notes"));
+        assert!(!SegmentImporter::is_mfa(&other));
+
+        let mut points = TextGrid::new(0.0, 1.0);
+        points.add_tier(Tier::Point(PointTier::new("spk1 - words", 0.0, 1.0)));
+        assert!(!SegmentImporter::is_mfa(&points));
+        assert!(!SegmentImporter::is_mfa(&TextGrid::new(0.0, 1.0)));
+    }
+}
@@ -0,0 +1,87 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Tier join
+
+    Labels every row of a MOSAIC table (UMD, CoreArea, CoreEuclidean, SpeechUMD, ...) with the TextGrid tiers by
+    its `timestamp` column, so each frame gets its phone / word / syllable.
+
+        - interval tiers: the text of the interval containing the timestamp (null outside the tier)
+        - point tiers: the mark on the rows at the timestamp nearest to the point (null elsewhere, several marks
+          on the same timestamp are joined with ",")
+
+    Timestamps have to be on the same clock as the TextGrid (see `praatAnalysis::sync` for the audio).
+*/
+
+use crate::annotation::textgrid::textgrid::{Tier};
+use polars::prelude::*;
+
+pub struct TierJoin;
+
+impl TierJoin {
+    pub fn labels(tier: &Tier, timestamps: &[f64]) -> Vec<Option<String>> {
+        match tier {
+            Tier::Interval(tier) => timestamps.iter().map(|&t| tier.label_at(t).map(|label| label.to_string())).collect(),
+            Tier::Point(tier) => {
+                let mut unique: Vec<f64> = timestamps.iter().copied().filter(|t| !t.is_nan()).collect();
+                unique.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                unique.dedup();
+                if unique.is_empty() { return vec![None; timestamps.len()]; }
+
+                let mut marks: Vec<Option<String>> = vec![None; unique.len()];
+                for point in &tier.points {
+                    let k = Self::nearest(&unique, point.time);
+                    marks[k] = Some(match marks[k].take() {
+                        Some(existing) => format!("{},{}", existing, point.mark),
+                        None => point.mark.clone(),
+                    });
+                }
+
+                timestamps.iter().map(|&t| {
+                    if t.is_nan() { return None; }
+                    let k = unique.partition_point(|&u| u < t);
+                    marks[k].clone()
+                }).collect()
+            }
+        }
+    }
+
+    // index of the value in `sorted` nearest to t
+    fn nearest(sorted: &[f64], t: f64) -> usize {
+        let k = sorted.partition_point(|&u| u < t);
+        if k == 0 { return 0; }
+        if k == sorted.len() { return k - 1; }
+        if t - sorted[k - 1] <= sorted[k] - t { k - 1 } else { k }
+    }
+
+    // one string column per tier (named after the tier, "<tier>_label" if the table already has that column)
+    pub fn annotate(df: &DataFrame, tiers: &[&Tier]) -> PolarsResult<DataFrame> {
+        let timestamp = df.column("timestamp")?.cast(&DataType::Float64)?;
+        let timestamps: Vec<f64> = timestamp.f64()?.into_iter().map(|t| t.unwrap_or(f64::NAN)).collect();
+
+        let mut annotated = df.clone();
+        for tier in tiers {
+            let labels = Self::labels(tier, &timestamps);
+            let name = if annotated.get_column_names().contains(&tier.name()) {
+                format!("{}_label", tier.name())
+            } else {
+                tier.name().to_string()
+            };
+            annotated.with_column(Series::new(&name, labels))?;
+        }
+        Ok(annotated)
+    }
+}
@@ -0,0 +1,513 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    TextGrid
+
+    Praat TextGrid reader / writer. Reads the long ("ooTextFile" as Praat saves it) and short text formats in
+    UTF-8 or UTF-16 (with a byte order mark, as Praat writes any file with non ASCII labels).
+
+    Both formats hold the same sequence of values, the long one just labels them (`xmin = 0`, `intervals [1]:`),
+    so the reader does what Praat does: tokenises the file into quoted strings, numbers and `<flags>`, skips
+    everything else and reads the values in order.
+
+    Writing is UTF-8 (Praat reads it fine) in either format.
+*/
+
+use crate::errors::{AnnotationError, MosaicError};
+use crate::complexMeasurements::closure::closure::{ClosureIntervals};
+use crate::complexMeasurements::ddk::ddk::{DDKCycles};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval {
+    pub xmin: f64, // s
+    pub xmax: f64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    pub time: f64, // s
+    pub mark: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalTier {
+    pub name: String,
+    pub xmin: f64,
+    pub xmax: f64,
+    pub intervals: Vec<Interval>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointTier {
+    pub name: String,
+    pub xmin: f64,
+    pub xmax: f64,
+    pub points: Vec<Point>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tier {
+    Interval(IntervalTier),
+    Point(PointTier),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextGridFormat {
+    Long,
+    Short,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextGrid {
+    pub xmin: f64,
+    pub xmax: f64,
+    pub tiers: Vec<Tier>,
+}
+
+impl Tier {
+    pub fn name(&self) -> &str {
+        match self {
+            Tier::Interval(tier) => &tier.name,
+            Tier::Point(tier) => &tier.name,
+        }
+    }
+
+    fn bounds(&self) -> (f64, f64) {
+        match self {
+            Tier::Interval(tier) => (tier.xmin, tier.xmax),
+            Tier::Point(tier) => (tier.xmin, tier.xmax),
+        }
+    }
+}
+
+impl IntervalTier {
+    // text of the interval containing t. Intervals are [xmin, xmax) except the last one which includes xmax
+    pub fn label_at(&self, t: f64) -> Option<&str> {
+        let k = self.intervals.partition_point(|interval| interval.xmax <= t);
+        if k < self.intervals.len() {
+            let interval = &self.intervals[k];
+            if t >= interval.xmin { return Some(&interval.text); }
+        } else if let Some(last) = self.intervals.last() && t == last.xmax {
+            return Some(&last.text);
+        }
+        None
+    }
+}
+
+impl PointTier {
+    pub fn new(name: &str, xmin: f64, xmax: f64) -> Self {
+        Self { name: name.to_string(), xmin, xmax, points: Vec::new() }
+    }
+
+    // points are kept in time order (Praat requires it)
+    pub fn add_point(&mut self, time: f64, mark: String) {
+        let k = self.points.partition_point(|p| p.time <= time);
+        self.points.insert(k, Point { time, mark });
+        self.xmin = self.xmin.min(time);
+        self.xmax = self.xmax.max(time);
+    }
+
+    // closure onsets / offsets as "<signal>_closure_onset" / "<signal>_closure_offset" points
+    pub fn from_closures(name: &str, closures: &ClosureIntervals, xmin: f64, xmax: f64) -> Self {
+        let mut tier = Self::new(name, xmin, xmax);
+        for k in 0..closures.onset.len() {
+            tier.add_point(closures.onset[k], format!("{}_closure_onset", closures.signal[k]));
+            tier.add_point(closures.offset[k], format!("{}_closure_offset", closures.signal[k]));
+        }
+        tier
+    }
+
+    // DDK opening peaks as "<signal>_peak_<cycle>" points
+    pub fn from_ddk_cycles(name: &str, cycles: &DDKCycles, xmin: f64, xmax: f64) -> Self {
+        let mut tier = Self::new(name, xmin, xmax);
+        for k in 0..cycles.peak_time.len() {
+            tier.add_point(cycles.peak_time[k], format!("{}_peak_{}", cycles.signal[k], cycles.cycle[k]));
+        }
+        tier
+    }
+}
+
+impl TextGrid {
+    pub fn new(xmin: f64, xmax: f64) -> Self {
+        Self { xmin, xmax, tiers: Vec::new() }
+    }
+
+    pub fn add_tier(&mut self, tier: Tier) {
+        let (xmin, xmax) = tier.bounds();
+        self.xmin = self.xmin.min(xmin);
+        self.xmax = self.xmax.max(xmax);
+        self.tiers.push(tier);
+    }
+
+    pub fn tier(&self, name: &str) -> Result<&Tier, MosaicError> {
+        self.tiers.iter().find(|tier| tier.name() == name)
+            .ok_or_else(|| AnnotationError::MissingTier(name.to_string()).into())
+    }
+
+    pub fn interval_tier(&self, name: &str) -> Result<&IntervalTier, MosaicError> {
+        match self.tier(name)? {
+            Tier::Interval(tier) => Ok(tier),
+            Tier::Point(_) => Err(AnnotationError::WrongTierClass(name.to_string()).into()),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<TextGrid, MosaicError> {
+        let bytes = fs::read(path)?;
+        Self::parse(&Self::decode(&bytes))
+    }
+
+    // UTF-16 (either byte order, with or without BOM) or UTF-8 (BOM stripped)
+    pub fn decode(bytes: &[u8]) -> String {
+        let utf16 = |big_endian: bool, body: &[u8]| -> String {
+            let units: Vec<u16> = body.chunks_exact(2)
+                .map(|c| if big_endian { u16::from_be_bytes([c[0], c[1]]) } else { u16::from_le_bytes([c[0], c[1]]) })
+                .collect();
+            String::from_utf16_lossy(&units)
+        };
+
+        match bytes {
+            [0xFF, 0xFE, body @ ..] => utf16(false, body),
+            [0xFE, 0xFF, body @ ..] => utf16(true, body),
+            [0xEF, 0xBB, 0xBF, body @ ..] => String::from_utf8_lossy(body).into_owned(),
+            // no BOM: ASCII text in UTF-16 has every other byte 0
+            [a, 0, ..] if *a != 0 => utf16(false, bytes),
+            [0, b, ..] if *b != 0 => utf16(true, bytes),
+            _ => String::from_utf8_lossy(bytes).into_owned(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<TextGrid, MosaicError> {
+        let mut tokens = Tokens::new(text);
+
+        let file_type = tokens.string()?;
+        let object_class = tokens.string()?;
+        if file_type != "ooTextFile" || object_class != "TextGrid" {
+            return Err(AnnotationError::MalformedTextGrid(
+                format!("expected an ooTextFile TextGrid, found '{}' '{}'", file_type, object_class)
+            ).into());
+        }
+
+        let xmin = tokens.number()?;
+        let xmax = tokens.number()?;
+        let mut textgrid = TextGrid::new(xmin, xmax);
+        if !tokens.flag()? { return Ok(textgrid); } // <absent>: no tiers
+
+        let size = tokens.count()?;
+        for _ in 0..size {
+            let class = tokens.string()?;
+            let name = tokens.string()?;
+            let tier_xmin = tokens.number()?;
+            let tier_xmax = tokens.number()?;
+            let n = tokens.count()?;
+
+            let tier = match class.as_str() {
+                "IntervalTier" => {
+                    let mut intervals = Vec::with_capacity(n);
+                    for _ in 0..n {
+                        let xmin = tokens.number()?;
+                        let xmax = tokens.number()?;
+                        let text = tokens.string()?;
+                        intervals.push(Interval { xmin, xmax, text });
+                    }
+                    Tier::Interval(IntervalTier { name, xmin: tier_xmin, xmax: tier_xmax, intervals })
+                }
+                "TextTier" => {
+                    let mut points = Vec::with_capacity(n);
+                    for _ in 0..n {
+                        let time = tokens.number()?;
+                        let mark = tokens.string()?;
+                        points.push(Point { time, mark });
+                    }
+                    Tier::Point(PointTier { name, xmin: tier_xmin, xmax: tier_xmax, points })
+                }
+                _ => return Err(AnnotationError::UnknownTierClass(class).into()),
+            };
+            textgrid.tiers.push(tier);
+        }
+
+        Ok(textgrid)
+    }
+
+    pub fn write(&self, path: &Path, format: TextGridFormat) -> Result<(), MosaicError> {
+        fs::write(path, self.to_text(format))?;
+        println!("Successfully exported TextGrid to: {}", path.display());
+        Ok(())
+    }
+
+    pub fn to_text(&self, format: TextGridFormat) -> String {
+        let mut out = String::new();
+        let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+        let mut line = |long: String, short: String| {
+            out.push_str(if format == TextGridFormat::Long { &long } else { &short });
+            out.push('\n');
+        };
+
+        line("File type = \"ooTextFile\"".to_string(), "File type = \"ooTextFile\"".to_string());
+        line("Object class = \"TextGrid\"".to_string(), "Object class = \"TextGrid\"".to_string());
+        line(String::new(), String::new());
+        line(format!("xmin = {} ", self.xmin), format!("{}", self.xmin));
+        line(format!("xmax = {} ", self.xmax), format!("{}", self.xmax));
+        if self.tiers.is_empty() {
+            line("tiers? <absent> ".to_string(), "<absent>".to_string());
+            return out;
+        }
+        line("tiers? <exists> ".to_string(), "<exists>".to_string());
+        line(format!("size = {} ", self.tiers.len()), format!("{}", self.tiers.len()));
+        if format == TextGridFormat::Long { line("item []: ".to_string(), String::new()); }
+
+        for (k, tier) in self.tiers.iter().enumerate() {
+            let (class, count) = match tier {
+                Tier::Interval(t) => ("IntervalTier", t.intervals.len()),
+                Tier::Point(t) => ("TextTier", t.points.len()),
+            };
+            let (xmin, xmax) = tier.bounds();
+            line(format!("    item [{}]:", k + 1), String::new());
+            line(format!("        class = {} ", quote(class)), quote(class));
+            line(format!("        name = {} ", quote(tier.name())), quote(tier.name()));
+            line(format!("        xmin = {} ", xmin), format!("{}", xmin));
+            line(format!("        xmax = {} ", xmax), format!("{}", xmax));
+
+            match tier {
+                Tier::Interval(t) => {
+                    line(format!("        intervals: size = {} ", count), format!("{}", count));
+                    for (i, interval) in t.intervals.iter().enumerate() {
+                        line(format!("        intervals [{}]:", i + 1), String::new());
+                        line(format!("            xmin = {} ", interval.xmin), format!("{}", interval.xmin));
+                        line(format!("            xmax = {} ", interval.xmax), format!("{}", interval.xmax));
+                        line(format!("            text = {} ", quote(&interval.text)), quote(&interval.text));
+                    }
+                }
+                Tier::Point(t) => {
+                    line(format!("        points: size = {} ", count), format!("{}", count));
+                    for (i, point) in t.points.iter().enumerate() {
+                        line(format!("        points [{}]:", i + 1), String::new());
+                        line(format!("            number = {} ", point.time), format!("{}", point.time));
+                        line(format!("            mark = {} ", quote(&point.mark)), quote(&point.mark));
+                    }
+                }
+            }
+        }
+
+        // the short format has no labels so the label only lines are dropped
+        if format == TextGridFormat::Short {
+            out = out.lines().filter(|l| !l.is_empty()).map(|l| format!("{}\n", l)).collect();
+        }
+        out
+    }
+}
+
+// values of a Praat text file in order. Labels (`xmin =`, `item [1]:`, `intervals: size =`) and `!` comments are
+// skipped
+struct Tokens<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+enum Token {
+    Text(String),
+    Number(f64),
+    Flag(String),
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { chars: text.chars().peekable() }
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        loop {
+            let c = *self.chars.peek()?;
+            match c {
+                _ if c.is_whitespace() => { self.chars.next(); }
+                '!' => { for c in self.chars.by_ref() { if c == '\n' { break; } } }
+                '[' => { for c in self.chars.by_ref() { if c == ']' { break; } } }
+                '"' => {
+                    self.chars.next();
+                    let mut text = String::new();
+                    while let Some(c) = self.chars.next() {
+                        if c == '"' {
+                            // "" is an escaped quote
+                            if self.chars.peek() == Some(&'"') { self.chars.next(); text.push('"'); } else { break; }
+                        } else {
+                            text.push(c);
+                        }
+                    }
+                    return Some(Token::Text(text));
+                }
+                '<' => {
+                    let mut flag = String::new();
+                    for c in self.chars.by_ref() {
+                        if c == '>' { break; }
+                        if c != '<' { flag.push(c); }
+                    }
+                    return Some(Token::Flag(flag));
+                }
+                _ => {
+                    let mut word = String::new();
+                    while let Some(&c) = self.chars.peek() {
+                        if c.is_whitespace() || c == '"' || c == '!' { break; }
+                        word.push(c);
+                        self.chars.next();
+                    }
+                    if let Ok(number) = word.parse::<f64>() { return Some(Token::Number(number)); }
+                    // anything else is a label
+                }
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, MosaicError> {
+        match self.next_token() {
+            Some(Token::Text(text)) => Ok(text),
+            Some(_) => Err(AnnotationError::MalformedTextGrid("expected a quoted string".to_string()).into()),
+            None => Err(AnnotationError::UnexpectedEndOfFile.into()),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, MosaicError> {
+        match self.next_token() {
+            Some(Token::Number(number)) => Ok(number),
+            Some(_) => Err(AnnotationError::MalformedTextGrid("expected a number".to_string()).into()),
+            None => Err(AnnotationError::UnexpectedEndOfFile.into()),
+        }
+    }
+
+    fn count(&mut self) -> Result<usize, MosaicError> {
+        let number = self.number()?;
+        if number < 0.0 || number.fract() != 0.0 {
+            return Err(AnnotationError::MalformedTextGrid(format!("invalid count {}", number)).into());
+        }
+        Ok(number as usize)
+    }
+
+    fn flag(&mut self) -> Result<bool, MosaicError> {
+        match self.next_token() {
+            Some(Token::Flag(flag)) => Ok(flag == "exists"),
+            Some(_) => Err(AnnotationError::MalformedTextGrid("expected <exists> or <absent>".to_string()).into()),
+            None => Err(AnnotationError::UnexpectedEndOfFile.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the same TextGrid as Praat writes it in both formats (escaped quotes and a non ASCII label)
+    const LONG: &str = "File type = \"ooTextFile\"
+Object class = \"TextGrid\"
+
+xmin = 0 
+xmax = 1.5 
+tiers? <exists> 
+size = 2 
+item []: 
+    item [1]:
+        class = \"IntervalTier\" 
+        name = \"words\" 
+        xmin = 0 
+        xmax = 1.5 
+        intervals: size = 2 
+        intervals [1]:
+            xmin = 0 
+            xmax = 0.7 
+            text = \"say \"\"\u{283}ip\"\"\" 
+        intervals [2]:
+            xmin = 0.7 
+            xmax = 1.5 
+            text = \"\" 
+    item [2]:
+        class = \"TextTier\" 
+        name = \"events\" 
+        xmin = 0 
+        xmax = 1.5 
+        points: size = 1 
+        points [1]:
+            number = 0.25 
+            mark = \"peak\" 
+";
+
+    const SHORT: &str = "File type = \"ooTextFile\"
+Object class = \"TextGrid\"
+
+0
+1.5
+<exists>
+2
+\"IntervalTier\"
+\"words\"
+0
+1.5
+2
+0
+0.7
+\"say \"\"\u{283}ip\"\"\"
+0.7
+1.5
+\"\"
+\"TextTier\"
+\"events\"
+0
+1.5
+1
+0.25
+\"peak\"
+";
+
+    fn expected() -> TextGrid {
+        let mut textgrid = TextGrid::new(0.0, 1.5);
+        textgrid.add_tier(Tier::Interval(IntervalTier {
+            name: "words".to_string(), xmin: 0.0, xmax: 1.5,
+            intervals: vec![
+                Interval { xmin: 0.0, xmax: 0.7, text: "say \"\u{283}ip\"".to_string() },
+                Interval { xmin: 0.7, xmax: 1.5, text: String::new() },
+            ],
+        }));
+        let mut events = PointTier::new("events", 0.0, 1.5);
+        events.add_point(0.25, "peak".to_string());
+        textgrid.add_tier(Tier::Point(events));
+        textgrid
+    }
+
+    #[test]
+    fn long_and_short_formats_parse_to_the_same_textgrid() {
+        let long = TextGrid::parse(LONG).unwrap();
+        let short = TextGrid::parse(SHORT).unwrap();
+        assert_eq!(long, expected());
+        assert_eq!(short, long);
+        assert_eq!(long.interval_tier("words").unwrap().label_at(0.2), Some("say \"\u{283}ip\""));
+        assert!(long.interval_tier("events").is_err());
+    }
+
+    #[test]
+    fn utf16le_with_bom_is_decoded() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(LONG.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        assert_eq!(TextGrid::decode(&bytes), LONG);
+        assert_eq!(TextGrid::parse(&TextGrid::decode(&bytes)).unwrap(), expected());
+    }
+
+    #[test]
+    fn written_textgrids_parse_back_unchanged() {
+        for format in [TextGridFormat::Long, TextGridFormat::Short] {
+            let text = expected().to_text(format);
+            assert_eq!(TextGrid::parse(&text).unwrap(), expected(), "{:?}", format);
+        }
+        assert_eq!(expected().to_text(TextGridFormat::Long), LONG);
+    }
+}
@@ -0,0 +1,307 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Closure
+
+    Lip closure (bilabial contact) detection for /p b m/ from the inner lip aperture
+    (InnerPhiltrum -> InnerLowerVermillionBorder from the euclidean calculator) or the inner lip area.
+
+    The threshold is estimated per speaker (per recording) from the rest (closed) part of the aperture distribution:
+
+        1. Otsu's split between the closed and open modes of the aperture
+        2. rest level = median of the values below the split, rest spread = 1.4826 * MAD of those values
+        3. threshold = rest level + k * rest spread (never above the split)
+
+    Each frame then gets the probability that the true aperture is below the threshold using its own uncertainty
+    (r_uncertainty / total_area_uncertainty), and closures run from where that probability reaches
+    `onset_probability` until it drops below `offset_probability`. With no uncertainty this is just aperture <
+    threshold.
+*/
+
+use crate::complexMeasurements::signal::signal::{Signal};
+use crate::coreMeasurements::euclidean::euclidean::{CoreEuclidean};
+use crate::coreMeasurements::area::area::{CoreArea};
+use polars::prelude::*;
+use std::fs::File;
+
+// aperture signal with the uncertainty of each sample
+#[derive(Debug, Clone)]
+pub struct Aperture {
+    pub signal: Signal,
+    pub uncertainty: Vec<f64>,
+}
+
+impl Aperture {
+    pub fn from_euclidean(data: &CoreEuclidean, point_1: &str, point_2: &str) -> Self {
+        let mut aperture = Self { signal: Signal::new(format!("{}->{}", point_1, point_2)), uncertainty: Vec::new() };
+        for i in 0..data.frame.len() {
+            if data.coordinate_type_1[i] == point_1 && data.coordinate_type_2[i] == point_2 {
+                aperture.push(data.frame[i], data.timestamp[i] as f64, data.r[i], data.r_uncertainty[i]);
+            }
+        }
+        aperture
+    }
+
+    pub fn from_area(data: &CoreArea, types_included: &str) -> Self {
+        let mut aperture = Self { signal: Signal::new(format!("area({})", types_included)), uncertainty: Vec::new() };
+        for i in 0..data.frame.len() {
+            if data.types_included[i] == types_included && data.status[i] == "ok" {
+                aperture.push(data.frame[i], data.timestamp[i] as f64, data.total_area[i], data.total_area_uncertainty[i]);
+            }
+        }
+        aperture
+    }
+
+    fn push(&mut self, frame: u32, timestamp: f64, value: f64, uncertainty: f64) {
+        if value.is_nan() { return; }
+        self.signal.push(frame, timestamp, value);
+        self.uncertainty.push(if uncertainty.is_finite() { uncertainty.abs() } else { 0.0 });
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClosureOptions {
+    pub rest_spread: f64, // k (rest spreads above the rest level)
+    pub onset_probability: f64,
+    pub offset_probability: f64,
+    pub min_duration: f64, // s, shorter closures are dropped
+    pub max_gap: f64, // s, closures separated by less than this are merged
+}
+
+impl Default for ClosureOptions {
+    fn default() -> Self {
+        Self { rest_spread: 3.0, onset_probability: 0.9, offset_probability: 0.5, min_duration: 0.02, max_gap: 0.02 }
+    }
+}
+
+// one detected closure
+#[derive(Debug, Clone, Copy)]
+pub struct Closure {
+    pub onset_frame: u32,
+    pub offset_frame: u32,
+    pub onset: f64, // s
+    pub offset: f64,
+    pub min_aperture: f64,
+    pub mean_probability: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClosureIntervals {
+    pub signal: Vec<String>,
+    pub closure: Vec<u32>,
+    pub onset_frame: Vec<u32>,
+    pub offset_frame: Vec<u32>,
+    pub onset: Vec<f64>, // s
+    pub offset: Vec<f64>,
+    pub duration: Vec<f64>,
+    pub min_aperture: Vec<f64>,
+    pub mean_probability: Vec<f64>,
+    pub threshold: Vec<f64>,
+    pub rest_level: Vec<f64>,
+}
+
+impl ClosureIntervals {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            signal: Vec::with_capacity(estimated_entries),
+            closure: Vec::with_capacity(estimated_entries),
+            onset_frame: Vec::with_capacity(estimated_entries),
+            offset_frame: Vec::with_capacity(estimated_entries),
+            onset: Vec::with_capacity(estimated_entries),
+            offset: Vec::with_capacity(estimated_entries),
+            duration: Vec::with_capacity(estimated_entries),
+            min_aperture: Vec::with_capacity(estimated_entries),
+            mean_probability: Vec::with_capacity(estimated_entries),
+            threshold: Vec::with_capacity(estimated_entries),
+            rest_level: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(
+        &mut self, signal: String, closure: u32, interval: Closure,
+        threshold: f64, rest_level: f64
+    ) {
+        self.signal.push(signal);
+        self.closure.push(closure);
+        self.onset_frame.push(interval.onset_frame);
+        self.offset_frame.push(interval.offset_frame);
+        self.onset.push(interval.onset);
+        self.offset.push(interval.offset);
+        self.duration.push(interval.offset - interval.onset);
+        self.min_aperture.push(interval.min_aperture);
+        self.mean_probability.push(interval.mean_probability);
+        self.threshold.push(threshold);
+        self.rest_level.push(rest_level);
+    }
+
+    pub fn save_closure_to_parquet(data: &ClosureIntervals, file_path: &str) -> PolarsResult<()> {
+        let s_signal = Series::new("signal", &data.signal);
+        let s_closure = Series::new("closure", &data.closure);
+        let s_onset_frame = Series::new("onset_frame", &data.onset_frame);
+        let s_offset_frame = Series::new("offset_frame", &data.offset_frame);
+        let s_onset = Series::new("onset", &data.onset);
+        let s_offset = Series::new("offset", &data.offset);
+        let s_duration = Series::new("duration", &data.duration);
+        let s_min = Series::new("min_aperture", &data.min_aperture);
+        let s_probability = Series::new("mean_probability", &data.mean_probability);
+        let s_threshold = Series::new("threshold", &data.threshold);
+        let s_rest = Series::new("rest_level", &data.rest_level);
+
+        let mut df = DataFrame::new(vec![
+            s_signal, s_closure, s_onset_frame, s_offset_frame, s_onset, s_offset,
+            s_duration, s_min, s_probability, s_threshold, s_rest,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported closure data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct ClosureCalculator;
+
+impl ClosureCalculator {
+    pub fn detect(aperture: &Aperture, options: &ClosureOptions) -> ClosureIntervals {
+        let signal = &aperture.signal;
+        let n = signal.len();
+        let mut closure_data = ClosureIntervals::construction(n / 10 + 1);
+        if n < 2 { return closure_data; }
+
+        let (threshold, rest_level) = Self::threshold(&signal.value, options.rest_spread);
+        let probability: Vec<f64> = (0..n)
+            .map(|k| Self::closed_probability(signal.value[k], aperture.uncertainty[k], threshold))
+            .collect();
+
+        // (first closed sample, first open sample after it) with hysteresis
+        let mut intervals: Vec<(usize, usize)> = Vec::new();
+        let mut onset = None;
+        for (k, p) in probability.iter().enumerate() {
+            match onset {
+                None if *p >= options.onset_probability => onset = Some(k),
+                Some(start) if *p < options.offset_probability => {
+                    intervals.push((start, k));
+                    onset = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = onset { intervals.push((start, n - 1)); }
+
+        // merge short gaps then drop short closures
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            if let Some(previous) = merged.last_mut()
+                && signal.timestamp[interval.0] - signal.timestamp[previous.1] < options.max_gap
+            {
+                previous.1 = interval.1;
+                continue;
+            }
+            merged.push(interval);
+        }
+        merged.retain(|(start, end)| signal.timestamp[*end] - signal.timestamp[*start] >= options.min_duration);
+
+        for (k, (start, end)) in merged.iter().enumerate() {
+            // the offset sample is the first open one, unless the closure runs to the end of the signal
+            let closed = *start..(*end).max(start + 1);
+            let min_aperture = signal.value[closed.clone()].iter().cloned().fold(f64::MAX, f64::min);
+            let mean_probability = probability[closed.clone()].iter().sum::<f64>() / closed.len() as f64;
+
+            let interval = Closure {
+                onset_frame: signal.frame[*start],
+                offset_frame: signal.frame[*end],
+                onset: signal.timestamp[*start],
+                offset: signal.timestamp[*end],
+                min_aperture,
+                mean_probability,
+            };
+            closure_data.add_point(signal.label.clone(), k as u32, interval, threshold, rest_level);
+        }
+
+        closure_data
+    }
+
+    // (threshold, rest level)
+    pub fn threshold(values: &[f64], rest_spread: f64) -> (f64, f64) {
+        let split = Self::otsu(values);
+        let mut rest: Vec<f64> = values.iter().cloned().filter(|v| *v <= split).collect();
+        if rest.is_empty() { return (split, split); }
+
+        let rest_level = Self::median(&mut rest);
+        let mut deviations: Vec<f64> = rest.iter().map(|v| (v - rest_level).abs()).collect();
+        let spread = 1.4826 * Self::median(&mut deviations);
+
+        ((rest_level + rest_spread * spread).min(split), rest_level)
+    }
+
+    // Otsu's split (maximises the between class variance) over a 256 bin histogram
+    fn otsu(values: &[f64]) -> f64 {
+        let max = values.iter().cloned().fold(f64::MIN, f64::max);
+        let min = values.iter().cloned().fold(f64::MAX, f64::min);
+        if max <= min { return max; }
+
+        let bins = 256;
+        let width = (max - min) / bins as f64;
+        let mut histogram = vec![0.0; bins];
+        for v in values {
+            let bin = (((v - min) / width) as usize).min(bins - 1);
+            histogram[bin] += 1.0;
+        }
+
+        let total = values.len() as f64;
+        let total_sum: f64 = histogram.iter().enumerate().map(|(k, h)| k as f64 * h).sum();
+        let (mut weight_low, mut sum_low) = (0.0, 0.0);
+        let (mut best, mut best_variance) = (0, -1.0);
+        for (k, h) in histogram.iter().enumerate() {
+            weight_low += h;
+            sum_low += k as f64 * h;
+            let weight_high = total - weight_low;
+            if weight_low == 0.0 || weight_high == 0.0 { continue; }
+
+            let mean_low = sum_low / weight_low;
+            let mean_high = (total_sum - sum_low) / weight_high;
+            let variance = weight_low * weight_high * (mean_low - mean_high).powi(2);
+            if variance > best_variance {
+                best_variance = variance;
+                best = k;
+            }
+        }
+        min + (best + 1) as f64 * width
+    }
+
+    fn median(values: &mut [f64]) -> f64 {
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let n = values.len();
+        if n % 2 == 1 { values[n / 2] } else { 0.5 * (values[n / 2 - 1] + values[n / 2]) }
+    }
+
+    // P(true aperture < threshold) for a normal measurement error
+    fn closed_probability(value: f64, sigma: f64, threshold: f64) -> f64 {
+        if sigma <= 0.0 {
+            return if value < threshold { 1.0 } else { 0.0 };
+        }
+        0.5 * (1.0 + Self::erf((threshold - value) / (sigma * std::f64::consts::SQRT_2)))
+    }
+
+    // Abramowitz & Stegun 7.1.26 (|error| < 1.5e-7)
+    fn erf(x: f64) -> f64 {
+        let sign = x.signum();
+        let x = x.abs();
+        let t = 1.0 / (1.0 + 0.3275911 * x);
+        let polynomial = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+        sign * (1.0 - polynomial * (-x * x).exp())
+    }
+}
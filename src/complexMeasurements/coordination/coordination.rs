@@ -0,0 +1,329 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Coordination
+
+    Inter-articulator timing between two MOSAIC time series (i.e. upper vs lower lip, lip vs jaw):
+
+        - continuous relative phase (CRP) from the Hilbert transform of each amplitude centred signal
+        - cross-correlation with the lag at the peak
+        - spatiotemporal index (STI) of each signal across repetitions (trials)
+
+    Signal b is linearly interpolated onto the timestamps of signal a so the two do not need the same frames.
+    Output is keyed by trial. Phases are in degrees.
+*/
+
+use crate::complexMeasurements::signal::signal::{Signal, z_normalise};
+use crate::complexMeasurements::signal::dsp::{analytic_signal, wrap_angle, circular_mean, circular_sd};
+use polars::prelude::*;
+use std::fs::File;
+
+#[derive(Debug, Clone)]
+pub struct CoordinationTrial {
+    pub trial: String,
+    pub a: Signal,
+    pub b: Signal,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CoordinationOptions {
+    pub max_lag: f64, // largest cross-correlation lag searched (s)
+    pub normalised_points: usize, // time normalisation for STI and deviation phase
+}
+
+impl Default for CoordinationOptions {
+    fn default() -> Self {
+        Self { max_lag: 0.25, normalised_points: 50 }
+    }
+}
+
+// CRP time series
+#[derive(Debug, Clone)]
+pub struct CoordinationPhase {
+    pub trial: Vec<String>,
+    pub timestamp: Vec<f64>,
+    pub phase_a: Vec<f64>,
+    pub phase_b: Vec<f64>,
+    pub relative_phase: Vec<f64>,
+}
+
+impl CoordinationPhase {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            trial: Vec::with_capacity(estimated_entries),
+            timestamp: Vec::with_capacity(estimated_entries),
+            phase_a: Vec::with_capacity(estimated_entries),
+            phase_b: Vec::with_capacity(estimated_entries),
+            relative_phase: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(&mut self, trial: String, timestamp: f64, phase_a: f64, phase_b: f64, relative_phase: f64) {
+        self.trial.push(trial);
+        self.timestamp.push(timestamp);
+        self.phase_a.push(phase_a);
+        self.phase_b.push(phase_b);
+        self.relative_phase.push(relative_phase);
+    }
+
+    pub fn save_coordination_phase_to_parquet(data: &CoordinationPhase, file_path: &str) -> PolarsResult<()> {
+        let s_trial = Series::new("trial", &data.trial);
+        let s_time = Series::new("timestamp", &data.timestamp);
+        let s_phase_a = Series::new("phase_a", &data.phase_a);
+        let s_phase_b = Series::new("phase_b", &data.phase_b);
+        let s_relative = Series::new("relative_phase", &data.relative_phase);
+
+        let mut df = DataFrame::new(vec![s_trial, s_time, s_phase_a, s_phase_b, s_relative])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported coordination phase data to: {}", file_path);
+        Ok(())
+    }
+}
+
+// one row per trial. sti_a, sti_b and deviation_phase are across all trials (same value on every row, left out of
+// the parquet when there is only one trial)
+#[derive(Debug, Clone)]
+pub struct CoordinationSummary {
+    pub trial: Vec<String>,
+    pub signal_a: Vec<String>,
+    pub signal_b: Vec<String>,
+    pub samples: Vec<u32>,
+    pub mean_relative_phase: Vec<f64>, // circular mean
+    pub marp: Vec<f64>, // mean absolute relative phase
+    pub relative_phase_sd: Vec<f64>,
+    pub peak_correlation: Vec<f64>,
+    pub lag: Vec<f64>, // s, positive when b follows a
+    pub sti_a: Vec<f64>,
+    pub sti_b: Vec<f64>,
+    pub deviation_phase: Vec<f64>,
+}
+
+impl CoordinationSummary {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            trial: Vec::with_capacity(estimated_entries),
+            signal_a: Vec::with_capacity(estimated_entries),
+            signal_b: Vec::with_capacity(estimated_entries),
+            samples: Vec::with_capacity(estimated_entries),
+            mean_relative_phase: Vec::with_capacity(estimated_entries),
+            marp: Vec::with_capacity(estimated_entries),
+            relative_phase_sd: Vec::with_capacity(estimated_entries),
+            peak_correlation: Vec::with_capacity(estimated_entries),
+            lag: Vec::with_capacity(estimated_entries),
+            sti_a: Vec::with_capacity(estimated_entries),
+            sti_b: Vec::with_capacity(estimated_entries),
+            deviation_phase: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn save_coordination_summary_to_parquet(data: &CoordinationSummary, file_path: &str) -> PolarsResult<()> {
+        let s_trial = Series::new("trial", &data.trial);
+        let s_signal_a = Series::new("signal_a", &data.signal_a);
+        let s_signal_b = Series::new("signal_b", &data.signal_b);
+        let s_samples = Series::new("samples", &data.samples);
+        let s_mean = Series::new("mean_relative_phase", &data.mean_relative_phase);
+        let s_marp = Series::new("marp", &data.marp);
+        let s_sd = Series::new("relative_phase_sd", &data.relative_phase_sd);
+        let s_peak = Series::new("peak_correlation", &data.peak_correlation);
+        let s_lag = Series::new("lag", &data.lag);
+        let mut columns = vec![
+            s_trial, s_signal_a, s_signal_b, s_samples,
+            s_mean, s_marp, s_sd, s_peak, s_lag,
+        ];
+        // STI and deviation phase are across trials, so with a single trial they would only ever be NaN
+        if data.trial.len() >= 2 {
+            columns.push(Series::new("sti_a", &data.sti_a));
+            columns.push(Series::new("sti_b", &data.sti_b));
+            columns.push(Series::new("deviation_phase", &data.deviation_phase));
+        }
+
+        let mut df = DataFrame::new(columns)?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported coordination summary data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct CoordinationCalculator;
+
+impl CoordinationCalculator {
+    pub fn analyse(trials: &[CoordinationTrial], options: &CoordinationOptions) -> (CoordinationPhase, CoordinationSummary) {
+        let mut phase_data = CoordinationPhase::construction(trials.iter().map(|t| t.a.len()).sum());
+        let mut summary_data = CoordinationSummary::construction(trials.len());
+
+        let mut normalised_crp = Vec::with_capacity(trials.len());
+
+        for trial in trials {
+            let (timestamps, phase_a, phase_b, crp) = Self::relative_phase(&trial.a, &trial.b);
+            for k in 0..timestamps.len() {
+                phase_data.add_point(trial.trial.clone(), timestamps[k], phase_a[k], phase_b[k], crp[k]);
+            }
+
+            let n = crp.len() as f64;
+            let (mean, marp, sd) = if crp.is_empty() {
+                (f64::NAN, f64::NAN, f64::NAN)
+            } else {
+                let radians: Vec<f64> = crp.iter().map(|c| c.to_radians()).collect();
+                let mean = circular_mean(&radians).to_degrees();
+                let marp = crp.iter().map(|c| c.abs()).sum::<f64>() / n;
+                // circular so phases either side of +-180 degrees aren't treated as far apart
+                let sd = if crp.len() > 1 { circular_sd(&radians).to_degrees() } else { f64::NAN };
+                (mean, marp, sd)
+            };
+
+            let (peak, lag) = Self::cross_correlation(&trial.a, &trial.b, options.max_lag);
+
+            summary_data.trial.push(trial.trial.clone());
+            summary_data.signal_a.push(trial.a.label.clone());
+            summary_data.signal_b.push(trial.b.label.clone());
+            summary_data.samples.push(crp.len() as u32);
+            summary_data.mean_relative_phase.push(mean);
+            summary_data.marp.push(marp);
+            summary_data.relative_phase_sd.push(sd);
+            summary_data.peak_correlation.push(peak);
+            summary_data.lag.push(lag);
+
+            // CRP on normalised time for the deviation phase
+            let mut crp_signal = Signal::new("crp".to_string());
+            for k in 0..timestamps.len() { crp_signal.push(k as u32, timestamps[k], crp[k]); }
+            normalised_crp.push(crp_signal.time_normalise(options.normalised_points));
+        }
+
+        // across trial measures
+        let repetitions_a: Vec<Signal> = trials.iter().map(|t| t.a.clone()).collect();
+        let repetitions_b: Vec<Signal> = trials.iter().map(|t| t.b.clone()).collect();
+        let sti_a = Self::spatiotemporal_index(&repetitions_a, options.normalised_points);
+        let sti_b = Self::spatiotemporal_index(&repetitions_b, options.normalised_points);
+        let deviation_phase = Self::mean_point_sd(&normalised_crp);
+
+        for _ in trials {
+            summary_data.sti_a.push(sti_a);
+            summary_data.sti_b.push(sti_b);
+            summary_data.deviation_phase.push(deviation_phase);
+        }
+
+        (phase_data, summary_data)
+    }
+
+    // phase angle (degrees) of the analytic signal after centring the amplitude on 0 ([-1, 1])
+    pub fn phase_angle(values: &[f64]) -> Vec<f64> {
+        let max = values.iter().cloned().fold(f64::MIN, f64::max);
+        let min = values.iter().cloned().fold(f64::MAX, f64::min);
+        let half_range = 0.5 * (max - min);
+        if values.is_empty() || half_range <= 0.0 { return vec![0.0; values.len()]; }
+
+        let centred: Vec<f64> = values.iter().map(|v| (v - 0.5 * (max + min)) / half_range).collect();
+        analytic_signal(&centred).iter().map(|z| z.im.atan2(z.re).to_degrees()).collect()
+    }
+
+    // (timestamps, phase a, phase b, CRP = phase a - phase b wrapped to [-180, 180])
+    pub fn relative_phase(a: &Signal, b: &Signal) -> (Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>) {
+        let (timestamps, values_a, values_b) = Self::align(a, b);
+        let phase_a = Self::phase_angle(&values_a);
+        let phase_b = Self::phase_angle(&values_b);
+        let crp = phase_a.iter().zip(&phase_b)
+            .map(|(pa, pb)| wrap_angle((pa - pb).to_radians()).to_degrees())
+            .collect();
+        (timestamps, phase_a, phase_b, crp)
+    }
+
+    // b interpolated onto the timestamps of a (samples outside b are dropped)
+    fn align(a: &Signal, b: &Signal) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let mut timestamps = Vec::with_capacity(a.len());
+        let mut values_a = Vec::with_capacity(a.len());
+        let mut values_b = Vec::with_capacity(a.len());
+        for k in 0..a.len() {
+            let value_b = b.interpolate(a.timestamp[k]);
+            if value_b.is_nan() { continue; }
+            timestamps.push(a.timestamp[k]);
+            values_a.push(a.value[k]);
+            values_b.push(value_b);
+        }
+        (timestamps, values_a, values_b)
+    }
+
+    // pearson correlation of a(t) and b(t + lag) for every lag up to max_lag
+    // returns the correlation with the largest magnitude (sign kept, so anti-phase coupling is negative) and its lag (s)
+    pub fn cross_correlation(a: &Signal, b: &Signal, max_lag: f64) -> (f64, f64) {
+        let (timestamps, values_a, values_b) = Self::align(a, b);
+        let n = values_a.len();
+        if n < 3 { return (f64::NAN, f64::NAN); }
+
+        let dt = (timestamps[n - 1] - timestamps[0]) / (n - 1) as f64;
+        let max_shift = if dt > 0.0 { ((max_lag / dt).round() as usize).min(n - 3) } else { 0 };
+
+        let mut best = (f64::NAN, f64::NAN);
+        for shift in -(max_shift as isize)..=(max_shift as isize) {
+            let (start_a, start_b) = if shift >= 0 { (0, shift as usize) } else { ((-shift) as usize, 0) };
+            let length = n - shift.unsigned_abs();
+            let r = Self::pearson(&values_a[start_a..start_a + length], &values_b[start_b..start_b + length]);
+            if r.is_nan() { continue; }
+            if best.0.is_nan() || r.abs() > best.0.abs() {
+                best = (r, shift as f64 * dt);
+            }
+        }
+        best
+    }
+
+    fn pearson(x: &[f64], y: &[f64]) -> f64 {
+        let n = x.len() as f64;
+        let mean_x = x.iter().sum::<f64>() / n;
+        let mean_y = y.iter().sum::<f64>() / n;
+        let mut sxy = 0.0;
+        let mut sxx = 0.0;
+        let mut syy = 0.0;
+        for (xi, yi) in x.iter().zip(y) {
+            sxy += (xi - mean_x) * (yi - mean_y);
+            sxx += (xi - mean_x).powi(2);
+            syy += (yi - mean_y).powi(2);
+        }
+        if sxx <= 0.0 || syy <= 0.0 { return f64::NAN; }
+        sxy / (sxx * syy).sqrt()
+    }
+
+    // STI (Smith et al., 1995): each repetition is linearly time normalised and z normalised, then the standard
+    // deviations across repetitions at each normalised point are summed
+    pub fn spatiotemporal_index(repetitions: &[Signal], points: usize) -> f64 {
+        let normalised: Vec<Vec<f64>> = repetitions.iter()
+            .filter(|r| r.len() >= 2)
+            .map(|r| z_normalise(&r.time_normalise(points)))
+            .collect();
+        let sd = Self::point_sd(&normalised);
+        if sd.is_empty() { f64::NAN } else { sd.iter().sum() }
+    }
+
+    // standard deviation across repetitions at each normalised point (empty with fewer than 2 repetitions)
+    pub fn point_sd(normalised: &[Vec<f64>]) -> Vec<f64> {
+        if normalised.len() < 2 { return Vec::new(); }
+        let points = normalised.iter().map(|r| r.len()).min().unwrap_or(0);
+        let n = normalised.len() as f64;
+
+        (0..points).map(|k| {
+            let mean = normalised.iter().map(|r| r[k]).sum::<f64>() / n;
+            (normalised.iter().map(|r| (r[k] - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+        }).collect()
+    }
+
+    // deviation phase: mean over normalised time of the SD of the CRP across repetitions
+    fn mean_point_sd(normalised: &[Vec<f64>]) -> f64 {
+        let sd = Self::point_sd(normalised);
+        if sd.is_empty() { f64::NAN } else { sd.iter().sum::<f64>() / sd.len() as f64 }
+    }
+}
@@ -0,0 +1,328 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    DDK
+
+    Opening/closing cycle detection for diadochokinetic tasks (/pataka/, /papapa/) from a lip aperture or jaw
+    distance signal (any `Signal`, i.e. `Signal::from_euclidean` or `Signal::from_area`).
+
+    Peaks are picked with hysteresis: the signal has to rise above the high threshold to start an opening and fall
+    below the low threshold to finish it, so small wobbles don't create extra cycles. Peaks closer than the minimum
+    distance are then merged.
+
+    A cycle runs from one closure (trough) through the opening peak to the next closure.
+*/
+
+use crate::complexMeasurements::signal::signal::{Signal};
+use polars::prelude::*;
+use std::fs::File;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DDKOptions {
+    pub high_fraction: f64, // of the signal range above the minimum
+    pub low_fraction: f64,
+    pub min_distance: f64, // s between peaks
+    pub smoothing: usize, // moving average window (samples, 1 for none)
+}
+
+impl Default for DDKOptions {
+    fn default() -> Self {
+        Self { high_fraction: 0.6, low_fraction: 0.4, min_distance: 0.08, smoothing: 3 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DDKCycles {
+    pub signal: Vec<String>,
+    pub cycle: Vec<u32>,
+    pub onset: Vec<f64>, // s
+    pub peak_time: Vec<f64>,
+    pub offset: Vec<f64>,
+    pub duration: Vec<f64>,
+    pub peak_value: Vec<f64>,
+    pub amplitude: Vec<f64>, // peak - mean of the two closures
+}
+
+impl DDKCycles {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            signal: Vec::with_capacity(estimated_entries),
+            cycle: Vec::with_capacity(estimated_entries),
+            onset: Vec::with_capacity(estimated_entries),
+            peak_time: Vec::with_capacity(estimated_entries),
+            offset: Vec::with_capacity(estimated_entries),
+            duration: Vec::with_capacity(estimated_entries),
+            peak_value: Vec::with_capacity(estimated_entries),
+            amplitude: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(
+        &mut self, signal: String, cycle: u32,
+        (onset, peak_time, offset): (f64, f64, f64), peak_value: f64, amplitude: f64
+    ) {
+        self.signal.push(signal);
+        self.cycle.push(cycle);
+        self.onset.push(onset);
+        self.peak_time.push(peak_time);
+        self.offset.push(offset);
+        self.duration.push(offset - onset);
+        self.peak_value.push(peak_value);
+        self.amplitude.push(amplitude);
+    }
+
+    pub fn save_ddk_cycles_to_parquet(data: &DDKCycles, file_path: &str) -> PolarsResult<()> {
+        let s_signal = Series::new("signal", &data.signal);
+        let s_cycle = Series::new("cycle", &data.cycle);
+        let s_onset = Series::new("onset", &data.onset);
+        let s_peak_time = Series::new("peak_time", &data.peak_time);
+        let s_offset = Series::new("offset", &data.offset);
+        let s_duration = Series::new("duration", &data.duration);
+        let s_peak_value = Series::new("peak_value", &data.peak_value);
+        let s_amplitude = Series::new("amplitude", &data.amplitude);
+
+        let mut df = DataFrame::new(vec![
+            s_signal, s_cycle, s_onset, s_peak_time, s_offset, s_duration, s_peak_value, s_amplitude,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported DDK cycle data to: {}", file_path);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DDKSummary {
+    pub signal: Vec<String>,
+    pub cycles: Vec<u32>,
+    pub rate: Vec<f64>, // cycles/s
+    pub mean_duration: Vec<f64>,
+    pub duration_cv: Vec<f64>,
+    pub mean_amplitude: Vec<f64>,
+    pub amplitude_cv: Vec<f64>,
+    pub duration_npvi: Vec<f64>,
+    pub regularity: Vec<f64>, // autocorrelation peak at the cycle period
+}
+
+impl DDKSummary {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            signal: Vec::with_capacity(estimated_entries),
+            cycles: Vec::with_capacity(estimated_entries),
+            rate: Vec::with_capacity(estimated_entries),
+            mean_duration: Vec::with_capacity(estimated_entries),
+            duration_cv: Vec::with_capacity(estimated_entries),
+            mean_amplitude: Vec::with_capacity(estimated_entries),
+            amplitude_cv: Vec::with_capacity(estimated_entries),
+            duration_npvi: Vec::with_capacity(estimated_entries),
+            regularity: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn save_ddk_summary_to_parquet(data: &DDKSummary, file_path: &str) -> PolarsResult<()> {
+        let s_signal = Series::new("signal", &data.signal);
+        let s_cycles = Series::new("cycles", &data.cycles);
+        let s_rate = Series::new("rate", &data.rate);
+        let s_duration = Series::new("mean_duration", &data.mean_duration);
+        let s_duration_cv = Series::new("duration_cv", &data.duration_cv);
+        let s_amplitude = Series::new("mean_amplitude", &data.mean_amplitude);
+        let s_amplitude_cv = Series::new("amplitude_cv", &data.amplitude_cv);
+        let s_npvi = Series::new("duration_npvi", &data.duration_npvi);
+        let s_regularity = Series::new("regularity", &data.regularity);
+
+        let mut df = DataFrame::new(vec![
+            s_signal, s_cycles, s_rate, s_duration, s_duration_cv,
+            s_amplitude, s_amplitude_cv, s_npvi, s_regularity,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported DDK summary data to: {}", file_path);
+        Ok(())
+    }
+}
+
+// sample indices of one cycle
+#[derive(Debug, Clone, Copy)]
+struct Cycle {
+    onset: usize,
+    peak: usize,
+    offset: usize,
+}
+
+pub struct DDKCalculator;
+
+impl DDKCalculator {
+    pub fn detect(signal: &Signal, options: &DDKOptions) -> (DDKCycles, DDKSummary) {
+        let smoothed = Self::moving_average(&signal.value, options.smoothing.max(1));
+        let cycles = Self::merge_close(Self::hysteresis(&smoothed, options), &smoothed, &signal.timestamp, options.min_distance);
+
+        let mut cycle_data = DDKCycles::construction(cycles.len());
+        let mut durations = Vec::with_capacity(cycles.len());
+        let mut amplitudes = Vec::with_capacity(cycles.len());
+
+        for (k, cycle) in cycles.iter().enumerate() {
+            let closure = 0.5 * (signal.value[cycle.onset] + signal.value[cycle.offset]);
+            let amplitude = signal.value[cycle.peak] - closure;
+            cycle_data.add_point(
+                signal.label.clone(), k as u32,
+                (signal.timestamp[cycle.onset], signal.timestamp[cycle.peak], signal.timestamp[cycle.offset]),
+                signal.value[cycle.peak], amplitude,
+            );
+            durations.push(signal.timestamp[cycle.offset] - signal.timestamp[cycle.onset]);
+            amplitudes.push(amplitude);
+        }
+
+        let mut summary = DDKSummary::construction(1);
+        let (mean_duration, duration_sd) = Self::mean_sd(&durations);
+        let (mean_amplitude, amplitude_sd) = Self::mean_sd(&amplitudes);
+        let rate = match (cycles.first(), cycles.last()) {
+            (Some(first), Some(last)) => {
+                let span = signal.timestamp[last.offset] - signal.timestamp[first.onset];
+                if span > 0.0 { cycles.len() as f64 / span } else { f64::NAN }
+            }
+            _ => f64::NAN,
+        };
+
+        summary.signal.push(signal.label.clone());
+        summary.cycles.push(cycles.len() as u32);
+        summary.rate.push(rate);
+        summary.mean_duration.push(mean_duration);
+        summary.duration_cv.push(duration_sd / mean_duration);
+        summary.mean_amplitude.push(mean_amplitude);
+        summary.amplitude_cv.push(amplitude_sd / mean_amplitude);
+        summary.duration_npvi.push(Self::npvi(&durations));
+        summary.regularity.push(Self::regularity(&smoothed, &cycles));
+
+        (cycle_data, summary)
+    }
+
+    // centred moving average (shorter window at the ends)
+    fn moving_average(values: &[f64], window: usize) -> Vec<f64> {
+        let half = window / 2;
+        (0..values.len()).map(|k| {
+            let start = k.saturating_sub(half);
+            let end = (k + half + 1).min(values.len());
+            values[start..end].iter().sum::<f64>() / (end - start) as f64
+        }).collect()
+    }
+
+    fn hysteresis(values: &[f64], options: &DDKOptions) -> Vec<Cycle> {
+        if values.len() < 3 { return Vec::new(); }
+        let max = values.iter().cloned().fold(f64::MIN, f64::max);
+        let min = values.iter().cloned().fold(f64::MAX, f64::min);
+        let range = max - min;
+        if range <= 0.0 { return Vec::new(); }
+
+        let high = min + options.high_fraction * range;
+        let low = min + options.low_fraction * range;
+
+        // troughs and peaks alternate, starting with a trough (a leading opening has no onset so is dropped)
+        let mut troughs = Vec::new();
+        let mut peaks = Vec::new();
+        let mut open = values[0] > high;
+        let mut extreme = 0;
+        for k in 0..values.len() {
+            if open {
+                if values[k] > values[extreme] { extreme = k; }
+                if values[k] < low {
+                    if !troughs.is_empty() { peaks.push(extreme); }
+                    open = false;
+                    extreme = k;
+                }
+            } else {
+                if values[k] < values[extreme] { extreme = k; }
+                if values[k] > high {
+                    troughs.push(extreme);
+                    open = true;
+                    extreme = k;
+                }
+            }
+        }
+        // final closure (an opening still in progress at the end has no offset)
+        if !open { troughs.push(extreme); }
+
+        peaks.iter().enumerate()
+            .filter(|(k, _)| k + 1 < troughs.len())
+            .map(|(k, &peak)| Cycle { onset: troughs[k], peak, offset: troughs[k + 1] })
+            .collect()
+    }
+
+    // cycles whose peaks are closer than min_distance are merged into one (keeping the higher peak)
+    fn merge_close(cycles: Vec<Cycle>, values: &[f64], timestamps: &[f64], min_distance: f64) -> Vec<Cycle> {
+        let mut merged: Vec<Cycle> = Vec::with_capacity(cycles.len());
+        for cycle in cycles {
+            if let Some(previous) = merged.last_mut()
+                && timestamps[cycle.peak] - timestamps[previous.peak] < min_distance
+            {
+                if values[cycle.peak] > values[previous.peak] { previous.peak = cycle.peak; }
+                previous.offset = cycle.offset;
+                continue;
+            }
+            merged.push(cycle);
+        }
+        merged
+    }
+
+    fn mean_sd(values: &[f64]) -> (f64, f64) {
+        let n = values.len() as f64;
+        if values.is_empty() { return (f64::NAN, f64::NAN); }
+        let mean = values.iter().sum::<f64>() / n;
+        if values.len() < 2 { return (mean, f64::NAN); }
+        (mean, (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt())
+    }
+
+    // normalised pairwise variability index of consecutive durations
+    fn npvi(durations: &[f64]) -> f64 {
+        if durations.len() < 2 { return f64::NAN; }
+        let total: f64 = durations.windows(2)
+            .map(|d| (d[0] - d[1]).abs() / (0.5 * (d[0] + d[1])))
+            .sum();
+        100.0 * total / (durations.len() - 1) as f64
+    }
+
+    // largest normalised autocorrelation of the detected span for lags within 0.5-1.5x the median cycle length
+    // (1 is perfectly periodic)
+    fn regularity(values: &[f64], cycles: &[Cycle]) -> f64 {
+        let (Some(first), Some(last)) = (cycles.first(), cycles.last()) else { return f64::NAN };
+        if cycles.len() < 2 { return f64::NAN; }
+
+        let span = &values[first.onset..=last.offset];
+        let mean = span.iter().sum::<f64>() / span.len() as f64;
+        let centred: Vec<f64> = span.iter().map(|v| v - mean).collect();
+
+        let mut periods: Vec<usize> = cycles.iter().map(|c| c.offset - c.onset).collect();
+        periods.sort();
+        let period = periods[periods.len() / 2];
+
+        let lower = (period / 2).max(1);
+        let upper = (period * 3 / 2).min(centred.len().saturating_sub(2));
+        let mut best = f64::NAN;
+        for lag in lower..=upper {
+            let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+            for k in 0..centred.len() - lag {
+                sxy += centred[k] * centred[k + lag];
+                sxx += centred[k] * centred[k];
+                syy += centred[k + lag] * centred[k + lag];
+            }
+            if sxx <= 0.0 || syy <= 0.0 { continue; }
+            let r = sxy / (sxx * syy).sqrt();
+            if best.is_nan() || r > best { best = r; }
+        }
+        best
+    }
+}
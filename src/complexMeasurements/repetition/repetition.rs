@@ -0,0 +1,545 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Repetition
+
+    Repeated utterance paradigms (i.e. "buy Bobby a puppy" x10). Each repetition is cut out of a signal by its
+    boundaries (from a TextGrid, gesture segmentation, etc.), time normalised and amplitude (z) normalised.
+
+        - Linear: linear time normalisation only, this gives the classic STI (Smith et al., 1995)
+        - DynamicTimeWarping: each repetition is warped onto a template by DTW
+        - Registration: FDA style registration with smooth monotone warping functions (Ramsay & Silverman, 2005)
+
+    The STI is reported for the aligned curves so the amplitude variability can be separated from the timing
+    variability (the warping functions).
+*/
+
+use crate::complexMeasurements::signal::signal::{Signal, z_normalise};
+use crate::complexMeasurements::coordination::coordination::{CoordinationCalculator};
+use polars::prelude::*;
+use std::fs::File;
+
+#[derive(Debug, Clone)]
+pub struct Repetition {
+    pub label: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AlignmentMethod {
+    Linear,
+    // window is the Sakoe-Chiba band in normalised points (None for no band)
+    DynamicTimeWarping { window: Option<usize>, iterations: usize },
+    // knots is the number of piecewise linear segments of each warping function, lambda penalises warps away from
+    // the identity
+    Registration { knots: usize, lambda: f64, iterations: usize },
+}
+
+impl AlignmentMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::DynamicTimeWarping { .. } => "dtw",
+            Self::Registration { .. } => "registration",
+        }
+    }
+}
+
+// one row per normalised point
+#[derive(Debug, Clone)]
+pub struct RepetitionCurves {
+    pub signal: Vec<String>,
+    pub method: Vec<String>,
+    pub point: Vec<u32>,
+    pub normalised_time: Vec<f64>,
+    pub mean: Vec<f64>, // z normalised
+    pub sd: Vec<f64>,
+    pub mean_raw: Vec<f64>, // signal units
+    pub sd_raw: Vec<f64>,
+}
+
+impl RepetitionCurves {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            signal: Vec::with_capacity(estimated_entries),
+            method: Vec::with_capacity(estimated_entries),
+            point: Vec::with_capacity(estimated_entries),
+            normalised_time: Vec::with_capacity(estimated_entries),
+            mean: Vec::with_capacity(estimated_entries),
+            sd: Vec::with_capacity(estimated_entries),
+            mean_raw: Vec::with_capacity(estimated_entries),
+            sd_raw: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(
+        &mut self, signal: String, method: String, point: u32, normalised_time: f64,
+        normalised: (f64, f64), raw: (f64, f64) // (mean, sd)
+    ) {
+        self.signal.push(signal);
+        self.method.push(method);
+        self.point.push(point);
+        self.normalised_time.push(normalised_time);
+        self.mean.push(normalised.0);
+        self.sd.push(normalised.1);
+        self.mean_raw.push(raw.0);
+        self.sd_raw.push(raw.1);
+    }
+
+    pub fn save_repetition_curves_to_parquet(data: &RepetitionCurves, file_path: &str) -> PolarsResult<()> {
+        let s_signal = Series::new("signal", &data.signal);
+        let s_method = Series::new("method", &data.method);
+        let s_point = Series::new("point", &data.point);
+        let s_time = Series::new("normalised_time", &data.normalised_time);
+        let s_mean = Series::new("mean", &data.mean);
+        let s_sd = Series::new("sd", &data.sd);
+        let s_mean_raw = Series::new("mean_raw", &data.mean_raw);
+        let s_sd_raw = Series::new("sd_raw", &data.sd_raw);
+
+        let mut df = DataFrame::new(vec![
+            s_signal, s_method, s_point, s_time, s_mean, s_sd, s_mean_raw, s_sd_raw,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported repetition curve data to: {}", file_path);
+        Ok(())
+    }
+}
+
+// warping function h(t) of each repetition (aligned curve at t is the repetition at h(t))
+#[derive(Debug, Clone)]
+pub struct RepetitionWarping {
+    pub signal: Vec<String>,
+    pub method: Vec<String>,
+    pub repetition: Vec<String>,
+    pub point: Vec<u32>,
+    pub normalised_time: Vec<f64>,
+    pub warped_time: Vec<f64>,
+}
+
+impl RepetitionWarping {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            signal: Vec::with_capacity(estimated_entries),
+            method: Vec::with_capacity(estimated_entries),
+            repetition: Vec::with_capacity(estimated_entries),
+            point: Vec::with_capacity(estimated_entries),
+            normalised_time: Vec::with_capacity(estimated_entries),
+            warped_time: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(
+        &mut self, signal: String, method: String, repetition: String,
+        point: u32, normalised_time: f64, warped_time: f64
+    ) {
+        self.signal.push(signal);
+        self.method.push(method);
+        self.repetition.push(repetition);
+        self.point.push(point);
+        self.normalised_time.push(normalised_time);
+        self.warped_time.push(warped_time);
+    }
+
+    pub fn save_repetition_warping_to_parquet(data: &RepetitionWarping, file_path: &str) -> PolarsResult<()> {
+        let s_signal = Series::new("signal", &data.signal);
+        let s_method = Series::new("method", &data.method);
+        let s_repetition = Series::new("repetition", &data.repetition);
+        let s_point = Series::new("point", &data.point);
+        let s_time = Series::new("normalised_time", &data.normalised_time);
+        let s_warped = Series::new("warped_time", &data.warped_time);
+
+        let mut df = DataFrame::new(vec![
+            s_signal, s_method, s_repetition, s_point, s_time, s_warped,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported repetition warping data to: {}", file_path);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RepetitionSummary {
+    pub signal: Vec<String>,
+    pub method: Vec<String>,
+    pub repetitions: Vec<u32>,
+    pub linear_sti: Vec<f64>, // classic STI (linear normalisation)
+    pub aligned_sti: Vec<f64>, // STI after alignment (amplitude variability)
+    pub warp_deviation: Vec<f64>, // mean |h(t) - t| (timing variability)
+    pub mean_duration: Vec<f64>,
+    pub duration_sd: Vec<f64>,
+}
+
+impl RepetitionSummary {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            signal: Vec::with_capacity(estimated_entries),
+            method: Vec::with_capacity(estimated_entries),
+            repetitions: Vec::with_capacity(estimated_entries),
+            linear_sti: Vec::with_capacity(estimated_entries),
+            aligned_sti: Vec::with_capacity(estimated_entries),
+            warp_deviation: Vec::with_capacity(estimated_entries),
+            mean_duration: Vec::with_capacity(estimated_entries),
+            duration_sd: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn save_repetition_summary_to_parquet(data: &RepetitionSummary, file_path: &str) -> PolarsResult<()> {
+        let s_signal = Series::new("signal", &data.signal);
+        let s_method = Series::new("method", &data.method);
+        let s_repetitions = Series::new("repetitions", &data.repetitions);
+        let s_linear = Series::new("linear_sti", &data.linear_sti);
+        let s_aligned = Series::new("aligned_sti", &data.aligned_sti);
+        let s_warp = Series::new("warp_deviation", &data.warp_deviation);
+        let s_duration = Series::new("mean_duration", &data.mean_duration);
+        let s_duration_sd = Series::new("duration_sd", &data.duration_sd);
+
+        let mut df = DataFrame::new(vec![
+            s_signal, s_method, s_repetitions, s_linear, s_aligned, s_warp, s_duration, s_duration_sd,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported repetition summary data to: {}", file_path);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RepetitionResults {
+    pub curves: RepetitionCurves,
+    pub warping: RepetitionWarping,
+    pub summary: RepetitionSummary,
+}
+
+pub struct RepetitionCalculator;
+
+impl RepetitionCalculator {
+    pub fn analyse(
+        signal: &Signal, repetitions: &[Repetition],
+        method: &AlignmentMethod, points: usize
+    ) -> RepetitionResults {
+        let mut curves = RepetitionCurves::construction(points);
+        let mut warping = RepetitionWarping::construction(points * repetitions.len());
+        let mut summary = RepetitionSummary::construction(1);
+
+        // repetitions with fewer than 2 samples can't be normalised
+        let mut labels = Vec::with_capacity(repetitions.len());
+        let mut windows = Vec::with_capacity(repetitions.len());
+        for repetition in repetitions {
+            let window = signal.window(repetition.start, repetition.end);
+            if window.len() < 2 { continue; }
+            labels.push(repetition.label.clone());
+            windows.push(window);
+        }
+
+        let raw: Vec<Vec<f64>> = windows.iter().map(|w| w.time_normalise(points)).collect();
+        let normalised: Vec<Vec<f64>> = raw.iter().map(|r| z_normalise(r)).collect();
+
+        // warping functions on the normalised grid
+        let warps: Vec<Vec<f64>> = match *method {
+            AlignmentMethod::Linear => vec![Self::grid(points); normalised.len()],
+            AlignmentMethod::DynamicTimeWarping { window, iterations } =>
+                Self::dtw_align(&normalised, window, iterations),
+            AlignmentMethod::Registration { knots, lambda, iterations } =>
+                Self::register(&normalised, knots, lambda, iterations),
+        };
+
+        let aligned: Vec<Vec<f64>> = normalised.iter().zip(&warps).map(|(c, h)| Self::warp(c, h)).collect();
+        let aligned_raw: Vec<Vec<f64>> = raw.iter().zip(&warps).map(|(c, h)| Self::warp(c, h)).collect();
+
+        let method_name = method.name().to_string();
+        let grid = Self::grid(points);
+
+        let (mean, sd) = (Self::point_mean(&aligned), CoordinationCalculator::point_sd(&aligned));
+        let (mean_raw, sd_raw) = (Self::point_mean(&aligned_raw), CoordinationCalculator::point_sd(&aligned_raw));
+        let at = |values: &[f64], k: usize| values.get(k).copied().unwrap_or(f64::NAN);
+        for (k, t) in grid.iter().enumerate() {
+            curves.add_point(
+                signal.label.clone(), method_name.clone(), k as u32, *t,
+                (at(&mean, k), at(&sd, k)), (at(&mean_raw, k), at(&sd_raw, k)),
+            );
+        }
+
+        for (label, h) in labels.iter().zip(&warps) {
+            for k in 0..points {
+                warping.add_point(signal.label.clone(), method_name.clone(), label.clone(), k as u32, grid[k], h[k]);
+            }
+        }
+
+        let durations: Vec<f64> = windows.iter().map(|w| w.timestamp[w.len() - 1] - w.timestamp[0]).collect();
+        let n = durations.len() as f64;
+        let mean_duration = if durations.is_empty() { f64::NAN } else { durations.iter().sum::<f64>() / n };
+        let duration_sd = if durations.len() < 2 { f64::NAN } else {
+            (durations.iter().map(|d| (d - mean_duration).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+        };
+
+        let warp_deviation = if warps.is_empty() { f64::NAN } else {
+            warps.iter().map(|h| {
+                h.iter().zip(&grid).map(|(hk, tk)| (hk - tk).abs()).sum::<f64>() / points as f64
+            }).sum::<f64>() / warps.len() as f64
+        };
+
+        summary.signal.push(signal.label.clone());
+        summary.method.push(method_name);
+        summary.repetitions.push(windows.len() as u32);
+        summary.linear_sti.push(CoordinationCalculator::spatiotemporal_index(&windows, points));
+        summary.aligned_sti.push(if sd.is_empty() { f64::NAN } else { sd.iter().sum() });
+        summary.warp_deviation.push(warp_deviation);
+        summary.mean_duration.push(mean_duration);
+        summary.duration_sd.push(duration_sd);
+
+        RepetitionResults { curves, warping, summary }
+    }
+
+    fn grid(points: usize) -> Vec<f64> {
+        if points < 2 { return vec![0.0; points]; }
+        (0..points).map(|k| k as f64 / (points - 1) as f64).collect()
+    }
+
+    fn point_mean(curves: &[Vec<f64>]) -> Vec<f64> {
+        if curves.is_empty() { return Vec::new(); }
+        let points = curves.iter().map(|c| c.len()).min().unwrap_or(0);
+        let n = curves.len() as f64;
+        (0..points).map(|k| curves.iter().map(|c| c[k]).sum::<f64>() / n).collect()
+    }
+
+    // curve (on the normalised grid) evaluated at the warped times h
+    fn warp(curve: &[f64], h: &[f64]) -> Vec<f64> {
+        h.iter().map(|t| Self::sample(curve, *t)).collect()
+    }
+
+    // linear interpolation of a curve on the normalised grid at t in [0, 1]
+    fn sample(curve: &[f64], t: f64) -> f64 {
+        let n = curve.len();
+        if n == 0 { return f64::NAN; }
+        if n == 1 { return curve[0]; }
+        let position = t.clamp(0.0, 1.0) * (n - 1) as f64;
+        let k = (position.floor() as usize).min(n - 2);
+        let w = position - k as f64;
+        curve[k] * (1.0 - w) + curve[k + 1] * w
+    }
+
+    // DTW of each curve against the template (starting from the linear mean, then the mean of the aligned curves)
+    // the warping function at template point j is the mean of the curve points matched to j, which is monotone
+    fn dtw_align(curves: &[Vec<f64>], window: Option<usize>, iterations: usize) -> Vec<Vec<f64>> {
+        if curves.is_empty() { return Vec::new(); }
+        let points = curves[0].len();
+        let mut warps = vec![Self::grid(points); curves.len()];
+
+        let mut template = Self::point_mean(curves);
+        for _ in 0..iterations.max(1) {
+            warps = curves.iter().map(|c| Self::dtw_warp(c, &template, window)).collect();
+            let aligned: Vec<Vec<f64>> = curves.iter().zip(&warps).map(|(c, h)| Self::warp(c, h)).collect();
+            template = Self::point_mean(&aligned);
+        }
+        warps
+    }
+
+    fn dtw_warp(curve: &[f64], template: &[f64], window: Option<usize>) -> Vec<f64> {
+        let n = curve.len();
+        let m = template.len();
+        if n < 2 || m < 2 { return Self::grid(m); }
+
+        // band must at least cover the diagonal when the lengths differ
+        let band = window.unwrap_or(n.max(m)).max(n.abs_diff(m));
+
+        let mut cost = vec![f64::INFINITY; (n + 1) * (m + 1)];
+        let at = |i: usize, j: usize| i * (m + 1) + j;
+        cost[at(0, 0)] = 0.0;
+        for i in 1..=n {
+            let lower = i.saturating_sub(band).max(1);
+            let upper = (i + band).min(m);
+            for j in lower..=upper {
+                let d = (curve[i - 1] - template[j - 1]).powi(2);
+                let best = cost[at(i - 1, j - 1)].min(cost[at(i - 1, j)]).min(cost[at(i, j - 1)]);
+                cost[at(i, j)] = d + best;
+            }
+        }
+
+        // backtrack
+        let mut matched_sum = vec![0.0_f64; m];
+        let mut matched_count = vec![0.0_f64; m];
+        let (mut i, mut j) = (n, m);
+        while i > 0 && j > 0 {
+            matched_sum[j - 1] += (i - 1) as f64;
+            matched_count[j - 1] += 1.0;
+            let diagonal = cost[at(i - 1, j - 1)];
+            let up = cost[at(i - 1, j)];
+            let left = cost[at(i, j - 1)];
+            if diagonal <= up && diagonal <= left { i -= 1; j -= 1; }
+            else if up <= left { i -= 1; }
+            else { j -= 1; }
+        }
+
+        (0..m).map(|j| matched_sum[j] / matched_count[j].max(1.0) / (n - 1) as f64).collect()
+    }
+
+    // FDA registration: each warping function is piecewise linear with `knots` segments whose widths are
+    // exp(c_j) / sum(exp(c)) so it is always monotone with h(0) = 0 and h(1) = 1. c is chosen to minimise
+    // sum (x(h(t)) - mean(t))^2 + lambda sum c^2 by gradient descent, alternating with updating the mean.
+    // The warps are then composed with the inverse of their mean so the average warp is the identity.
+    fn register(curves: &[Vec<f64>], knots: usize, lambda: f64, iterations: usize) -> Vec<Vec<f64>> {
+        if curves.is_empty() { return Vec::new(); }
+        let points = curves[0].len();
+        let grid = Self::grid(points);
+        let knots = knots.max(1);
+
+        let mut coefficients = vec![vec![0.0; knots]; curves.len()];
+        let mut template = Self::point_mean(curves);
+
+        for _ in 0..iterations.max(1) {
+            for (curve, c) in curves.iter().zip(coefficients.iter_mut()) {
+                Self::fit_warp(curve, &template, &grid, c, lambda);
+            }
+            let aligned: Vec<Vec<f64>> = curves.iter().zip(&coefficients)
+                .map(|(curve, c)| Self::warp(curve, &Self::warp_function(c, &grid)))
+                .collect();
+            template = Self::point_mean(&aligned);
+        }
+
+        let warps: Vec<Vec<f64>> = coefficients.iter().map(|c| Self::warp_function(c, &grid)).collect();
+
+        // centre: h_i(t) -> h_i(mean_h^-1(t))
+        let mean_warp = Self::point_mean(&warps);
+        let inverse: Vec<f64> = grid.iter().map(|t| Self::invert(&mean_warp, &grid, *t)).collect();
+        warps.iter().map(|h| inverse.iter().map(|t| Self::sample(h, *t)).collect()).collect()
+    }
+
+    fn warp_function(c: &[f64], grid: &[f64]) -> Vec<f64> {
+        let knots = c.len();
+        let weights: Vec<f64> = c.iter().map(|v| v.exp()).collect();
+        let total: f64 = weights.iter().sum();
+
+        let mut breaks = Vec::with_capacity(knots + 1);
+        breaks.push(0.0);
+        for w in &weights { breaks.push(breaks[breaks.len() - 1] + w / total); }
+
+        grid.iter().map(|t| {
+            let position = t.clamp(0.0, 1.0) * knots as f64;
+            let k = (position.floor() as usize).min(knots - 1);
+            let w = position - k as f64;
+            breaks[k] * (1.0 - w) + breaks[k + 1] * w
+        }).collect()
+    }
+
+    fn registration_cost(curve: &[f64], template: &[f64], grid: &[f64], c: &[f64], lambda: f64) -> f64 {
+        let aligned = Self::warp(curve, &Self::warp_function(c, grid));
+        let fit: f64 = aligned.iter().zip(template).map(|(a, m)| (a - m).powi(2)).sum();
+        fit + lambda * c.iter().map(|v| v * v).sum::<f64>()
+    }
+
+    fn fit_warp(curve: &[f64], template: &[f64], grid: &[f64], c: &mut [f64], lambda: f64) {
+        let h = 1e-5;
+        let mut step = 0.5;
+        let mut current = Self::registration_cost(curve, template, grid, c, lambda);
+
+        for _ in 0..100 {
+            // central difference gradient
+            let mut gradient = vec![0.0; c.len()];
+            for j in 0..c.len() {
+                let original = c[j];
+                c[j] = original + h;
+                let plus = Self::registration_cost(curve, template, grid, c, lambda);
+                c[j] = original - h;
+                let minus = Self::registration_cost(curve, template, grid, c, lambda);
+                c[j] = original;
+                gradient[j] = (plus - minus) / (2.0 * h);
+            }
+            let norm = gradient.iter().map(|g| g * g).sum::<f64>().sqrt();
+            if norm < 1e-8 { break; }
+
+            // backtracking line search
+            let mut accepted = false;
+            while step > 1e-8 {
+                let trial: Vec<f64> = c.iter().zip(&gradient).map(|(v, g)| v - step * g / norm).collect();
+                let cost = Self::registration_cost(curve, template, grid, &trial, lambda);
+                if cost < current {
+                    c.copy_from_slice(&trial);
+                    current = cost;
+                    step *= 1.5;
+                    accepted = true;
+                    break;
+                }
+                step *= 0.5;
+            }
+            if !accepted { break; }
+        }
+    }
+
+    // t such that h(t) = value for a monotone h on the grid
+    fn invert(h: &[f64], grid: &[f64], value: f64) -> f64 {
+        let k = h.partition_point(|v| *v < value);
+        if k == 0 { return grid[0]; }
+        if k >= h.len() { return grid[grid.len() - 1]; }
+        let (h0, h1) = (h[k - 1], h[k]);
+        if h1 <= h0 { return grid[k]; }
+        let w = (value - h0) / (h1 - h0);
+        grid[k - 1] * (1.0 - w) + grid[k] * w
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    // the same curve twice, 2 s apart, sampled at 100 Hz
+    fn shifted_copies() -> (Signal, Vec<Repetition>) {
+        let mut signal = Signal::new("test".to_string());
+        for k in 0..=300 {
+            let t = k as f64 * 0.01;
+            let phase = t - if t >= 2.0 { 2.0 } else { 0.0 };
+            signal.push(k + 1, t, (2.0 * PI * phase).sin() + 0.5 * (6.0 * PI * phase).sin());
+        }
+        let repetitions = vec![
+            Repetition { label: "1".to_string(), start: 0.0, end: 1.0 },
+            Repetition { label: "2".to_string(), start: 2.0, end: 3.0 },
+        ];
+        (signal, repetitions)
+    }
+
+    #[test]
+    fn aligned_copies_have_zero_sti_and_monotone_warps() {
+        let (signal, repetitions) = shifted_copies();
+        let methods = [
+            AlignmentMethod::Linear,
+            AlignmentMethod::DynamicTimeWarping { window: None, iterations: 2 },
+            AlignmentMethod::Registration { knots: 4, lambda: 0.01, iterations: 2 },
+        ];
+
+        for method in methods {
+            let results = RepetitionCalculator::analyse(&signal, &repetitions, &method, 50);
+            assert_eq!(results.summary.repetitions[0], 2);
+            assert!(results.summary.aligned_sti[0].abs() < 1e-6, "{}: aligned STI {}", method.name(), results.summary.aligned_sti[0]);
+
+            for label in ["1", "2"] {
+                let h: Vec<f64> = (0..results.warping.repetition.len())
+                    .filter(|&k| results.warping.repetition[k] == label)
+                    .map(|k| results.warping.warped_time[k])
+                    .collect();
+                assert_eq!(h.len(), 50);
+                assert!(h[0].abs() < 1e-9 && (h[49] - 1.0).abs() < 1e-9, "{}: h(0) = {}, h(1) = {}", method.name(), h[0], h[49]);
+                assert!(h.windows(2).all(|w| w[1] >= w[0]), "{}: warp isn't monotone", method.name());
+            }
+        }
+    }
+}
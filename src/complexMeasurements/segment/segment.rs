@@ -0,0 +1,164 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Segment summaries
+
+    Per segment statistics of any MOSAIC measure (named measures, areas, curve descriptors, SpeechUMD features,
+    all as `Signal`s) for the statistics people actually report, i.e. lip aperture over each vowel together with
+    F1 / F2 at the vowel midpoint.
+
+    Tidy table: one row per segment per measure.
+
+        - samples: frames of the measure inside [start, end]
+        - mean / min / max: over those frames (NaN when the segment is shorter than a frame)
+        - midpoint: interpolated at (start + end) / 2, so short segments still get a value. NaN when the frames
+          either side of the midpoint are more than one analysis step apart (i.e. the midpoint is unvoiced)
+
+    Kinematic and acoustic measures are summarised over the same segments and at the same midpoint, so they can
+    be paired row by row. The SpeechUMD should be synced first (`praatAnalysis::sync`).
+*/
+
+use crate::annotation::segments::segments::{Segments};
+use crate::complexMeasurements::signal::signal::{Signal};
+use polars::prelude::*;
+use std::fs::File;
+
+// one measure summarised over one segment
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentStats {
+    pub samples: u32,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    pub midpoint: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SegmentSummary {
+    pub trial: Vec<String>,
+    pub segment_id: Vec<u32>,
+    pub tier: Vec<String>,
+    pub label: Vec<String>,
+    pub start: Vec<f64>, // s
+    pub end: Vec<f64>,
+    pub measure: Vec<String>,
+    pub samples: Vec<u32>,
+    pub mean: Vec<f64>,
+    pub min: Vec<f64>,
+    pub max: Vec<f64>,
+    pub midpoint: Vec<f64>,
+}
+
+impl SegmentSummary {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            trial: Vec::with_capacity(estimated_entries),
+            segment_id: Vec::with_capacity(estimated_entries),
+            tier: Vec::with_capacity(estimated_entries),
+            label: Vec::with_capacity(estimated_entries),
+            start: Vec::with_capacity(estimated_entries),
+            end: Vec::with_capacity(estimated_entries),
+            measure: Vec::with_capacity(estimated_entries),
+            samples: Vec::with_capacity(estimated_entries),
+            mean: Vec::with_capacity(estimated_entries),
+            min: Vec::with_capacity(estimated_entries),
+            max: Vec::with_capacity(estimated_entries),
+            midpoint: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    // segment k of `segments`
+    pub fn add_point(
+        &mut self, trial: String, segments: &Segments, k: usize, measure: String, stats: SegmentStats
+    ) {
+        self.trial.push(trial);
+        self.segment_id.push(segments.segment_id[k]);
+        self.tier.push(segments.tier[k].clone());
+        self.label.push(segments.label[k].clone());
+        self.start.push(segments.start[k]);
+        self.end.push(segments.end[k]);
+        self.measure.push(measure);
+        self.samples.push(stats.samples);
+        self.mean.push(stats.mean);
+        self.min.push(stats.min);
+        self.max.push(stats.max);
+        self.midpoint.push(stats.midpoint);
+    }
+
+    pub fn save_segment_summary_to_parquet(data: &SegmentSummary, file_path: &str) -> PolarsResult<()> {
+        let s_trial = Series::new("trial", &data.trial);
+        let s_id = Series::new("segment_id", &data.segment_id);
+        let s_tier = Series::new("tier", &data.tier);
+        let s_label = Series::new("label", &data.label);
+        let s_start = Series::new("start", &data.start);
+        let s_end = Series::new("end", &data.end);
+        let s_measure = Series::new("measure", &data.measure);
+        let s_samples = Series::new("samples", &data.samples);
+        let s_mean = Series::new("mean", &data.mean);
+        let s_min = Series::new("min", &data.min);
+        let s_max = Series::new("max", &data.max);
+        let s_midpoint = Series::new("midpoint", &data.midpoint);
+
+        let mut df = DataFrame::new(vec![
+            s_trial, s_id, s_tier, s_label, s_start, s_end,
+            s_measure, s_samples, s_mean, s_min, s_max, s_midpoint,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported segment summary data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct SegmentSummaryCalculator;
+
+impl SegmentSummaryCalculator {
+    // `tiers` limits the segments summarised (i.e. only "phones"), None for every tier
+    pub fn summarise(trial: &str, segments: &Segments, tiers: Option<&[&str]>, measures: &[Signal]) -> SegmentSummary {
+        let mut summary = SegmentSummary::construction(segments.len() * measures.len());
+
+        for k in 0..segments.len() {
+            if let Some(tiers) = tiers && !tiers.contains(&segments.tier[k].as_str()) { continue; }
+            let (start, end) = (segments.start[k], segments.end[k]);
+
+            for measure in measures {
+                let window = measure.window(start, end);
+                // 1.5 steps so timestamp rounding isn't read as a gap
+                let max_gap = 1.5 / measure.sample_rate();
+                let (mean, min, max) = if window.is_empty() {
+                    (f64::NAN, f64::NAN, f64::NAN)
+                } else {
+                    (
+                        window.value.iter().sum::<f64>() / window.len() as f64,
+                        window.value.iter().copied().fold(f64::INFINITY, f64::min),
+                        window.value.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                    )
+                };
+
+                summary.add_point(trial.to_string(), segments, k, measure.label.clone(), SegmentStats {
+                    samples: window.len() as u32,
+                    mean,
+                    min,
+                    max,
+                    midpoint: measure.interpolate_within(0.5 * (start + end), max_gap),
+                });
+            }
+        }
+
+        summary
+    }
+}
@@ -0,0 +1,146 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    DSP helpers
+
+    FFT (radix-2, Bluestein for any other length), analytic signal (Hilbert transform) and phase helpers.
+    Written here rather than pulling in an fft crate as the signals we deal with are small.
+*/
+
+use nalgebra::{Complex};
+use std::f64::consts::PI;
+
+// in place DFT of any length. The inverse is scaled by 1/n
+pub fn fft(data: &mut [Complex<f64>], inverse: bool) {
+    let n = data.len();
+    if n <= 1 { return; }
+
+    if n.is_power_of_two() {
+        radix2(data, inverse);
+    } else {
+        bluestein(data, inverse);
+    }
+
+    if inverse {
+        let scale = 1.0 / n as f64;
+        for value in data.iter_mut() { *value *= scale; }
+    }
+}
+
+// iterative cooley-tukey (unscaled)
+fn radix2(data: &mut [Complex<f64>], inverse: bool) {
+    let n = data.len();
+
+    // bit reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j { data.swap(i, j); }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f64;
+        let w_len = Complex::new(angle.cos(), angle.sin());
+        for start in (0..n).step_by(len) {
+            let mut w = Complex::new(1.0, 0.0);
+            for k in 0..len / 2 {
+                let u = data[start + k];
+                let v = data[start + k + len / 2] * w;
+                data[start + k] = u + v;
+                data[start + k + len / 2] = u - v;
+                w *= w_len;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// chirp z-transform: jk = (j^2 + k^2 - (k - j)^2) / 2 turns the DFT into a convolution (unscaled)
+fn bluestein(data: &mut [Complex<f64>], inverse: bool) {
+    let n = data.len();
+    let m = (2 * n - 1).next_power_of_two();
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let zero = Complex::new(0.0, 0.0);
+
+    // k^2 mod 2n keeps the angle small for long signals
+    let chirp: Vec<Complex<f64>> = (0..n).map(|k| {
+        let angle = sign * PI * ((k * k) % (2 * n)) as f64 / n as f64;
+        Complex::new(angle.cos(), angle.sin())
+    }).collect();
+
+    let mut a = vec![zero; m];
+    for k in 0..n { a[k] = data[k] * chirp[k]; }
+
+    let mut b = vec![zero; m];
+    b[0] = chirp[0].conj();
+    for k in 1..n {
+        b[k] = chirp[k].conj();
+        b[m - k] = chirp[k].conj();
+    }
+
+    radix2(&mut a, false);
+    radix2(&mut b, false);
+    for k in 0..m { a[k] *= b[k]; }
+    radix2(&mut a, true);
+
+    let scale = 1.0 / m as f64;
+    for k in 0..n { data[k] = a[k] * chirp[k] * scale; }
+}
+
+// analytic signal x + i H(x)
+pub fn analytic_signal(values: &[f64]) -> Vec<Complex<f64>> {
+    let n = values.len();
+    let mut spectrum: Vec<Complex<f64>> = values.iter().map(|v| Complex::new(*v, 0.0)).collect();
+    fft(&mut spectrum, false);
+
+    // keep DC (and nyquist), double the positive frequencies, remove the negative ones
+    for (k, value) in spectrum.iter_mut().enumerate() {
+        let gain = if k == 0 || (n.is_multiple_of(2) && k == n / 2) { 1.0 }
+            else if k < n.div_ceil(2) { 2.0 }
+            else { 0.0 };
+        *value *= gain;
+    }
+
+    fft(&mut spectrum, true);
+    spectrum
+}
+
+// wrap to (-pi, pi]
+pub fn wrap_angle(angle: f64) -> f64 {
+    let wrapped = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped == -PI { PI } else { wrapped }
+}
+
+// circular mean of angles (rads)
+pub fn circular_mean(angles: &[f64]) -> f64 {
+    let (sin, cos) = angles.iter().fold((0.0, 0.0), |acc, a| (acc.0 + a.sin(), acc.1 + a.cos()));
+    sin.atan2(cos)
+}
+
+// circular standard deviation sqrt(-2 ln R) of angles (rads), R is the mean resultant length
+pub fn circular_sd(angles: &[f64]) -> f64 {
+    if angles.is_empty() { return f64::NAN; }
+    let (sin, cos) = angles.iter().fold((0.0, 0.0), |acc, a| (acc.0 + a.sin(), acc.1 + a.cos()));
+    let r = (sin.powi(2) + cos.powi(2)).sqrt() / angles.len() as f64;
+    (-2.0 * r.min(1.0).ln()).sqrt()
+}
@@ -0,0 +1,207 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Signal
+
+    A single MOSAIC time series (landmark coordinate, euclidean distance, area, named measure or SpeechUMD
+    feature) so the complex measurements can work on any of them. Frames where the measurement is missing (or NaN)
+    are left out rather than filled in.
+*/
+
+use crate::UMD::UMD::{UMD};
+use crate::coreMeasurements::euclidean::euclidean::{CoreEuclidean};
+use crate::coreMeasurements::area::area::{CoreArea};
+use crate::coreMeasurements::named::named::{CoreNamedMeasure};
+use crate::praatAnalysis::speechUMD::speechUMD::{SpeechUMD};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Debug, Clone)]
+pub struct Signal {
+    pub label: String,
+    pub frame: Vec<u32>,
+    pub timestamp: Vec<f64>,
+    pub value: Vec<f64>,
+}
+
+impl Signal {
+    pub fn new(label: String) -> Self {
+        Self { label, frame: Vec::new(), timestamp: Vec::new(), value: Vec::new() }
+    }
+
+    pub fn push(&mut self, frame: u32, timestamp: f64, value: f64) {
+        if value.is_nan() { return; }
+        self.frame.push(frame);
+        self.timestamp.push(timestamp);
+        self.value.push(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.value.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    // pose corrected coordinate of one landmark
+    pub fn from_landmark(umd: &UMD, landmark: &str, axis: Axis) -> Self {
+        let label = format!("{}_{}", landmark, match axis { Axis::X => "x", Axis::Y => "y", Axis::Z => "z" });
+        let mut signal = Self::new(label);
+
+        let mut last_frame = None;
+        for i in 0..umd.frame.len() {
+            // first occurrence per frame (same as FrameIndex)
+            if umd.types[i] != landmark || last_frame == Some(umd.frame[i]) { continue; }
+            last_frame = Some(umd.frame[i]);
+
+            let value = match axis {
+                Axis::X => umd.x_rotated[i],
+                Axis::Y => umd.y_rotated[i],
+                Axis::Z => umd.z_rotated[i],
+            };
+            signal.push(umd.frame[i], umd.timestamp[i] as f64, value);
+        }
+        signal
+    }
+
+    // distance between two points ("origin" for the origin)
+    pub fn from_euclidean(data: &CoreEuclidean, point_1: &str, point_2: &str) -> Self {
+        let mut signal = Self::new(format!("{}->{}", point_1, point_2));
+        for i in 0..data.frame.len() {
+            if data.coordinate_type_1[i] == point_1 && data.coordinate_type_2[i] == point_2 {
+                signal.push(data.frame[i], data.timestamp[i] as f64, data.r[i]);
+            }
+        }
+        signal
+    }
+
+    // total area of one area set (skipped frames are left out)
+    pub fn from_area(data: &CoreArea, types_included: &str) -> Self {
+        let mut signal = Self::new(format!("area({})", types_included));
+        for i in 0..data.frame.len() {
+            if data.types_included[i] == types_included && data.status[i] == "ok" {
+                signal.push(data.frame[i], data.timestamp[i] as f64, data.total_area[i]);
+            }
+        }
+        signal
+    }
+
+    pub fn from_named(data: &CoreNamedMeasure, measure: &str) -> Self {
+        let mut signal = Self::new(measure.to_string());
+        for i in 0..data.frame.len() {
+            if data.measure[i] == measure {
+                signal.push(data.frame[i], data.timestamp[i] as f64, data.value[i]);
+            }
+        }
+        signal
+    }
+
+    // one SpeechUMD column (unvoiced / undefined frames are left out). Unknown features give an empty signal
+    pub fn from_speech(data: &SpeechUMD, feature: &str) -> Self {
+        let mut signal = Self::new(feature.to_string());
+        let values = match feature {
+            "f0" => &data.f0,
+            "intensity" => &data.intensity,
+            "f1" => &data.f1,
+            "f2" => &data.f2,
+            "f3" => &data.f3,
+            "f4" => &data.f4,
+            "hnr" => &data.hnr,
+            "spectral_cog" => &data.spectral_cog,
+            "spectral_sd" => &data.spectral_sd,
+            "spectral_skewness" => &data.spectral_skewness,
+            "spectral_kurtosis" => &data.spectral_kurtosis,
+            _ => return signal,
+        };
+        for ((frame, timestamp), value) in data.frame.iter().zip(&data.timestamp).zip(values) {
+            signal.push(*frame, *timestamp as f64, *value);
+        }
+        signal
+    }
+
+    // samples with start <= timestamp <= end (i.e. one repetition or one trial)
+    pub fn window(&self, start: f64, end: f64) -> Self {
+        let mut signal = Self::new(self.label.clone());
+        for i in 0..self.len() {
+            if self.timestamp[i] >= start && self.timestamp[i] <= end {
+                signal.push(self.frame[i], self.timestamp[i], self.value[i]);
+            }
+        }
+        signal
+    }
+
+    // linear interpolation (NaN outside the signal)
+    pub fn interpolate(&self, t: f64) -> f64 {
+        let n = self.len();
+        if n == 0 || t < self.timestamp[0] || t > self.timestamp[n - 1] { return f64::NAN; }
+
+        let k = self.timestamp.partition_point(|&ts| ts < t);
+        if k == 0 { return self.value[0]; }
+        let (t0, t1) = (self.timestamp[k - 1], self.timestamp[k]);
+        if t1 <= t0 { return self.value[k]; }
+        let w = (t - t0) / (t1 - t0);
+        self.value[k - 1] * (1.0 - w) + self.value[k] * w
+    }
+
+    // linear interpolation that is NaN when the samples either side of t are more than max_gap apart, i.e. t
+    // falls in a run of missing frames (an unvoiced stretch of f0, undefined formants)
+    pub fn interpolate_within(&self, t: f64, max_gap: f64) -> f64 {
+        let n = self.len();
+        if n == 0 || t < self.timestamp[0] || t > self.timestamp[n - 1] { return f64::NAN; }
+
+        let k = self.timestamp.partition_point(|&ts| ts < t);
+        if self.timestamp[k] == t { return self.value[k]; }
+        if self.timestamp[k] - self.timestamp[k - 1] > max_gap { return f64::NAN; }
+        self.interpolate(t)
+    }
+
+    // linear time normalisation to `points` equally spaced samples from the first to the last timestamp
+    pub fn time_normalise(&self, points: usize) -> Vec<f64> {
+        let n = self.len();
+        if n == 0 || points == 0 { return Vec::new(); }
+        if points == 1 || n == 1 { return vec![self.value[0]; points]; }
+
+        let (start, end) = (self.timestamp[0], self.timestamp[n - 1]);
+        (0..points).map(|k| {
+            let t = start + (end - start) * k as f64 / (points - 1) as f64;
+            self.interpolate(t)
+        }).collect()
+    }
+
+    // median sample rate (Hz)
+    pub fn sample_rate(&self) -> f64 {
+        let mut dt: Vec<f64> = self.timestamp.windows(2).map(|w| w[1] - w[0]).filter(|d| *d > 0.0).collect();
+        if dt.is_empty() { return f64::NAN; }
+        dt.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        1.0 / dt[dt.len() / 2]
+    }
+}
+
+// amplitude normalisation (zero mean, unit standard deviation)
+pub fn z_normalise(values: &[f64]) -> Vec<f64> {
+    let n = values.len() as f64;
+    if values.len() < 2 { return vec![0.0; values.len()]; }
+    let mean = values.iter().sum::<f64>() / n;
+    let sd = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    if sd <= 0.0 { return vec![0.0; values.len()]; }
+    values.iter().map(|v| (v - mean) / sd).collect()
+}
@@ -0,0 +1,279 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Working space
+
+    Per trial range of motion summaries of each landmark trajectory (pose corrected coordinates):
+
+        - range along each axis
+        - convex hull area (x/y) and volume (x/y/z)
+        - 95% confidence ellipse area (x/y) and ellipsoid volume (x/y/z)
+        - path length, mean speed and peak speed
+
+    One row per landmark per trial. 3D measures are NaN when the trajectory has no depth (2D data).
+*/
+
+use crate::UMD::UMD::{UMD};
+use crate::coreMeasurements::area::polygon::{PolygonCalculator};
+use nalgebra::{Matrix2, Matrix3, Vector3};
+use polars::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+
+// chi squared quantiles at 0.95
+const CHI2_95_2D: f64 = 5.991464547107979;
+const CHI2_95_3D: f64 = 7.814727903251178;
+
+#[derive(Debug, Clone)]
+pub struct CoreWorkspace {
+    pub trial: Vec<String>,
+    pub landmark: Vec<String>,
+    pub samples: Vec<u32>,
+    pub range_x: Vec<f64>,
+    pub range_y: Vec<f64>,
+    pub range_z: Vec<f64>,
+    pub hull_area: Vec<f64>,
+    pub hull_volume: Vec<f64>,
+    pub ellipse_area: Vec<f64>,
+    pub ellipsoid_volume: Vec<f64>,
+    pub path_length: Vec<f64>,
+    pub duration: Vec<f64>,
+    pub mean_speed: Vec<f64>,
+    pub peak_speed: Vec<f64>,
+}
+
+impl CoreWorkspace {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            trial: Vec::with_capacity(estimated_entries),
+            landmark: Vec::with_capacity(estimated_entries),
+            samples: Vec::with_capacity(estimated_entries),
+            range_x: Vec::with_capacity(estimated_entries),
+            range_y: Vec::with_capacity(estimated_entries),
+            range_z: Vec::with_capacity(estimated_entries),
+            hull_area: Vec::with_capacity(estimated_entries),
+            hull_volume: Vec::with_capacity(estimated_entries),
+            ellipse_area: Vec::with_capacity(estimated_entries),
+            ellipsoid_volume: Vec::with_capacity(estimated_entries),
+            path_length: Vec::with_capacity(estimated_entries),
+            duration: Vec::with_capacity(estimated_entries),
+            mean_speed: Vec::with_capacity(estimated_entries),
+            peak_speed: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn save_workspace_to_parquet(data: &CoreWorkspace, file_path: &str) -> PolarsResult<()> {
+        let s_trial = Series::new("trial", &data.trial);
+        let s_landmark = Series::new("landmark", &data.landmark);
+        let s_samples = Series::new("samples", &data.samples);
+        let s_range_x = Series::new("range_x", &data.range_x);
+        let s_range_y = Series::new("range_y", &data.range_y);
+        let s_range_z = Series::new("range_z", &data.range_z);
+        let s_hull_area = Series::new("hull_area", &data.hull_area);
+        let s_hull_volume = Series::new("hull_volume", &data.hull_volume);
+        let s_ellipse = Series::new("ellipse_area", &data.ellipse_area);
+        let s_ellipsoid = Series::new("ellipsoid_volume", &data.ellipsoid_volume);
+        let s_path = Series::new("path_length", &data.path_length);
+        let s_duration = Series::new("duration", &data.duration);
+        let s_mean_speed = Series::new("mean_speed", &data.mean_speed);
+        let s_peak_speed = Series::new("peak_speed", &data.peak_speed);
+
+        let mut df = DataFrame::new(vec![
+            s_trial, s_landmark, s_samples,
+            s_range_x, s_range_y, s_range_z,
+            s_hull_area, s_hull_volume, s_ellipse, s_ellipsoid,
+            s_path, s_duration, s_mean_speed, s_peak_speed,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported working space data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct WorkspaceCalculator;
+
+impl WorkspaceCalculator {
+    // window is (start, end) in seconds, None for the whole recording
+    pub fn calculate(umd: &UMD, trial: &str, window: Option<(f64, f64)>) -> CoreWorkspace {
+        // sample indices of each landmark (first occurrence per frame, in order of appearance)
+        let mut order: Vec<String> = Vec::new();
+        let mut samples: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut last_frame: HashMap<String, u32> = HashMap::new();
+        for i in 0..umd.frame.len() {
+            let t = umd.timestamp[i] as f64;
+            if let Some((start, end)) = window && (t < start || t > end) { continue; }
+            let label = &umd.types[i];
+            if last_frame.get(label) == Some(&umd.frame[i]) { continue; }
+            if umd.x_rotated[i].is_nan() || umd.y_rotated[i].is_nan() || umd.z_rotated[i].is_nan() { continue; }
+            last_frame.insert(label.clone(), umd.frame[i]);
+
+            samples.entry(label.clone()).or_insert_with(|| {
+                order.push(label.clone());
+                Vec::new()
+            }).push(i);
+        }
+
+        let mut workspace_data = CoreWorkspace::construction(order.len());
+        for label in &order {
+            let indices = &samples[label];
+            let points: Vec<Vector3<f64>> = indices.iter()
+                .map(|&i| Vector3::new(umd.x_rotated[i], umd.y_rotated[i], umd.z_rotated[i]))
+                .collect();
+            let timestamps: Vec<f64> = indices.iter().map(|&i| umd.timestamp[i] as f64).collect();
+            Self::summarise(&mut workspace_data, trial, label, &points, &timestamps);
+        }
+        workspace_data
+    }
+
+    fn summarise(data: &mut CoreWorkspace, trial: &str, landmark: &str, points: &[Vector3<f64>], timestamps: &[f64]) {
+        let n = points.len();
+
+        let mut min = Vector3::repeat(f64::MAX);
+        let mut max = Vector3::repeat(f64::MIN);
+        for p in points {
+            min = min.inf(p);
+            max = max.sup(p);
+        }
+        let range = if n > 0 { max - min } else { Vector3::repeat(f64::NAN) };
+        let has_depth = n > 0 && range.z > 1e-12;
+
+        // hull area / volume
+        let planar: Vec<(f64, f64)> = points.iter().map(|p| (p.x, p.y)).collect();
+        let hull = PolygonCalculator::convex_hull_2d(&planar);
+        let hull_area = if hull.len() >= 3 {
+            let polygon: Vec<(f64, f64)> = hull.iter().map(|&k| planar[k]).collect();
+            PolygonCalculator::shoelace(&polygon).abs()
+        } else if n > 0 { 0.0 } else { f64::NAN };
+        let hull_volume = if has_depth { Self::convex_hull_volume(points) } else { f64::NAN };
+
+        // confidence ellipse / ellipsoid
+        let (ellipse_area, ellipsoid_volume) = if n >= 3 {
+            let mean = points.iter().fold(Vector3::zeros(), |acc, p| acc + p) / n as f64;
+            let covariance = points.iter()
+                .fold(Matrix3::zeros(), |acc, p| acc + (p - mean) * (p - mean).transpose()) / (n - 1) as f64;
+            let planar_covariance = Matrix2::new(covariance[(0, 0)], covariance[(0, 1)], covariance[(1, 0)], covariance[(1, 1)]);
+
+            let ellipse = std::f64::consts::PI * CHI2_95_2D * planar_covariance.determinant().max(0.0).sqrt();
+            let ellipsoid = if has_depth {
+                4.0 / 3.0 * std::f64::consts::PI * CHI2_95_3D.powf(1.5) * covariance.determinant().max(0.0).sqrt()
+            } else { f64::NAN };
+            (ellipse, ellipsoid)
+        } else { (f64::NAN, f64::NAN) };
+
+        // path length and speed
+        let path_length: f64 = points.windows(2).map(|w| (w[1] - w[0]).norm()).sum();
+        let duration = if n > 0 { timestamps[n - 1] - timestamps[0] } else { f64::NAN };
+        let mean_speed = if duration > 0.0 { path_length / duration } else { f64::NAN };
+        let peak_speed = points.windows(2).zip(timestamps.windows(2))
+            .filter(|(_, t)| t[1] > t[0])
+            .map(|(p, t)| (p[1] - p[0]).norm() / (t[1] - t[0]))
+            .fold(f64::NAN, f64::max);
+
+        data.trial.push(trial.to_string());
+        data.landmark.push(landmark.to_string());
+        data.samples.push(n as u32);
+        data.range_x.push(range.x);
+        data.range_y.push(range.y);
+        data.range_z.push(range.z);
+        data.hull_area.push(hull_area);
+        data.hull_volume.push(hull_volume);
+        data.ellipse_area.push(ellipse_area);
+        data.ellipsoid_volume.push(ellipsoid_volume);
+        data.path_length.push(path_length);
+        data.duration.push(duration);
+        data.mean_speed.push(mean_speed);
+        data.peak_speed.push(peak_speed);
+    }
+
+    // incremental 3D convex hull, returns the enclosed volume (0 when every point is coplanar)
+    pub fn convex_hull_volume(points: &[Vector3<f64>]) -> f64 {
+        let Some((initial, eps)) = Self::initial_tetrahedron(points) else { return 0.0 };
+        let interior = initial.iter().fold(Vector3::zeros(), |acc, &k| acc + points[k]) / 4.0;
+
+        // faces are counter-clockwise seen from outside
+        let [a, b, c, d] = initial;
+        let mut faces: Vec<[usize; 3]> = [[a, b, c], [a, b, d], [a, c, d], [b, c, d]].iter()
+            .map(|&[i, j, k]| {
+                let normal = (points[j] - points[i]).cross(&(points[k] - points[i]));
+                if normal.dot(&(interior - points[i])) > 0.0 { [i, k, j] } else { [i, j, k] }
+            })
+            .collect();
+
+        for p in 0..points.len() {
+            if initial.contains(&p) { continue; }
+
+            let visible: Vec<bool> = faces.iter().map(|&[i, j, k]| {
+                let normal = (points[j] - points[i]).cross(&(points[k] - points[i]));
+                normal.dot(&(points[p] - points[i])) > eps * normal.norm()
+            }).collect();
+            if !visible.iter().any(|v| *v) { continue; }
+
+            // horizon: edges of visible faces whose neighbouring face is not visible
+            let visible_edges: HashSet<(usize, usize)> = faces.iter().zip(&visible)
+                .filter(|(_, v)| **v)
+                .flat_map(|(&[i, j, k], _)| [(i, j), (j, k), (k, i)])
+                .collect();
+            let horizon: Vec<(usize, usize)> = visible_edges.iter()
+                .filter(|(i, j)| !visible_edges.contains(&(*j, *i)))
+                .cloned()
+                .collect();
+
+            let mut kept: Vec<[usize; 3]> = faces.iter().zip(&visible)
+                .filter(|(_, v)| !**v)
+                .map(|(f, _)| *f)
+                .collect();
+            kept.extend(horizon.iter().map(|&(i, j)| [i, j, p]));
+            faces = kept;
+        }
+
+        faces.iter()
+            .map(|&[i, j, k]| (points[i] - interior).dot(&(points[j] - interior).cross(&(points[k] - interior))) / 6.0)
+            .sum::<f64>()
+            .abs()
+    }
+
+    // 4 non-coplanar points and a distance tolerance scaled to the data
+    fn initial_tetrahedron(points: &[Vector3<f64>]) -> Option<([usize; 4], f64)> {
+        if points.len() < 4 { return None; }
+
+        let mut min = Vector3::repeat(f64::MAX);
+        let mut max = Vector3::repeat(f64::MIN);
+        for p in points { min = min.inf(p); max = max.sup(p); }
+        let eps = 1e-9 * (max - min).norm().max(1e-12);
+
+        let a = 0;
+        let b = (0..points.len()).max_by(|&i, &j| {
+            (points[i] - points[a]).norm().partial_cmp(&(points[j] - points[a]).norm()).unwrap()
+        })?;
+        let ab = points[b] - points[a];
+        if ab.norm() <= eps { return None; }
+
+        let c = (0..points.len()).max_by(|&i, &j| {
+            ab.cross(&(points[i] - points[a])).norm().partial_cmp(&ab.cross(&(points[j] - points[a])).norm()).unwrap()
+        })?;
+        let normal = ab.cross(&(points[c] - points[a]));
+        if normal.norm() <= eps * ab.norm() { return None; }
+
+        let d = (0..points.len()).max_by(|&i, &j| {
+            normal.dot(&(points[i] - points[a])).abs().partial_cmp(&normal.dot(&(points[j] - points[a])).abs()).unwrap()
+        })?;
+        if normal.dot(&(points[d] - points[a])).abs() <= eps * normal.norm() { return None; }
+
+        Some(([a, b, c, d], eps))
+    }
+}
@@ -14,8 +14,11 @@ MOSAIC. If not, see <https://www.gnu.org/licenses/>.
 */
 
 use crate::UMD::UMD::{UMD};
+use crate::coreMeasurements::pairs::pairs::{PairSpec, FrameIndex};
 //use crate::coreMeasurements::euclidean::{CoreEuclidean};
+use nalgebra::{Vector3};
 use polars::prelude::*;
+use std::f64::consts::FRAC_PI_2;
 use std::fs::File;
 
 #[derive(Debug, Clone)]
@@ -73,8 +76,7 @@ impl CoreAngle {
         &mut self, frame: u32, timestamp: f32, 
         coord_1: (u32, String, f64, f64, f64),
         coord_2: (u32, String, f64, f64, f64),
-        theta: f64, theta_uncertainty: f64, 
-        phi: f64, phi_uncertainty: f64
+        theta: (f64, f64), phi: (f64, f64)
     ) {
         self.frame.push(frame);
         self.timestamp.push(timestamp);
@@ -91,10 +93,11 @@ impl CoreAngle {
         self.y2.push(coord_2.3);
         self.z2.push(coord_2.4);
 
-        self.theta.push(theta);
-        self.theta_uncertainty.push(theta_uncertainty);
-        self.phi.push(phi);
-        self.phi_uncertainty.push(phi_uncertainty);
+        // (value, uncertainty)
+        self.theta.push(theta.0);
+        self.theta_uncertainty.push(theta.1);
+        self.phi.push(phi.0);
+        self.phi_uncertainty.push(phi.1);
     }
 
     pub fn save_angle_to_parquet(angle: &CoreAngle, file_path: &str) -> PolarsResult<()> {
@@ -133,24 +136,110 @@ impl CoreAngle {
     }
 }
 
+// JOINT ANGLES
+// the included angle at a vertex (3 points), the angle between 2 segments, or the angle between a segment and a plane
+
+#[derive(Debug, Clone, Copy)]
+pub enum JointAngleSpec {
+    // angle at the middle landmark, i.e. [commissure, philtrum, commissure]
+    Vertex([&'static str; 3]),
+    // angle between segment 1 -> 2 and segment 3 -> 4
+    Segments([&'static str; 2], [&'static str; 2]),
+    // angle between segment 1 -> 2 and the plane through 3 landmarks (0 = in the plane, pi/2 = along the normal)
+    SegmentPlane([&'static str; 2], [&'static str; 3]),
+}
+
+impl JointAngleSpec {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Vertex(_) => "vertex",
+            Self::Segments(_, _) => "segments",
+            Self::SegmentPlane(_, _) => "segment_plane",
+        }
+    }
+
+    pub fn landmarks(&self) -> Vec<&'static str> {
+        match self {
+            Self::Vertex(points) => points.to_vec(),
+            Self::Segments(s1, s2) => vec![s1[0], s1[1], s2[0], s2[1]],
+            Self::SegmentPlane(s, plane) => vec![s[0], s[1], plane[0], plane[1], plane[2]],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CoreJointAngle {
+    pub frame: Vec<u32>,
+    pub timestamp: Vec<f32>,
+    pub kind: Vec<String>,
+    pub types_included: Vec<String>,
+
+    // angle (rads)
+    pub angle: Vec<f64>,
+    pub angle_uncertainty: Vec<f64>,
+}
+
+impl CoreJointAngle {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            frame: Vec::with_capacity(estimated_entries),
+            timestamp: Vec::with_capacity(estimated_entries),
+            kind: Vec::with_capacity(estimated_entries),
+            types_included: Vec::with_capacity(estimated_entries),
+            angle: Vec::with_capacity(estimated_entries),
+            angle_uncertainty: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(
+        &mut self, frame: u32, timestamp: f32, kind: String,
+        types_included: String, angle: f64, angle_uncertainty: f64
+    ) {
+        self.frame.push(frame);
+        self.timestamp.push(timestamp);
+        self.kind.push(kind);
+        self.types_included.push(types_included);
+        self.angle.push(angle);
+        self.angle_uncertainty.push(angle_uncertainty);
+    }
+
+    pub fn save_joint_angle_to_parquet(angle: &CoreJointAngle, file_path: &str) -> PolarsResult<()> {
+        let s_frame = Series::new("frame", &angle.frame);
+        let s_time = Series::new("timestamp", &angle.timestamp);
+        let s_kind = Series::new("kind", &angle.kind);
+        let s_types = Series::new("types_included", &angle.types_included);
+        let s_angle = Series::new("angle", &angle.angle);
+        let s_angle_uncertainty = Series::new("angle_uncertainty", &angle.angle_uncertainty);
+
+        let mut df = DataFrame::new(vec![
+            s_frame, s_time, s_kind, s_types, s_angle, s_angle_uncertainty,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported joint angle data to: {}", file_path);
+        Ok(())
+    }
+}
+
 pub struct AngleCalculator;
 
 struct ThetaCalc;
 struct PhiCalc;
+struct JointCalc;
 
 impl AngleCalculator {
 
-    pub fn angle(umd: &UMD, pairs: &[String; 2]) -> CoreAngle {
+    pub fn angle(umd: &UMD, pairs: &[PairSpec]) -> CoreAngle {
         let total_points = umd.frame.len();
         if total_points == 0 {
             return CoreAngle::construction(0);
         }
 
         let mut angle_data = CoreAngle::construction(total_points / 68);
-        
-        let p1_is_origin = pairs[0].to_lowercase() == "origin";
-        let target_all = pairs[1] == "*";
+        let mut frame_pairs: Vec<(Option<usize>, usize)> = Vec::new();
 
+        // every pair spec is resolved in the same pass over the frames
         let mut i = 0;
         while i < total_points {
             let start_idx = i;
@@ -162,39 +251,16 @@ impl AngleCalculator {
             }
             let end_idx = i;
 
-            if p1_is_origin {
-                if target_all {
-                    for k in start_idx..end_idx {
-                        Self::process_with_origin(&mut angle_data, umd, k);
-                    }
-                } else {
-                    for k in start_idx..end_idx {
-                        if &umd.types[k] == &pairs[1] {
-                            Self::process_with_origin(&mut angle_data, umd, k);
-                        }
-                    }
-                }
-            } else {
-                let mut p1_idx = None;
-                for k in start_idx..end_idx {
-                    if &umd.types[k] == &pairs[0] {
-                        p1_idx = Some(k);
-                        break;
-                    }
-                }
+            let index = FrameIndex::new(umd, start_idx, end_idx);
+            frame_pairs.clear();
+            for spec in pairs {
+                spec.resolve(umd, &index, &mut frame_pairs);
+            }
 
-                if let Some(idx1) = p1_idx {
-                    if target_all {
-                        for k in start_idx..end_idx {
-                            Self::process_pair(&mut angle_data, umd, idx1, k);
-                        }
-                    } else {
-                        for k in start_idx..end_idx {
-                            if &umd.types[k] == &pairs[1] {
-                                Self::process_pair(&mut angle_data, umd, idx1, k);
-                            }
-                        }
-                    }
+            for &(idx1, idx2) in &frame_pairs {
+                match idx1 {
+                    None => Self::process_with_origin(&mut angle_data, umd, idx2),
+                    Some(idx1) => Self::process_pair(&mut angle_data, umd, idx1, idx2),
                 }
             }
         }
@@ -202,6 +268,69 @@ impl AngleCalculator {
         angle_data
     }
 
+    pub fn joint_angle(umd: &UMD, specs: &[JointAngleSpec]) -> CoreJointAngle {
+        let total_points = umd.frame.len();
+        if total_points == 0 {
+            return CoreJointAngle::construction(0);
+        }
+
+        let mut joint_data = CoreJointAngle::construction((total_points / 68 + 1) * specs.len());
+        let identifiers: Vec<String> = specs.iter().map(|s| s.landmarks().join(",")).collect();
+
+        let mut i = 0;
+        while i < total_points {
+            let start_idx = i;
+            let current_frame = umd.frame[i];
+
+            // identify frame block
+            while i < total_points && umd.frame[i] == current_frame {
+                i += 1;
+            }
+            let end_idx = i;
+
+            let index = FrameIndex::new(umd, start_idx, end_idx);
+
+            for (k, spec) in specs.iter().enumerate() {
+                // skip the spec for this frame if any landmark is missing
+                let indices: Option<Vec<usize>> = spec.landmarks().iter().map(|l| index.get(l)).collect();
+                let Some(indices) = indices else { continue; };
+
+                let points: Vec<Vector3<f64>> = indices.iter().map(|&idx| Self::position(umd, idx)).collect();
+                let sigmas: Vec<Vector3<f64>> = indices.iter().map(|&idx| Self::sigma(umd, idx)).collect();
+
+                let (angle, gradients) = match spec {
+                    JointAngleSpec::Vertex(_) => JointCalc::vertex(&points),
+                    JointAngleSpec::Segments(_, _) => JointCalc::segments(&points),
+                    JointAngleSpec::SegmentPlane(_, _) => JointCalc::segment_plane(&points),
+                };
+                let angle_unc = JointCalc::calculate_uncertainty(&gradients, &sigmas);
+
+                joint_data.add_point(
+                    current_frame,
+                    umd.timestamp[start_idx],
+                    spec.kind().to_string(),
+                    identifiers[k].clone(),
+                    angle,
+                    angle_unc,
+                );
+            }
+        }
+
+        joint_data
+    }
+
+    fn position(umd: &UMD, idx: usize) -> Vector3<f64> {
+        Vector3::new(umd.x_rotated[idx], umd.y_rotated[idx], umd.z_rotated[idx])
+    }
+
+    fn sigma(umd: &UMD, idx: usize) -> Vector3<f64> {
+        Vector3::new(
+            if idx < umd.x_rotated_uncertainty.len() { umd.x_rotated_uncertainty[idx] } else { 0.0 },
+            if idx < umd.y_rotated_uncertainty.len() { umd.y_rotated_uncertainty[idx] } else { 0.0 },
+            if idx < umd.z_rotated_uncertainty.len() { umd.z_rotated_uncertainty[idx] } else { 0.0 },
+        )
+    }
+
     fn process_with_origin(angle_data: &mut CoreAngle, umd: &UMD, idx2: usize) {
         let x2 = umd.x_rotated[idx2];
         let y2 = umd.y_rotated[idx2];
@@ -231,7 +360,7 @@ impl AngleCalculator {
             umd.timestamp[idx2],
             (0, "origin".to_string(), 0.0, 0.0, 0.0), 
             (umd.coordinate_number[idx2], umd.types[idx2].clone(), x2, y2, z2),
-            theta, theta_unc, phi, phi_unc
+            (theta, theta_unc), (phi, phi_unc)
         );
     }
 
@@ -287,10 +416,8 @@ impl AngleCalculator {
             umd.timestamp[idx1],
             (umd.coordinate_number[idx1], umd.types[idx1].clone(), x1, y1, z1),
             (umd.coordinate_number[idx2], umd.types[idx2].clone(), x2, y2, z2),
-            theta,
-            theta_unc,
-            phi,
-            phi_unc
+            (theta, theta_unc),
+            (phi, phi_unc)
         );
     }
 }
@@ -341,4 +468,75 @@ impl PhiCalc {
 
         ((d_dx * svx).powi(2) + (d_dy * svy).powi(2) + (d_dz * svz).powi(2)).sqrt()
     }
-}
\ No newline at end of file
+}
+
+impl JointCalc {
+    // included angle between u and v (0 - pi) + the partial derivatives with respect to u and v
+    fn included(u: &Vector3<f64>, v: &Vector3<f64>) -> (f64, Vector3<f64>, Vector3<f64>) {
+        let cross = u.cross(v).norm();
+        let dot = u.dot(v);
+        let angle = cross.atan2(dot);
+
+        let u2 = u.norm_squared();
+        let v2 = v.norm_squared();
+        if cross == 0.0 || u2 == 0.0 || v2 == 0.0 {
+            return (angle, Vector3::zeros(), Vector3::zeros()); // division by 0 error
+        }
+
+        // dTheta/du = -(v - (u.v / |u|^2) u) / |u x v|
+        // dTheta/dv = -(u - (u.v / |v|^2) v) / |u x v|
+        let d_du = -(v - u * (dot / u2)) / cross;
+        let d_dv = -(u - v * (dot / v2)) / cross;
+
+        (angle, d_du, d_dv)
+    }
+
+    // points: [a, vertex, c]
+    fn vertex(points: &[Vector3<f64>]) -> (f64, Vec<Vector3<f64>>) {
+        let u = points[0] - points[1];
+        let v = points[2] - points[1];
+        let (angle, d_du, d_dv) = Self::included(&u, &v);
+
+        (angle, vec![d_du, -(d_du + d_dv), d_dv])
+    }
+
+    // points: [a, b, c, d] for segments a -> b and c -> d
+    fn segments(points: &[Vector3<f64>]) -> (f64, Vec<Vector3<f64>>) {
+        let u = points[1] - points[0];
+        let v = points[3] - points[2];
+        let (angle, d_du, d_dv) = Self::included(&u, &v);
+
+        (angle, vec![-d_du, d_du, -d_dv, d_dv])
+    }
+
+    // points: [a, b, p, q, r] for segment a -> b and the plane through p, q, r
+    fn segment_plane(points: &[Vector3<f64>]) -> (f64, Vec<Vector3<f64>>) {
+        let u = points[1] - points[0];
+        let e1 = points[3] - points[2];
+        let e2 = points[4] - points[2];
+        let n = e1.cross(&e2);
+
+        // the angle to the plane is the complement of the angle to the normal
+        let (to_normal, d_du, d_dn) = Self::included(&u, &n);
+        let angle = (FRAC_PI_2 - to_normal).abs();
+        let sign = if to_normal > FRAC_PI_2 { 1.0 } else { -1.0 };
+
+        let g_u = d_du * sign;
+        let g_n = d_dn * sign;
+
+        // n = e1 x e2 so the normal gradient is passed back onto the plane landmarks
+        let g_p = (e1 - e2).cross(&g_n);
+        let g_q = e2.cross(&g_n);
+        let g_r = g_n.cross(&e1);
+
+        (angle, vec![-g_u, g_u, g_p, g_q, g_r])
+    }
+
+    fn calculate_uncertainty(gradients: &[Vector3<f64>], sigmas: &[Vector3<f64>]) -> f64 {
+        let mut variance = 0.0;
+        for (g, s) in gradients.iter().zip(sigmas) {
+            variance += (g.x * s.x).powi(2) + (g.y * s.y).powi(2) + (g.z * s.z).powi(2);
+        }
+        variance.sqrt()
+    }
+}
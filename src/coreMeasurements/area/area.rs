@@ -22,11 +22,15 @@ Rn kind of catered toward openface lip curves but I wouold love to make it so yo
 the entire shape of the tongue) and get the area from that.
 
 
-I will do uncertainty for this but it is a little scary so I leaving it for a minute
+Uncertainty is propagated analytically: landmark uncertainty -> curve coefficient covariance (see CurveCalculator)
+-> transformed into the basis -> gradient of the bezier signed area (including the movement of the quadrant split
+points). AreaCalculator::monte_carlo_area re-runs the whole pipeline on perturbed landmarks to validate it.
 */
 
-use crate::coreMeasurements::curve::curve::{CoreCurve};
+use crate::coreMeasurements::curve::curve::{CoreCurve, CurveCoefficients};
+use crate::coreMeasurements::contour::contour::{ContourCalculator};
 use crate::UMD::UMD::{UMD};
+use std::collections::HashMap;
 use std::f64::consts::PI;
 use polars::prelude::*;
 use std::fs::File;
@@ -35,11 +39,17 @@ pub struct CoreArea {
     pub frame: Vec<u32>,
     pub timestamp: Vec<f32>,
     pub types_included: Vec<String>,
+    pub status: Vec<String>, // "ok" or the reason the frame was skipped (area columns are NaN)
     pub total_area: Vec<f64>,
     pub q1_area: Vec<f64>,
     pub q2_area: Vec<f64>,
     pub q3_area: Vec<f64>,
     pub q4_area: Vec<f64>,
+    pub total_area_uncertainty: Vec<f64>,
+    pub q1_area_uncertainty: Vec<f64>,
+    pub q2_area_uncertainty: Vec<f64>,
+    pub q3_area_uncertainty: Vec<f64>,
+    pub q4_area_uncertainty: Vec<f64>,
 }
 
 impl CoreArea {
@@ -48,26 +58,68 @@ impl CoreArea {
             frame: Vec::with_capacity(estimated_entries),
             timestamp: Vec::with_capacity(estimated_entries),
             types_included: Vec::with_capacity(estimated_entries),
+            status: Vec::with_capacity(estimated_entries),
             total_area: Vec::with_capacity(estimated_entries),
             q1_area: Vec::with_capacity(estimated_entries),
             q2_area: Vec::with_capacity(estimated_entries),
             q3_area: Vec::with_capacity(estimated_entries),
             q4_area: Vec::with_capacity(estimated_entries),
+            total_area_uncertainty: Vec::with_capacity(estimated_entries),
+            q1_area_uncertainty: Vec::with_capacity(estimated_entries),
+            q2_area_uncertainty: Vec::with_capacity(estimated_entries),
+            q3_area_uncertainty: Vec::with_capacity(estimated_entries),
+            q4_area_uncertainty: Vec::with_capacity(estimated_entries),
         }
     }
 
+    // areas and uncertainties are (total, q1, q2, q3, q4)
+    pub fn add_point(
+        &mut self, frame: u32, timestamp: f32, types_included: String,
+        areas: (f64, f64, f64, f64, f64),
+        uncertainties: (f64, f64, f64, f64, f64),
+    ) {
+        self.frame.push(frame);
+        self.timestamp.push(timestamp);
+        self.types_included.push(types_included);
+        self.status.push("ok".to_string());
+        self.total_area.push(areas.0);
+        self.q1_area.push(areas.1);
+        self.q2_area.push(areas.2);
+        self.q3_area.push(areas.3);
+        self.q4_area.push(areas.4);
+        self.total_area_uncertainty.push(uncertainties.0);
+        self.q1_area_uncertainty.push(uncertainties.1);
+        self.q2_area_uncertainty.push(uncertainties.2);
+        self.q3_area_uncertainty.push(uncertainties.3);
+        self.q4_area_uncertainty.push(uncertainties.4);
+    }
+
+    // frames that could not be measured are kept (as NaN) so they can be told apart from missing frames
+    pub fn add_skipped(&mut self, frame: u32, timestamp: f32, types_included: String, reason: String) {
+        let nan = (f64::NAN, f64::NAN, f64::NAN, f64::NAN, f64::NAN);
+        self.add_point(frame, timestamp, types_included, nan, nan);
+        *self.status.last_mut().unwrap() = reason;
+    }
+
     pub fn save_area_to_parquet(area: &CoreArea, file_path: &str) -> PolarsResult<()> {
         let s_frame = Series::new("frame", &area.frame);
         let s_time = Series::new("timestamp", &area.timestamp);
         let s_types = Series::new("types_included", &area.types_included);
+        let s_status = Series::new("status", &area.status);
         let s_total = Series::new("total_area", &area.total_area);
         let s_q1 = Series::new("q1_area", &area.q1_area);
         let s_q2 = Series::new("q2_area", &area.q2_area);
         let s_q3 = Series::new("q3_area", &area.q3_area);
         let s_q4 = Series::new("q4_area", &area.q4_area);
+        let s_total_unc = Series::new("total_area_uncertainty", &area.total_area_uncertainty);
+        let s_q1_unc = Series::new("q1_area_uncertainty", &area.q1_area_uncertainty);
+        let s_q2_unc = Series::new("q2_area_uncertainty", &area.q2_area_uncertainty);
+        let s_q3_unc = Series::new("q3_area_uncertainty", &area.q3_area_uncertainty);
+        let s_q4_unc = Series::new("q4_area_uncertainty", &area.q4_area_uncertainty);
 
         let mut df = DataFrame::new(vec![
-            s_frame, s_time, s_types, s_total, s_q1, s_q2, s_q3, s_q4
+            s_frame, s_time, s_types, s_status, s_total, s_q1, s_q2, s_q3, s_q4,
+            s_total_unc, s_q1_unc, s_q2_unc, s_q3_unc, s_q4_unc,
         ])?;
 
         let file = File::create(file_path).map_err(PolarsError::from)?;
@@ -76,6 +128,13 @@ impl CoreArea {
     }
 }
 
+// curves of one area set in one frame after the basis transformation
+pub(crate) struct BasisCurves {
+    pub curves: Vec<CubicBezier>,
+    pub covariances: Vec<[[f64; 8]; 8]>, // see transform_covariance
+    pub scale: f64,
+}
+
 pub struct AreaCalculator;
 
 impl AreaCalculator {
@@ -86,7 +145,30 @@ impl AreaCalculator {
         area_sets: &[&[&str]]
     ) -> CoreArea {
         let mut area_data = CoreArea::construction(curve.frame.len());
-        
+
+        Self::transformed_sets(curve, umd, basis_sets, area_sets, |frame, timestamp, types_included, result| {
+            match result {
+                Ok(set) => {
+                    let uncertainties = calculate_area_uncertainty(&set.curves, &set.covariances, set.scale);
+                    let areas = calculate_total_area(set.curves, set.scale);
+                    area_data.add_point(frame, timestamp, types_included, areas, uncertainties);
+                }
+                Err(reason) => area_data.add_skipped(frame, timestamp, types_included, reason),
+            }
+        });
+        area_data
+    }
+
+    // walks the curve/UMD frame blocks and hands every area set its curves in the commissure/philtrum basis
+    // (or the reason the set could not be measured in that frame)
+    pub(crate) fn transformed_sets<F>(
+        curve: &CoreCurve,
+        umd: &UMD,
+        basis_sets: &[&[&str; 4]],
+        area_sets: &[&[&str]],
+        mut f: F
+    )
+    where F: FnMut(u32, f32, String, Result<BasisCurves, String>) {
         let total_curve_entries = curve.frame.len();
         let total_umd_entries = umd.frame.len();
         let mut curve_idx = 0;
@@ -94,7 +176,11 @@ impl AreaCalculator {
 
         while curve_idx < total_curve_entries {
             let current_frame = curve.frame[curve_idx];
-            
+
+            // frames without curves still have UMD entries
+            while umd_idx < total_umd_entries && umd.frame[umd_idx] < current_frame {
+                umd_idx += 1;
+            }
             let umd_start = umd_idx;
             while umd_idx < total_umd_entries && umd.frame[umd_idx] == current_frame {
                 umd_idx += 1;
@@ -109,52 +195,141 @@ impl AreaCalculator {
 
             for (k, set) in area_sets.iter().enumerate() {
                 let basis_names = basis_sets[k];
+                let timestamp = curve.timestamp[curve_start];
 
-                let (mut p_lc, mut p_rc, mut p_lm, mut p_ph) = (Vec3::zero(), Vec3::zero(), Vec3::zero(), Vec3::zero());
-
+                let mut basis_points: [Option<Vec3>; 4] = [None; 4];
                 for j in umd_start..umd_end {
                     let pos = Vec3::new(umd.x_rotated[j], umd.y_rotated[j], umd.z_rotated[j]);
-                    if &umd.types[j] == basis_names[0] { p_lc = pos; }
-                    if &umd.types[j] == basis_names[1] { p_rc = pos; }
-                    if &umd.types[j] == basis_names[2] { p_lm = pos; }
-                    if &umd.types[j] == basis_names[3] { p_ph = pos; }
+                    for b in 0..4 {
+                        if umd.types[j] == basis_names[b] { basis_points[b] = Some(pos); }
+                    }
                 }
 
+                let [Some(p_lc), Some(p_rc), Some(p_lm), Some(p_ph)] = basis_points else {
+                    let missing = (0..4).find(|&b| basis_points[b].is_none()).unwrap();
+                    f(current_frame, timestamp, set.join(","), Err(format!("missing landmark: {}", basis_names[missing])));
+                    continue;
+                };
+
                 let (basis_inv, scale) = match calculate_basis(p_lc, p_rc, p_lm, p_ph) {
                     Ok(res) => res,
-                    Err(_) => continue, 
+                    Err(reason) => {
+                        f(current_frame, timestamp, set.join(","), Err(reason));
+                        continue;
+                    }
                 };
-                
+
                 let origin = p_lc.add(p_rc).scale(0.5);
 
+                // the signed area and quadrant split work on cubic Beziers, so higher degree polynomial fits can't
+                // be measured without misrepresenting the curve
+                let higher_degree = (curve_start..curve_end).find(|&j| {
+                    set.contains(&curve.types_included[j].as_str())
+                        && [&curve.x_coeffs[j], &curve.y_coeffs[j], &curve.z_coeffs[j]].iter().any(|c| c.higher.iter().any(|&h| h != 0.0))
+                });
+                if let Some(j) = higher_degree {
+                    f(current_frame, timestamp, set.join(","), Err(format!("curve {} is above degree 3", curve.types_included[j])));
+                    continue;
+                }
+
                 let mut curves = Vec::new();
+                let mut covariances = Vec::new();
                 for j in curve_start..curve_end {
                     let current_type = &curve.types_included[j];
                     if set.contains(&current_type.as_str()) {
-                         let cb = CubicBezier::new(
-                            Vec3::new(curve.x_coeffs[j].d, curve.y_coeffs[j].d, curve.z_coeffs[j].d),
-                            Vec3::new(curve.x_coeffs[j].c, curve.y_coeffs[j].c, curve.z_coeffs[j].c),
-                            Vec3::new(curve.x_coeffs[j].b, curve.y_coeffs[j].b, curve.z_coeffs[j].b),
-                            Vec3::new(curve.x_coeffs[j].a, curve.y_coeffs[j].a, curve.z_coeffs[j].a),
-                        );
+                        let cb = CubicBezier::from_coefficients(&curve.x_coeffs[j], &curve.y_coeffs[j], &curve.z_coeffs[j]);
                         curves.push(transform_curve(&cb, &basis_inv, origin));
+                        covariances.push(transform_covariance(&basis_inv, &curve.x_covariance[j], &curve.y_covariance[j], &curve.z_covariance[j]));
                     }
                 }
 
-                let (total, q1, q2, q3, q4) = calculate_total_area(curves, scale);
+                if curves.is_empty() {
+                    f(current_frame, timestamp, set.join(","), Err("no curves".to_string()));
+                    continue;
+                }
+
+                f(current_frame, timestamp, set.join(","), Ok(BasisCurves { curves, covariances, scale }));
+            }
+        }
+    }
 
-                area_data.frame.push(current_frame);
-                area_data.timestamp.push(curve.timestamp[curve_start]);
-                area_data.types_included.push(set.join(","));
-                area_data.total_area.push(total);
-                area_data.q1_area.push(q1);
-                area_data.q2_area.push(q2);
-                area_data.q3_area.push(q3);
-                area_data.q4_area.push(q4);
+    // Monte Carlo check of the analytic uncertainty. Every draw perturbs the rotated landmarks by N(0, sigma),
+    // refits the curves with `fit` and recalculates the area. The area columns are the unperturbed result and
+    // the uncertainty columns are the standard deviation over the draws.
+    pub fn monte_carlo_area<F>(
+        umd: &UMD,
+        fit: F,
+        basis_sets: &[&[&str; 4]],
+        area_sets: &[&[&str]],
+        draws: usize,
+        seed: u64
+    ) -> CoreArea
+    where F: Fn(&UMD) -> CoreCurve {
+        let mut area_data = Self::calculate_area(&fit(umd), umd, basis_sets, area_sets);
+
+        let rows: HashMap<(u32, String), usize> = area_data.frame.iter().zip(&area_data.types_included)
+            .enumerate()
+            .map(|(k, (frame, types))| ((*frame, types.clone()), k))
+            .collect();
+
+        let mut count = vec![0usize; rows.len()];
+        let mut sum = vec![[0.0; 5]; rows.len()];
+        let mut sum_sq = vec![[0.0; 5]; rows.len()];
+
+        let mut sampler = NormalSampler::new(seed);
+        let sigma = |values: &Vec<f64>, i: usize| if i < values.len() { values[i] } else { 0.0 };
+
+        for _ in 0..draws {
+            let mut perturbed = umd.clone();
+            for i in 0..perturbed.frame.len() {
+                perturbed.x_rotated[i] += sigma(&umd.x_rotated_uncertainty, i) * sampler.sample();
+                perturbed.y_rotated[i] += sigma(&umd.y_rotated_uncertainty, i) * sampler.sample();
+                perturbed.z_rotated[i] += sigma(&umd.z_rotated_uncertainty, i) * sampler.sample();
             }
+
+            let draw = Self::calculate_area(&fit(&perturbed), &perturbed, basis_sets, area_sets);
+            for r in 0..draw.frame.len() {
+                if draw.status[r] != "ok" { continue; }
+                let Some(&k) = rows.get(&(draw.frame[r], draw.types_included[r].clone())) else { continue; };
+                let values = [draw.total_area[r], draw.q1_area[r], draw.q2_area[r], draw.q3_area[r], draw.q4_area[r]];
+                count[k] += 1;
+                for v in 0..5 {
+                    sum[k][v] += values[v];
+                    sum_sq[k][v] += values[v] * values[v];
+                }
+            }
+        }
+
+        for k in 0..count.len() {
+            let n = count[k] as f64;
+            let sd: Vec<f64> = (0..5).map(|v| {
+                if count[k] < 2 { return f64::NAN; }
+                let mean = sum[k][v] / n;
+                ((sum_sq[k][v] - n * mean * mean) / (n - 1.0)).max(0.0).sqrt()
+            }).collect();
+
+            area_data.total_area_uncertainty[k] = sd[0];
+            area_data.q1_area_uncertainty[k] = sd[1];
+            area_data.q2_area_uncertainty[k] = sd[2];
+            area_data.q3_area_uncertainty[k] = sd[3];
+            area_data.q4_area_uncertainty[k] = sd[4];
         }
         area_data
     }
+
+    // area of closed contours (see coreMeasurements/contour) - each contour is a single area set
+    pub fn calculate_contour_area(
+        contour: &CoreCurve,
+        umd: &UMD,
+        basis_sets: &[&[&str; 4]],
+        contour_loops: &[&[&str]]
+    ) -> CoreArea {
+        let identifiers: Vec<String> = contour_loops.iter().map(|l| ContourCalculator::contour_identifier(l)).collect();
+        let identifier_sets: Vec<[&str; 1]> = identifiers.iter().map(|id| [id.as_str()]).collect();
+        let area_sets: Vec<&[&str]> = identifier_sets.iter().map(|set| &set[..]).collect();
+
+        Self::calculate_area(contour, umd, basis_sets, &area_sets)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -162,7 +337,6 @@ pub struct Vec3 { x: f64, y: f64, z: f64 }
 
 impl Vec3 {
     fn new(x: f64, y: f64, z: f64) -> Self { Self { x, y, z } }
-    fn zero() -> Self { Self { x: 0.0, y: 0.0, z: 0.0 } }
     fn add(self, other: Vec3) -> Vec3 { Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z) }
     fn sub(self, other: Vec3) -> Vec3 { Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z) }
     fn scale(self, s: f64) -> Vec3 { Vec3::new(self.x * s, self.y * s, self.z * s) }
@@ -185,6 +359,14 @@ pub struct Mat3 { cols: [Vec3; 3] }
 impl Mat3 {
     fn from_cols(c1: Vec3, c2: Vec3, c3: Vec3) -> Self { Self { cols: [c1, c2, c3] } }
     
+    fn row(&self, r: usize) -> [f64; 3] {
+        match r {
+            0 => [self.cols[0].x, self.cols[1].x, self.cols[2].x],
+            1 => [self.cols[0].y, self.cols[1].y, self.cols[2].y],
+            _ => [self.cols[0].z, self.cols[1].z, self.cols[2].z],
+        }
+    }
+
     fn transform_vector(&self, v: Vec3) -> Vec3 {
         let x = self.cols[0].x * v.x + self.cols[1].x * v.y + self.cols[2].x * v.z;
         let y = self.cols[0].y * v.x + self.cols[1].y * v.y + self.cols[2].y * v.z;
@@ -232,6 +414,33 @@ pub struct CubicBezier { p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3 }
 impl CubicBezier {
     fn new(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3) -> Self { Self { p0, p1, p2, p3 } }
 
+    // CoreCurve stores power basis coefficients (a t^3 + b t^2 + c t + d) so they have to be converted into control points
+    fn from_coefficients(x: &CurveCoefficients, y: &CurveCoefficients, z: &CurveCoefficients) -> Self {
+        let a = Vec3::new(x.a, y.a, z.a);
+        let b = Vec3::new(x.b, y.b, z.b);
+        let c = Vec3::new(x.c, y.c, z.c);
+        let d = Vec3::new(x.d, y.d, z.d);
+
+        let p0 = d;
+        let p1 = d.add(c.scale(1.0 / 3.0));
+        let p2 = d.add(c.scale(2.0 / 3.0)).add(b.scale(1.0 / 3.0));
+        let p3 = d.add(c).add(b).add(a);
+        Self::new(p0, p1, p2, p3)
+    }
+
+    // back to power basis (a t^3 + b t^2 + c t + d)
+    fn power_coefficients(&self) -> (Vec3, Vec3, Vec3, Vec3) {
+        let a = self.p3.sub(self.p2.scale(3.0)).add(self.p1.scale(3.0)).sub(self.p0);
+        let b = self.p0.scale(3.0).sub(self.p1.scale(6.0)).add(self.p2.scale(3.0));
+        let c = self.p1.sub(self.p0).scale(3.0);
+        (a, b, c, self.p0)
+    }
+
+    // same curve with `centre` moved to the origin
+    pub(crate) fn relative_to(&self, centre: Vec3) -> CubicBezier {
+        CubicBezier::new(self.p0.sub(centre), self.p1.sub(centre), self.p2.sub(centre), self.p3.sub(centre))
+    }
+
     fn eval(&self, t: f64) -> Vec3 {
         let one_minus_t = 1.0 - t;
         let a = one_minus_t.powi(3);
@@ -354,7 +563,7 @@ pub fn calculate_total_area(
         split_params.dedup();
 
         let mut segments = Vec::new();
-        let mut current_curve = curve.clone();
+        let mut current_curve = curve;
         let mut t_start = 0.0;
 
         for t_global in split_params {
@@ -386,4 +595,250 @@ pub fn calculate_total_area(
     let total = q1 + q2 + q3 + q4;
 
     (total, q1, q2, q3, q4)
-}
\ No newline at end of file
+}
+
+// covariance of the transformed (basis) x and y power coefficients [x_a, x_b, x_c, x_d, y_a, y_b, y_c, y_d]
+// the transformed axis u is sum_m B[u][m] * axis_m and the original axes are independent
+// the basis itself is treated as fixed
+fn transform_covariance(basis_inv: &Mat3, cx: &[f64], cy: &[f64], cz: &[f64]) -> [[f64; 8]; 8] {
+    let rows = [basis_inv.row(0), basis_inv.row(1)];
+    let axes = [cx, cy, cz];
+    let mut cov = [[0.0; 8]; 8];
+
+    for u in 0..2 {
+        for v in 0..2 {
+            for p in 0..4 {
+                for q in 0..4 {
+                    let mut acc = 0.0;
+                    for m in 0..3 {
+                        if axes[m].len() == 16 { acc += rows[u][m] * rows[v][m] * axes[m][p * 4 + q]; }
+                    }
+                    cov[u * 4 + p][v * 4 + q] = acc;
+                }
+            }
+        }
+    }
+    cov
+}
+
+// signed area of each quadrant (unscaled) and its gradient with respect to the power coefficients
+// [x_a, x_b, x_c, x_d, y_a, y_b, y_c, y_d] of a transformed curve. Splits the curve the same way as calculate_total_area
+fn quadrant_gradients(curve: &CubicBezier) -> ([f64; 4], [[f64; 8]; 4]) {
+    let (a, b, c, d) = curve.power_coefficients();
+    let x = [a.x, b.x, c.x, d.x];
+    let y = [a.y, b.y, c.y, d.y];
+    let power = |i: usize| (3 - i) as i32;
+    let poly = |coeffs: &[f64; 4], t: f64| coeffs.iter().fold(0.0, |acc, k| acc * t + k);
+
+    // split points tagged with the axis they are a root of (0 = x, 1 = y)
+    let mut roots: Vec<(f64, usize)> = solve_cubic_roots(curve.p0.x, curve.p1.x, curve.p2.x, curve.p3.x)
+        .into_iter().map(|t| (t, 0)).collect();
+    roots.extend(solve_cubic_roots(curve.p0.y, curve.p1.y, curve.p2.y, curve.p3.y).into_iter().map(|t| (t, 1)));
+    roots.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    roots.dedup_by(|a, b| a.0 == b.0);
+
+    let mut bounds: Vec<(f64, Option<usize>)> = vec![(0.0, None)];
+    let mut t_start = 0.0;
+    for (t_global, axis) in roots {
+        let t_local = (t_global - t_start) / (1.0 - t_start);
+        if t_local > 1e-6 && t_local < 1.0 - 1e-6 {
+            bounds.push((t_global, Some(axis)));
+            t_start = t_global;
+        }
+    }
+    bounds.push((1.0, None));
+
+    let mut areas = [0.0; 4];
+    let mut gradients = [[0.0; 8]; 4];
+
+    for w in bounds.windows(2) {
+        let ((ta, axis_a), (tb, axis_b)) = (w[0], w[1]);
+        let mid = curve.eval(0.5 * (ta + tb));
+        let quadrant = if mid.x >= 0.0 && mid.y >= 0.0 { 0 }
+            else if mid.x < 0.0 && mid.y >= 0.0 { 1 }
+            else if mid.x < 0.0 && mid.y < 0.0 { 2 }
+            else { 3 };
+
+        // A = 1/2 int (x y' - y x') dt = 1/2 sum_pq x_p y_q (q - p) / (p + q) [t^(p+q)] from ta to tb
+        for i in 0..4 {
+            for j in 0..4 {
+                let (p, q) = (power(i), power(j));
+                if p + q == 0 { continue; }
+                let k = 0.5 * (q - p) as f64 / (p + q) as f64 * (tb.powi(p + q) - ta.powi(p + q));
+                areas[quadrant] += k * x[i] * y[j];
+                gradients[quadrant][i] += k * y[j];
+                gradients[quadrant][4 + j] += k * x[i];
+            }
+        }
+
+        // the split points move with the coefficients: x(t) = 0 gives dt/dx_p = -t^p / x'(t)
+        // so the end point term 1/2 (x y' - y x') dt/dx_p becomes 1/2 y t^p (and -1/2 x t^q for y(t) = 0)
+        for (t, axis, sign) in [(tb, axis_b, 1.0), (ta, axis_a, -1.0)] {
+            match axis {
+                Some(0) => for (i, g) in gradients[quadrant][..4].iter_mut().enumerate() { *g += sign * 0.5 * poly(&y, t) * t.powi(power(i)); },
+                Some(_) => for (j, g) in gradients[quadrant][4..].iter_mut().enumerate() { *g -= sign * 0.5 * poly(&x, t) * t.powi(power(j)); },
+                None => {}
+            }
+        }
+    }
+    (areas, gradients)
+}
+
+// mean of points sampled along the curves (robust to the curves being in any order or direction)
+pub(crate) fn curves_centroid(curves: &[CubicBezier]) -> Vec3 {
+    const SAMPLES: usize = 16;
+    let mut sum = Vec3::new(0.0, 0.0, 0.0);
+    let mut count = 0;
+    for curve in curves {
+        for k in 0..=SAMPLES {
+            sum = sum.add(curve.eval(k as f64 / SAMPLES as f64));
+            count += 1;
+        }
+    }
+    if count == 0 { sum } else { sum.scale(1.0 / count as f64) }
+}
+
+// index of the angular sector containing `phi`. Sector k is [angles[k], angles[k + 1]) and the last wraps round
+pub(crate) fn sector_of(angles: &[f64], phi: f64) -> usize {
+    let turn = 2.0 * PI;
+    let d = (phi - angles[0]).rem_euclid(turn);
+    let mut sector = 0;
+    for (k, angle) in angles.iter().enumerate() {
+        if (angle - angles[0]).rem_euclid(turn) <= d { sector = k; }
+    }
+    sector
+}
+
+// generalisation of quadrant_gradients to any set of boundary rays from the origin (angles in rads from +x, ascending)
+// the curve is split wherever it crosses a boundary line w(t) = -sin(theta) x(t) + cos(theta) y(t) = 0
+pub(crate) fn sector_gradients(curve: &CubicBezier, angles: &[f64]) -> (Vec<f64>, Vec<[f64; 8]>) {
+    let (a, b, c, d) = curve.power_coefficients();
+    let x = [a.x, b.x, c.x, d.x];
+    let y = [a.y, b.y, c.y, d.y];
+    let power = |i: usize| (3 - i) as i32;
+    let poly = |coeffs: &[f64; 4], t: f64| coeffs.iter().fold(0.0, |acc, k| acc * t + k);
+
+    // split points tagged with the boundary they are a root of
+    let mut roots: Vec<(f64, usize)> = Vec::new();
+    for (k, theta) in angles.iter().enumerate() {
+        let (sin, cos) = theta.sin_cos();
+        let w = |p: Vec3| -sin * p.x + cos * p.y;
+        roots.extend(solve_cubic_roots(w(curve.p0), w(curve.p1), w(curve.p2), w(curve.p3)).into_iter().map(|t| (t, k)));
+    }
+    roots.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    roots.dedup_by(|a, b| a.0 == b.0);
+
+    let mut bounds: Vec<(f64, Option<usize>)> = vec![(0.0, None)];
+    let mut t_start = 0.0;
+    for (t_global, boundary) in roots {
+        let t_local = (t_global - t_start) / (1.0 - t_start);
+        if t_local > 1e-6 && t_local < 1.0 - 1e-6 {
+            bounds.push((t_global, Some(boundary)));
+            t_start = t_global;
+        }
+    }
+    bounds.push((1.0, None));
+
+    let mut areas = vec![0.0; angles.len()];
+    let mut gradients = vec![[0.0; 8]; angles.len()];
+
+    for w in bounds.windows(2) {
+        let ((ta, boundary_a), (tb, boundary_b)) = (w[0], w[1]);
+        let mid = curve.eval(0.5 * (ta + tb));
+        let sector = sector_of(angles, mid.y.atan2(mid.x));
+
+        for i in 0..4 {
+            for j in 0..4 {
+                let (p, q) = (power(i), power(j));
+                if p + q == 0 { continue; }
+                let k = 0.5 * (q - p) as f64 / (p + q) as f64 * (tb.powi(p + q) - ta.powi(p + q));
+                areas[sector] += k * x[i] * y[j];
+                gradients[sector][i] += k * y[j];
+                gradients[sector][4 + j] += k * x[i];
+            }
+        }
+
+        // on a boundary (x, y) = r (cos, sin) so the moving split point adds 1/2 r sin t^p and -1/2 r cos t^q
+        for (t, boundary, sign) in [(tb, boundary_b, 1.0), (ta, boundary_a, -1.0)] {
+            let Some(k) = boundary else { continue; };
+            let (sin, cos) = angles[k].sin_cos();
+            let r = cos * poly(&x, t) + sin * poly(&y, t);
+            for i in 0..4 {
+                gradients[sector][i] += sign * 0.5 * r * sin * t.powi(power(i));
+                gradients[sector][4 + i] -= sign * 0.5 * r * cos * t.powi(power(i));
+            }
+        }
+    }
+    (areas, gradients)
+}
+
+pub(crate) fn quadratic_form(g: &[f64; 8], cov: &[[f64; 8]; 8]) -> f64 {
+    let mut acc = 0.0;
+    for i in 0..8 {
+        for j in 0..8 {
+            acc += g[i] * cov[i][j] * g[j];
+        }
+    }
+    acc
+}
+
+// first order uncertainty of the total and quadrant areas (curves are treated as independent)
+pub fn calculate_area_uncertainty(
+    curves: &[CubicBezier],
+    covariances: &[[[f64; 8]; 8]],
+    scale: f64
+) -> (f64, f64, f64, f64, f64) {
+    let per_curve: Vec<([f64; 4], [[f64; 8]; 4])> = curves.iter().map(quadrant_gradients).collect();
+
+    let mut q_signed = [0.0; 4];
+    for (areas, _) in &per_curve {
+        for k in 0..4 { q_signed[k] += areas[k]; }
+    }
+
+    let mut q_var = [0.0; 4];
+    let mut total_var = 0.0;
+    for ((_, gradients), cov) in per_curve.iter().zip(covariances) {
+        // |q| only flips the sign of the gradient so each quadrant on its own does not need the sign
+        for k in 0..4 { q_var[k] += quadratic_form(&gradients[k], cov); }
+
+        // total = sum |q| so the quadrant gradients are combined with their signs
+        let mut total_gradient = [0.0; 8];
+        for k in 0..4 {
+            let sign = q_signed[k].signum();
+            for i in 0..8 { total_gradient[i] += sign * gradients[k][i]; }
+        }
+        total_var += quadratic_form(&total_gradient, cov);
+    }
+
+    let s = scale.abs();
+    (s * total_var.sqrt(), s * q_var[0].sqrt(), s * q_var[1].sqrt(), s * q_var[2].sqrt(), s * q_var[3].sqrt())
+}
+
+// xorshift64* with Box-Muller for the Monte Carlo draws (keeps the crate free of an rng dependency)
+struct NormalSampler {
+    state: u64,
+    spare: Option<f64>,
+}
+
+impl NormalSampler {
+    fn new(seed: u64) -> Self {
+        let state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        Self { state: if state == 0 { 1 } else { state }, spare: None }
+    }
+
+    fn uniform(&mut self) -> f64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let bits = self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        (bits as f64 + 0.5) / (1u64 << 53) as f64 // (0, 1)
+    }
+
+    fn sample(&mut self) -> f64 {
+        if let Some(spare) = self.spare.take() { return spare; }
+        let r = (-2.0 * self.uniform().ln()).sqrt();
+        let theta = 2.0 * PI * self.uniform();
+        self.spare = Some(r * theta.sin());
+        r * theta.cos()
+    }
+}
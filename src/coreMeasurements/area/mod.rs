@@ -13,4 +13,6 @@ You should have received a copy of the GNU General Public License along with
 MOSAIC. If not, see <https://www.gnu.org/licenses/>.
 */
 
-pub mod area;
\ No newline at end of file
+pub mod area;
+pub mod polygon;
+pub mod sector;
\ No newline at end of file
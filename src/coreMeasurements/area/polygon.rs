@@ -0,0 +1,251 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Polygon area
+
+    Alternatives to the curve based area for sparse landmark sets (i.e. EMA setups with only 4 lip sensors)
+    where fitting cubic curves and building the commissure/philtrum basis does not make sense.
+
+        - Shoelace: polygon through the landmarks in the order given
+        - ConvexHull: convex hull of the landmarks (order does not matter)
+
+    Both are measured in a chosen plane (the projected area). The quadrant columns of CoreArea are NaN as there
+    is no basis to split on. Frames that cannot be measured are kept with a status explaining why.
+*/
+
+use crate::UMD::UMD::{UMD};
+use crate::coreMeasurements::area::area::{CoreArea};
+use crate::coreMeasurements::curve::curve::{CurveCalculator};
+use crate::coreMeasurements::pairs::pairs::{FrameIndex};
+use nalgebra::{Matrix3, SymmetricEigen, Vector3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PolygonModel {
+    Shoelace,
+    ConvexHull,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectionPlane {
+    // pose corrected axes
+    XY,
+    XZ,
+    YZ,
+    // plane through 3 landmarks (first -> second is the in plane x axis)
+    Landmarks([&'static str; 3]),
+    // least squares plane through the polygon itself (largest possible projected area)
+    BestFit,
+}
+
+pub struct PolygonCalculator;
+
+impl PolygonCalculator {
+    pub fn polygon_area(
+        umd: &UMD,
+        landmark_sets: &[&[&str]],
+        model: PolygonModel,
+        plane: ProjectionPlane
+    ) -> CoreArea {
+        let total_entries = umd.frame.len();
+        let mut area_data = CoreArea::construction((total_entries / 68 + 1) * landmark_sets.len());
+
+        let mut i = 0;
+        while i < total_entries {
+            let current_frame = umd.frame[i];
+            let start_idx = i;
+            while i < total_entries && umd.frame[i] == current_frame { i += 1; }
+            let end_idx = i;
+
+            let index = FrameIndex::new(umd, start_idx, end_idx);
+            let timestamp = umd.timestamp[start_idx];
+
+            for set in landmark_sets {
+                let types_included = set.join(",");
+
+                let mut points = Vec::with_capacity(set.len());
+                let mut sigmas = Vec::with_capacity(set.len());
+                let mut missing = None;
+                for &label in *set {
+                    match index.get(label) {
+                        Some(idx) => {
+                            points.push(Vector3::new(umd.x_rotated[idx], umd.y_rotated[idx], umd.z_rotated[idx]));
+                            let s = CurveCalculator::point_sigma(umd, idx);
+                            sigmas.push(Vector3::new(s.0, s.1, s.2));
+                        }
+                        None => { missing = Some(label); break; }
+                    }
+                }
+
+                if let Some(label) = missing {
+                    area_data.add_skipped(current_frame, timestamp, types_included, format!("missing landmark: {}", label));
+                    continue;
+                }
+                // failed tracking leaves NaN coordinates, which would poison the plane and the hull
+                if points.iter().any(|p| !p.iter().all(|c| c.is_finite())) {
+                    area_data.add_skipped(current_frame, timestamp, types_included, "undefined landmark".to_string());
+                    continue;
+                }
+                if points.len() < 3 {
+                    area_data.add_skipped(current_frame, timestamp, types_included, "too few points".to_string());
+                    continue;
+                }
+
+                let (e1, e2) = match Self::plane_axes(umd, &index, &points, plane) {
+                    Ok(axes) => axes,
+                    Err(reason) => {
+                        area_data.add_skipped(current_frame, timestamp, types_included, reason);
+                        continue;
+                    }
+                };
+
+                // in plane coordinates
+                let projected: Vec<(f64, f64)> = points.iter().map(|p| (p.dot(&e1), p.dot(&e2))).collect();
+
+                let order: Vec<usize> = match model {
+                    PolygonModel::Shoelace => (0..projected.len()).collect(),
+                    PolygonModel::ConvexHull => Self::convex_hull_2d(&projected),
+                };
+                if order.len() < 3 {
+                    area_data.add_skipped(current_frame, timestamp, types_included, "degenerate polygon".to_string());
+                    continue;
+                }
+
+                let polygon: Vec<(f64, f64)> = order.iter().map(|&k| projected[k]).collect();
+                let area = Self::shoelace(&polygon).abs();
+                let uncertainty = Self::shoelace_uncertainty(&order, &projected, &sigmas, e1, e2);
+
+                let nan = f64::NAN;
+                area_data.add_point(
+                    current_frame, timestamp, types_included,
+                    (area, nan, nan, nan, nan),
+                    (uncertainty, nan, nan, nan, nan),
+                );
+            }
+        }
+        area_data
+    }
+
+    // signed area (positive when counter-clockwise)
+    pub fn shoelace(polygon: &[(f64, f64)]) -> f64 {
+        let n = polygon.len();
+        let mut acc = 0.0;
+        for k in 0..n {
+            let (x0, y0) = polygon[k];
+            let (x1, y1) = polygon[(k + 1) % n];
+            acc += x0 * y1 - x1 * y0;
+        }
+        0.5 * acc
+    }
+
+    // monotone chain convex hull, returns the indices of the hull in counter-clockwise order
+    pub fn convex_hull_2d(points: &[(f64, f64)]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        order.sort_by(|&a, &b| {
+            points[a].0.total_cmp(&points[b].0).then(points[a].1.total_cmp(&points[b].1))
+        });
+        if order.len() < 3 { return order; }
+
+        let cross = |o: usize, a: usize, b: usize| {
+            (points[a].0 - points[o].0) * (points[b].1 - points[o].1)
+                - (points[a].1 - points[o].1) * (points[b].0 - points[o].0)
+        };
+
+        let mut hull: Vec<usize> = Vec::with_capacity(2 * order.len());
+        // lower hull
+        for &k in &order {
+            while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], k) <= 0.0 { hull.pop(); }
+            hull.push(k);
+        }
+        // upper hull
+        let lower_len = hull.len() + 1;
+        for &k in order.iter().rev().skip(1) {
+            while hull.len() >= lower_len && cross(hull[hull.len() - 2], hull[hull.len() - 1], k) <= 0.0 { hull.pop(); }
+            hull.push(k);
+        }
+        hull.pop(); // last point is the first point again
+        hull
+    }
+
+    // orthonormal in plane axes (e1, e2)
+    fn plane_axes(
+        umd: &UMD, index: &FrameIndex, points: &[Vector3<f64>], plane: ProjectionPlane
+    ) -> Result<(Vector3<f64>, Vector3<f64>), String> {
+        match plane {
+            ProjectionPlane::XY => Ok((Vector3::x(), Vector3::y())),
+            ProjectionPlane::XZ => Ok((Vector3::x(), Vector3::z())),
+            ProjectionPlane::YZ => Ok((Vector3::y(), Vector3::z())),
+            ProjectionPlane::Landmarks(labels) => {
+                let mut p = [Vector3::zeros(); 3];
+                for (k, label) in labels.iter().enumerate() {
+                    let idx = index.get(label).ok_or_else(|| format!("missing landmark: {}", label))?;
+                    p[k] = Vector3::new(umd.x_rotated[idx], umd.y_rotated[idx], umd.z_rotated[idx]);
+                    if !p[k].iter().all(|c| c.is_finite()) { return Err("undefined landmark".to_string()); }
+                }
+                let u = p[1] - p[0];
+                let normal = u.cross(&(p[2] - p[0]));
+                if u.norm() < 1e-12 || normal.norm() < 1e-12 { return Err("degenerate plane".to_string()); }
+                let e1 = u.normalize();
+                let e2 = normal.normalize().cross(&e1);
+                Ok((e1, e2))
+            }
+            ProjectionPlane::BestFit => {
+                let n = points.len() as f64;
+                let centroid = points.iter().fold(Vector3::zeros(), |acc, p| acc + p) / n;
+                let mut scatter = Matrix3::zeros();
+                for p in points {
+                    let d = p - centroid;
+                    scatter += d * d.transpose();
+                }
+
+                // the two largest eigenvectors span the plane
+                let eigen = SymmetricEigen::new(scatter);
+                let mut order = [0, 1, 2];
+                order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+                if eigen.eigenvalues[order[1]] < 1e-12 { return Err("degenerate plane".to_string()); }
+
+                let e1: Vector3<f64> = eigen.eigenvectors.column(order[0]).into_owned();
+                let e2: Vector3<f64> = eigen.eigenvectors.column(order[1]).into_owned();
+                Ok((e1, e2))
+            }
+        }
+    }
+
+    // first order uncertainty of the shoelace area (plane and hull membership treated as fixed)
+    // dA/du_k = (v_{k+1} - v_{k-1}) / 2, dA/dv_k = (u_{k-1} - u_{k+1}) / 2
+    fn shoelace_uncertainty(
+        order: &[usize], projected: &[(f64, f64)], sigmas: &[Vector3<f64>],
+        e1: Vector3<f64>, e2: Vector3<f64>
+    ) -> f64 {
+        let n = order.len();
+        let mut variance = 0.0;
+        for k in 0..n {
+            let idx = order[k];
+            let prev = projected[order[(k + n - 1) % n]];
+            let next = projected[order[(k + 1) % n]];
+            let g_u = 0.5 * (next.1 - prev.1);
+            let g_v = 0.5 * (prev.0 - next.0);
+
+            // projected coordinate (co)variance from the independent x/y/z uncertainty
+            let s2 = sigmas[idx].component_mul(&sigmas[idx]);
+            let var_u = e1.component_mul(&e1).dot(&s2);
+            let var_v = e2.component_mul(&e2).dot(&s2);
+            let cov_uv = e1.component_mul(&e2).dot(&s2);
+
+            variance += g_u * g_u * var_u + g_v * g_v * var_v + 2.0 * g_u * g_v * cov_uv;
+        }
+        variance.sqrt()
+    }
+}
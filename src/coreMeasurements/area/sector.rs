@@ -0,0 +1,292 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Sector area decomposition
+
+    Generalises the 4 quadrants of calculate_total_area to any set of angular sectors in the commissure/philtrum
+    basis (left/right halves, upper/lower halves, N equal sectors or user defined split angles), either about the
+    basis origin or about the centroid of the mouth. Used for asymmetry studies (facial palsy, post-stroke speakers).
+
+    In the basis +x points towards the left commissure and +y towards the lower lip.
+*/
+
+use crate::UMD::UMD::{UMD};
+use crate::coreMeasurements::curve::curve::{CoreCurve};
+use crate::coreMeasurements::area::area::{AreaCalculator, BasisCurves, curves_centroid, sector_gradients, quadratic_form};
+use polars::prelude::*;
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::fs::File;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SectorScheme {
+    Quadrants, // same split as calculate_total_area
+    LeftRight, // split at the vertical line through the philtrum
+    UpperLower, // split at the commissure line
+    Angular { sectors: usize, about_centroid: bool }, // equal sectors starting at +x
+    Custom { angles: Vec<f64>, about_centroid: bool }, // split rays (rads from +x)
+}
+
+impl SectorScheme {
+    pub fn name(&self) -> String {
+        match self {
+            Self::Quadrants => "quadrants".to_string(),
+            Self::LeftRight => "left_right".to_string(),
+            Self::UpperLower => "upper_lower".to_string(),
+            Self::Angular { sectors, about_centroid } =>
+                format!("angular_{}{}", sectors, if *about_centroid { "_centroid" } else { "" }),
+            Self::Custom { about_centroid, .. } =>
+                format!("custom{}", if *about_centroid { "_centroid" } else { "" }),
+        }
+    }
+
+    // boundary rays, ascending within one turn
+    fn angles(&self) -> Vec<f64> {
+        match self {
+            Self::Quadrants => vec![0.0, FRAC_PI_2, PI, 3.0 * FRAC_PI_2],
+            Self::LeftRight => vec![-FRAC_PI_2, FRAC_PI_2],
+            Self::UpperLower => vec![0.0, PI],
+            Self::Angular { sectors, .. } => {
+                let n = (*sectors).max(1);
+                (0..n).map(|k| 2.0 * PI * k as f64 / n as f64).collect()
+            }
+            Self::Custom { angles, .. } => {
+                let mut sorted: Vec<f64> = angles.iter().map(|a| a.rem_euclid(2.0 * PI)).collect();
+                sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                sorted.dedup();
+                if sorted.is_empty() { vec![0.0] } else { sorted }
+            }
+        }
+    }
+
+    fn labels(&self) -> Vec<String> {
+        match self {
+            Self::Quadrants => vec!["q1".to_string(), "q2".to_string(), "q3".to_string(), "q4".to_string()],
+            Self::LeftRight => vec!["left".to_string(), "right".to_string()],
+            Self::UpperLower => vec!["lower".to_string(), "upper".to_string()],
+            _ => (1..=self.angles().len()).map(|k| format!("sector_{}", k)).collect(),
+        }
+    }
+
+    fn about_centroid(&self) -> bool {
+        match self {
+            Self::Angular { about_centroid, .. } | Self::Custom { about_centroid, .. } => *about_centroid,
+            _ => false,
+        }
+    }
+
+    // halves compared by the asymmetry index: (first - second) / (first + second)
+    // left - right for every scheme except UpperLower (lower - upper)
+    fn asymmetry_angles(&self) -> Vec<f64> {
+        match self {
+            Self::UpperLower => vec![0.0, PI],
+            _ => vec![-FRAC_PI_2, FRAC_PI_2],
+        }
+    }
+}
+
+// one sector of one area set in one frame
+#[derive(Debug, Clone)]
+pub struct SectorArea {
+    pub sector: String,
+    pub status: String,
+    pub area: f64,
+    pub area_uncertainty: f64,
+    pub proportion: f64,
+    pub asymmetry_index: f64,
+    pub asymmetry_uncertainty: f64,
+}
+
+// long table: one row per sector per area set per frame
+pub struct CoreSectorArea {
+    pub frame: Vec<u32>,
+    pub timestamp: Vec<f32>,
+    pub types_included: Vec<String>,
+    pub scheme: Vec<String>,
+    pub sector: Vec<String>,
+    pub status: Vec<String>,
+    pub area: Vec<f64>,
+    pub area_uncertainty: Vec<f64>,
+    pub proportion: Vec<f64>, // sector area / total area
+    pub asymmetry_index: Vec<f64>, // same for every sector of a frame
+    pub asymmetry_uncertainty: Vec<f64>,
+}
+
+impl CoreSectorArea {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            frame: Vec::with_capacity(estimated_entries),
+            timestamp: Vec::with_capacity(estimated_entries),
+            types_included: Vec::with_capacity(estimated_entries),
+            scheme: Vec::with_capacity(estimated_entries),
+            sector: Vec::with_capacity(estimated_entries),
+            status: Vec::with_capacity(estimated_entries),
+            area: Vec::with_capacity(estimated_entries),
+            area_uncertainty: Vec::with_capacity(estimated_entries),
+            proportion: Vec::with_capacity(estimated_entries),
+            asymmetry_index: Vec::with_capacity(estimated_entries),
+            asymmetry_uncertainty: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(
+        &mut self, frame: u32, timestamp: f32, types_included: String, scheme: String,
+        row: SectorArea,
+    ) {
+        self.frame.push(frame);
+        self.timestamp.push(timestamp);
+        self.types_included.push(types_included);
+        self.scheme.push(scheme);
+        self.sector.push(row.sector);
+        self.status.push(row.status);
+        self.area.push(row.area);
+        self.area_uncertainty.push(row.area_uncertainty);
+        self.proportion.push(row.proportion);
+        self.asymmetry_index.push(row.asymmetry_index);
+        self.asymmetry_uncertainty.push(row.asymmetry_uncertainty);
+    }
+
+    pub fn save_sector_area_to_parquet(data: &CoreSectorArea, file_path: &str) -> PolarsResult<()> {
+        let s_frame = Series::new("frame", &data.frame);
+        let s_time = Series::new("timestamp", &data.timestamp);
+        let s_types = Series::new("types_included", &data.types_included);
+        let s_scheme = Series::new("scheme", &data.scheme);
+        let s_sector = Series::new("sector", &data.sector);
+        let s_status = Series::new("status", &data.status);
+        let s_area = Series::new("area", &data.area);
+        let s_area_unc = Series::new("area_uncertainty", &data.area_uncertainty);
+        let s_proportion = Series::new("proportion", &data.proportion);
+        let s_asymmetry = Series::new("asymmetry_index", &data.asymmetry_index);
+        let s_asymmetry_unc = Series::new("asymmetry_uncertainty", &data.asymmetry_uncertainty);
+
+        let mut df = DataFrame::new(vec![
+            s_frame, s_time, s_types, s_scheme, s_sector, s_status,
+            s_area, s_area_unc, s_proportion, s_asymmetry, s_asymmetry_unc,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported sector area data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct SectorCalculator;
+
+impl SectorCalculator {
+    pub fn calculate_sectors(
+        curve: &CoreCurve,
+        umd: &UMD,
+        basis_sets: &[&[&str; 4]],
+        area_sets: &[&[&str]],
+        scheme: &SectorScheme
+    ) -> CoreSectorArea {
+        let angles = scheme.angles();
+        let labels = scheme.labels();
+        let asymmetry_angles = scheme.asymmetry_angles();
+        let scheme_name = scheme.name();
+
+        let mut sector_data = CoreSectorArea::construction(curve.frame.len() * angles.len());
+
+        AreaCalculator::transformed_sets(curve, umd, basis_sets, area_sets, |frame, timestamp, types_included, result| {
+            let set = match result {
+                Ok(set) => set,
+                Err(reason) => {
+                    let nan = f64::NAN;
+                    for label in &labels {
+                        sector_data.add_point(frame, timestamp, types_included.clone(), scheme_name.clone(), SectorArea {
+                            sector: label.clone(),
+                            status: reason.clone(),
+                            area: nan,
+                            area_uncertainty: nan,
+                            proportion: nan,
+                            asymmetry_index: nan,
+                            asymmetry_uncertainty: nan,
+                        });
+                    }
+                    return;
+                }
+            };
+
+            let (areas, uncertainties) = Self::sector_areas(&set, &angles, scheme.about_centroid());
+            let (asymmetry, asymmetry_uncertainty) = Self::asymmetry(&set, &asymmetry_angles, scheme.about_centroid());
+            let total: f64 = areas.iter().sum();
+
+            for (k, label) in labels.iter().enumerate() {
+                let proportion = if total > 0.0 { areas[k] / total } else { f64::NAN };
+                sector_data.add_point(frame, timestamp, types_included.clone(), scheme_name.clone(), SectorArea {
+                    sector: label.clone(),
+                    status: "ok".to_string(),
+                    area: areas[k],
+                    area_uncertainty: uncertainties[k],
+                    proportion,
+                    asymmetry_index: asymmetry,
+                    asymmetry_uncertainty,
+                });
+            }
+        });
+        sector_data
+    }
+
+    // signed sector areas and their gradients summed over the curves (one gradient per curve per sector)
+    fn signed_sectors(set: &BasisCurves, angles: &[f64], about_centroid: bool) -> (Vec<f64>, Vec<Vec<[f64; 8]>>) {
+        let centre = curves_centroid(&set.curves);
+        let mut signed = vec![0.0; angles.len()];
+        let mut gradients = Vec::with_capacity(set.curves.len());
+
+        for curve in &set.curves {
+            let curve = if about_centroid { curve.relative_to(centre) } else { *curve };
+            let (areas, curve_gradients) = sector_gradients(&curve, angles);
+            for k in 0..angles.len() { signed[k] += areas[k]; }
+            gradients.push(curve_gradients);
+        }
+        (signed, gradients)
+    }
+
+    // |sector| areas and first order uncertainty (centre and split rays treated as fixed)
+    fn sector_areas(set: &BasisCurves, angles: &[f64], about_centroid: bool) -> (Vec<f64>, Vec<f64>) {
+        let (signed, gradients) = Self::signed_sectors(set, angles, about_centroid);
+        let scale = set.scale.abs();
+
+        let areas = signed.iter().map(|a| scale * a.abs()).collect();
+        let uncertainties = (0..angles.len()).map(|k| {
+            let variance: f64 = gradients.iter().zip(&set.covariances)
+                .map(|(g, cov)| quadratic_form(&g[k], cov))
+                .sum();
+            scale * variance.sqrt()
+        }).collect();
+        (areas, uncertainties)
+    }
+
+    // AI = (a - b) / (a + b) for the two halves, dAI/da = 2b / (a + b)^2, dAI/db = -2a / (a + b)^2
+    fn asymmetry(set: &BasisCurves, halves: &[f64], about_centroid: bool) -> (f64, f64) {
+        let (signed, gradients) = Self::signed_sectors(set, halves, about_centroid);
+        let (a, b) = (signed[0].abs(), signed[1].abs());
+        if a + b <= 0.0 { return (f64::NAN, f64::NAN); }
+
+        let index = (a - b) / (a + b);
+        let d_a = 2.0 * b / (a + b).powi(2) * signed[0].signum();
+        let d_b = -2.0 * a / (a + b).powi(2) * signed[1].signum();
+
+        // the scale cancels in the ratio
+        let variance: f64 = gradients.iter().zip(&set.covariances).map(|(g, cov)| {
+            let mut combined = [0.0; 8];
+            for i in 0..8 { combined[i] = d_a * g[0][i] + d_b * g[1][i]; }
+            quadratic_form(&combined, cov)
+        }).sum();
+
+        (index, variance.sqrt())
+    }
+}
@@ -0,0 +1,299 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Closed contour fitting
+
+    Fits a single closed curve around an ordered loop of landmarks (i.e. all 12 outer lip points, or the tongue
+    sensors plus the palate) instead of several open curves which have to meet exactly at the ends.
+
+    Contours are written into a CoreCurve (one row per cubic segment) so they can be passed straight into
+    the AreaCalculator. The joins between segments are always at least C1 continuous:
+        - periodic cubic spline is C2 everywhere (including where the loop closes)
+        - fourier contour is converted to cubic hermite segments using the exact fourier tangents
+*/
+
+use crate::UMD::UMD::{UMD};
+use crate::coreMeasurements::curve::curve::{CoreCurve, CurveCoefficients, CurveCalculator, CurveFit};
+use crate::coreMeasurements::pairs::pairs::{FrameIndex};
+use nalgebra::{DMatrix, DVector};
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContourModel {
+    PeriodicSpline,
+    Fourier { harmonics: usize, segments: usize },
+}
+
+impl ContourModel {
+    pub fn name(&self) -> String {
+        match self {
+            Self::PeriodicSpline => "periodic_spline".to_string(),
+            Self::Fourier { harmonics, .. } => format!("fourier_{}", harmonics),
+        }
+    }
+}
+
+pub struct ContourCalculator;
+
+impl ContourCalculator {
+    pub fn contour_identifier(contour_loop: &[&str]) -> String {
+        contour_loop.join(",")
+    }
+
+    pub fn fit_contour(umd: &UMD, contour_loops: &[&[&str]], model: ContourModel) -> CoreCurve {
+        let total_entries = umd.frame.len();
+        if total_entries == 0 { return CoreCurve::construction(0); }
+
+        let mut contour_data = CoreCurve::construction(total_entries / 68);
+        let identifiers: Vec<String> = contour_loops.iter().map(|l| Self::contour_identifier(l)).collect();
+
+        let mut i = 0;
+        while i < total_entries {
+            let current_frame = umd.frame[i];
+            let start_idx = i;
+            while i < total_entries && umd.frame[i] == current_frame { i += 1; }
+            let end_idx = i;
+
+            let index = FrameIndex::new(umd, start_idx, end_idx);
+
+            for (k, contour_loop) in contour_loops.iter().enumerate() {
+                let mut points = Vec::with_capacity(contour_loop.len());
+                let mut sigmas = Vec::with_capacity(contour_loop.len());
+                for &target in *contour_loop {
+                    if let Some(idx) = index.get(target) {
+                        points.push((umd.x_rotated[idx], umd.y_rotated[idx], umd.z_rotated[idx]));
+                        sigmas.push(CurveCalculator::point_sigma(umd, idx));
+                    }
+                }
+
+                // a loop needs at least 3 points to enclose anything
+                if points.len() < 3 { continue; }
+
+                let ts = umd.timestamp[start_idx];
+                match model {
+                    ContourModel::PeriodicSpline =>
+                        Self::fit_periodic_spline(&mut contour_data, current_frame, ts, &points, &sigmas, &identifiers[k]),
+                    ContourModel::Fourier { harmonics, segments } =>
+                        Self::fit_fourier(&mut contour_data, current_frame, ts, &points, &sigmas, &identifiers[k], (harmonics, segments)),
+                }
+            }
+        }
+        contour_data
+    }
+
+    // closed chord length parameterisation (includes the closing chord back to the first point)
+    // returns the knots (0 - 1, one per point) and the segment lengths h
+    fn closed_chord_length(points: &[(f64, f64, f64)]) -> (Vec<f64>, Vec<f64>) {
+        let n = points.len();
+        let d: Vec<f64> = (0..n).map(|i| {
+            let p = points[i];
+            let q = points[(i + 1) % n];
+            ((q.0 - p.0).powi(2) + (q.1 - p.1).powi(2) + (q.2 - p.2).powi(2)).sqrt()
+        }).collect();
+
+        let l: f64 = d.iter().sum();
+        let h: Vec<f64> = d.iter().map(|di| if l > 0.0 { di / l } else { 0.0 }).collect();
+
+        let mut s = Vec::with_capacity(n);
+        let mut cumulative = 0.0;
+        for hi in &h {
+            s.push(cumulative);
+            cumulative += hi;
+        }
+        (s, h)
+    }
+
+    fn fit_periodic_spline(
+        data: &mut CoreCurve, frame: u32, ts: f32,
+        points: &[(f64, f64, f64)], sigmas: &[(f64, f64, f64)], types_str: &str
+    ) {
+        let n = points.len();
+        let (_, h) = Self::closed_chord_length(points);
+        if h.iter().any(|&hi| hi <= 0.0) { return; } // repeated points
+
+        // cyclic tridiagonal system for the second derivatives (every point is "interior" on a closed loop)
+        let mut a = DMatrix::<f64>::zeros(n, n);
+        for i in 0..n {
+            let prev = (i + n - 1) % n;
+            let next = (i + 1) % n;
+            a[(i, prev)] += h[prev];
+            a[(i, i)] += 2.0 * (h[prev] + h[i]);
+            a[(i, next)] += h[i];
+        }
+
+        let singular_values = a.clone().singular_values();
+        let max = singular_values.iter().cloned().fold(0.0, f64::max);
+        let min = singular_values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let condition_number = if min <= 0.0 { f64::INFINITY } else { max / min };
+
+        // same segment form as the natural spline in curve.rs (local u in [0, 1])
+        let segment_coeffs = |y0: f64, y1: f64, m0: f64, m1: f64, hi: f64| -> CurveCoefficients {
+            let slope = (y1 - y0) / hi - hi * (2.0 * m0 + m1) / 6.0;
+            CurveCoefficients::cubic(
+                (m1 - m0) * hi * hi / 6.0,
+                m0 * hi * hi / 2.0,
+                slope * hi,
+                y0,
+            )
+        };
+
+        // segment coefficients for a single axis
+        let lu = a.lu();
+        let axis = |y: &[f64]| -> Option<Vec<CurveCoefficients>> {
+            let rhs = DVector::from_iterator(n, (0..n).map(|i| {
+                let prev = (i + n - 1) % n;
+                let next = (i + 1) % n;
+                6.0 * ((y[next] - y[i]) / h[i] - (y[i] - y[prev]) / h[prev])
+            }));
+            let m = lu.solve(&rhs)?;
+            Some((0..n).map(|i| {
+                let next = (i + 1) % n;
+                segment_coeffs(y[i], y[next], m[i], m[next], h[i])
+            }).collect())
+        };
+
+        let xs: Vec<f64> = points.iter().map(|p| p.0).collect();
+        let ys: Vec<f64> = points.iter().map(|p| p.1).collect();
+        let zs: Vec<f64> = points.iter().map(|p| p.2).collect();
+        let (Some(cx), Some(cy), Some(cz)) = (axis(&xs), axis(&ys), axis(&zs)) else { return; };
+        let Some(responses) = CurveCalculator::unit_responses(n, axis) else { return; };
+
+        let sx: Vec<f64> = sigmas.iter().map(|s| s.0).collect();
+        let sy: Vec<f64> = sigmas.iter().map(|s| s.1).collect();
+        let sz: Vec<f64> = sigmas.iter().map(|s| s.2).collect();
+
+        let reliable = n >= 4;
+        for i in 0..n {
+            data.add_point(frame, ts, reliable, types_str.to_string(), CurveFit {
+                model: ContourModel::PeriodicSpline.name(),
+                degree: 3,
+                segment: i as u32,
+                x_coeffs: cx[i].clone(),
+                y_coeffs: cy[i].clone(),
+                z_coeffs: cz[i].clone(),
+                x_covariance: CurveCalculator::coefficient_covariance(&responses[i], &sx),
+                y_covariance: CurveCalculator::coefficient_covariance(&responses[i], &sy),
+                z_covariance: CurveCalculator::coefficient_covariance(&responses[i], &sz),
+                rms_residual: 0.0,
+                condition_number,
+            });
+        }
+    }
+
+    fn fit_fourier(
+        data: &mut CoreCurve, frame: u32, ts: f32,
+        points: &[(f64, f64, f64)], sigmas: &[(f64, f64, f64)], types_str: &str,
+        (requested_harmonics, segments): (usize, usize)
+    ) {
+        let n = points.len();
+        let (s, _) = Self::closed_chord_length(points);
+
+        // 2K + 1 unknowns per axis so K is reduced when there are not enough points
+        let harmonics = requested_harmonics.max(1).min((n - 1) / 2).max(1);
+        let reliable = n > 2 * requested_harmonics.max(1);
+        let cols = 2 * harmonics + 1;
+
+        let mut m_data = Vec::with_capacity(n * cols);
+        for &si in &s {
+            m_data.push(1.0);
+            for k in 1..=harmonics {
+                let w = 2.0 * PI * k as f64 * si;
+                m_data.push(w.cos());
+                m_data.push(w.sin());
+            }
+        }
+        let m = DMatrix::from_row_slice(n, cols, &m_data);
+
+        let px = DVector::from_iterator(n, points.iter().map(|p| p.0));
+        let py = DVector::from_iterator(n, points.iter().map(|p| p.1));
+        let pz = DVector::from_iterator(n, points.iter().map(|p| p.2));
+
+        let svd = m.clone().svd(true, true);
+        let max = svd.singular_values.iter().cloned().fold(0.0, f64::max);
+        let min = svd.singular_values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let condition_number = if min <= 0.0 { f64::INFINITY } else { max / min };
+
+        let cx = svd.solve(&px, 1e-9).unwrap_or(DVector::from_element(cols, 0.0));
+        let cy = svd.solve(&py, 1e-9).unwrap_or(DVector::from_element(cols, 0.0));
+        let cz = svd.solve(&pz, 1e-9).unwrap_or(DVector::from_element(cols, 0.0));
+
+        let rx = &m * &cx - &px;
+        let ry = &m * &cy - &py;
+        let rz = &m * &cz - &pz;
+        let rms_residual = ((rx.norm_squared() + ry.norm_squared() + rz.norm_squared()) / n as f64).sqrt();
+
+        // value and derivative (with respect to s) of the fourier series
+        let eval = |c: &DVector<f64>, si: f64| -> (f64, f64) {
+            let mut value = c[0];
+            let mut slope = 0.0;
+            for k in 1..=harmonics {
+                let omega = 2.0 * PI * k as f64;
+                let w = omega * si;
+                value += c[2 * k - 1] * w.cos() + c[2 * k] * w.sin();
+                slope += omega * (-c[2 * k - 1] * w.sin() + c[2 * k] * w.cos());
+            }
+            (value, slope)
+        };
+
+        // cubic hermite segment between s0 and s1 (tangents scaled to the local u)
+        let hermite = |c: &DVector<f64>, s0: f64, s1: f64| -> CurveCoefficients {
+            let (p0, d0) = eval(c, s0);
+            let (p1, d1) = eval(c, s1);
+            let m0 = d0 * (s1 - s0);
+            let m1 = d1 * (s1 - s0);
+            CurveCoefficients::cubic(
+                2.0 * p0 - 2.0 * p1 + m0 + m1,
+                -3.0 * p0 + 3.0 * p1 - 2.0 * m0 - m1,
+                m0,
+                p0,
+            )
+        };
+
+        let segments = segments.max(3);
+        let bounds = |j: usize| (j as f64 / segments as f64, (j + 1) as f64 / segments as f64);
+
+        // the hermite segments are linear in the fourier coefficients which are linear in the points
+        let axis = |y: &[f64]| -> Option<Vec<CurveCoefficients>> {
+            let c = svd.solve(&DVector::from_column_slice(y), 1e-9).ok()?;
+            Some((0..segments).map(|j| {
+                let (s0, s1) = bounds(j);
+                hermite(&c, s0, s1)
+            }).collect())
+        };
+        let Some(responses) = CurveCalculator::unit_responses(n, axis) else { return; };
+
+        let sx: Vec<f64> = sigmas.iter().map(|s| s.0).collect();
+        let sy: Vec<f64> = sigmas.iter().map(|s| s.1).collect();
+        let sz: Vec<f64> = sigmas.iter().map(|s| s.2).collect();
+
+        for (j, response) in responses.iter().enumerate() {
+            let (s0, s1) = bounds(j);
+            data.add_point(frame, ts, reliable, types_str.to_string(), CurveFit {
+                model: ContourModel::Fourier { harmonics, segments }.name(),
+                degree: 3,
+                segment: j as u32,
+                x_coeffs: hermite(&cx, s0, s1),
+                y_coeffs: hermite(&cy, s0, s1),
+                z_coeffs: hermite(&cz, s0, s1),
+                x_covariance: CurveCalculator::coefficient_covariance(response, &sx),
+                y_covariance: CurveCalculator::coefficient_covariance(response, &sy),
+                z_covariance: CurveCalculator::coefficient_covariance(response, &sz),
+                rms_residual,
+                condition_number,
+            });
+        }
+    }
+}
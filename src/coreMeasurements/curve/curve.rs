@@ -1,4 +1,5 @@
 use crate::UMD::UMD::{UMD};
+use crate::coreMeasurements::pairs::pairs::{FrameIndex};
 use nalgebra::{DMatrix, DVector};
 use polars::prelude::*;
 use std::fs::File;
@@ -9,16 +10,119 @@ pub struct CurveCoefficients {
     pub b: f64,
     pub c: f64,
     pub d: f64,
+    pub higher: Vec<f64>, // t^n ... t^4 (highest first) for polynomials above degree 3 - empty for cubics and splines
+}
+
+impl CurveCoefficients {
+    pub fn cubic(a: f64, b: f64, c: f64, d: f64) -> Self {
+        Self { a, b, c, d, higher: Vec::new() }
+    }
+
+    // power basis coefficients (highest first) of any degree
+    pub fn from_power(coeffs: &[f64]) -> Self {
+        let mut padded = vec![0.0; 4usize.saturating_sub(coeffs.len())];
+        padded.extend_from_slice(coeffs);
+        let split = padded.len() - 4;
+        Self {
+            a: padded[split],
+            b: padded[split + 1],
+            c: padded[split + 2],
+            d: padded[split + 3],
+            higher: padded[..split].to_vec(),
+        }
+    }
+
+    // all coefficients highest first
+    pub fn power(&self) -> Vec<f64> {
+        let mut coeffs = self.higher.clone();
+        coeffs.extend_from_slice(&[self.a, self.b, self.c, self.d]);
+        coeffs
+    }
+
+    pub fn degree(&self) -> usize {
+        self.higher.len() + 3
+    }
+
+    pub fn eval(&self, t: f64) -> f64 {
+        self.power().iter().fold(0.0, |acc, c| acc * t + c)
+    }
+
+    pub fn derivative(&self, t: f64) -> f64 {
+        let coeffs = self.power();
+        let n = coeffs.len() - 1;
+        let mut acc = 0.0;
+        for (k, c) in coeffs.iter().take(n).enumerate() {
+            acc = acc * t + c * (n - k) as f64;
+        }
+        acc
+    }
+
+    pub fn second_derivative(&self, t: f64) -> f64 {
+        let coeffs = self.power();
+        let n = coeffs.len() - 1;
+        if n < 2 { return 0.0; }
+        let mut acc = 0.0;
+        for (k, c) in coeffs.iter().take(n - 1).enumerate() {
+            let p = (n - k) as f64;
+            acc = acc * t + c * p * (p - 1.0);
+        }
+        acc
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurveModel {
+    Polynomial(usize), // degree (reduced to points - 1 when there are not enough points)
+    NaturalSpline,     // natural cubic spline through the points (one row per segment)
+    CatmullRom,        // uniform Catmull-Rom spline through the points (one row per segment)
+}
+
+impl CurveModel {
+    pub fn name(&self) -> String {
+        match self {
+            Self::Polynomial(n) => format!("polynomial_{}", n),
+            Self::NaturalSpline => "natural_spline".to_string(),
+            Self::CatmullRom => "catmull_rom".to_string(),
+        }
+    }
+}
+
+// one fitted curve (or spline segment) with its uncertainty and diagnostics
+#[derive(Debug, Clone)]
+pub struct CurveFit {
+    pub model: String,
+    pub degree: u32,
+    pub segment: u32,
+    pub x_coeffs: CurveCoefficients,
+    pub y_coeffs: CurveCoefficients,
+    pub z_coeffs: CurveCoefficients,
+    pub x_covariance: Vec<f64>,
+    pub y_covariance: Vec<f64>,
+    pub z_covariance: Vec<f64>,
+    pub rms_residual: f64,
+    pub condition_number: f64,
 }
 
 pub struct CoreCurve {
     pub frame: Vec<u32>,
     pub timestamp: Vec<f32>,
-    pub is_reliable: Vec<bool>, // for undertermined points (i.e. the 3 "inner" lip openface points) - as we are fitting to a cubic curve
-    pub types_included: Vec<String>, 
+    pub is_reliable: Vec<bool>, // for undertermined points (i.e. the 3 "inner" lip openface points) - fewer points than the model needs
+    pub types_included: Vec<String>,
+    pub model: Vec<String>,
+    pub degree: Vec<u32>, // degree actually fitted
+    pub segment: Vec<u32>, // 0 for polynomials, segment number for splines
     pub x_coeffs: Vec<CurveCoefficients>,
     pub y_coeffs: Vec<CurveCoefficients>,
     pub z_coeffs: Vec<CurveCoefficients>,
+
+    // covariance of the cubic coefficients (a, b, c, d) propagated from the landmark uncertainties (4x4 row major)
+    pub x_covariance: Vec<Vec<f64>>,
+    pub y_covariance: Vec<Vec<f64>>,
+    pub z_covariance: Vec<Vec<f64>>,
+
+    // fit diagnostics
+    pub rms_residual: Vec<f64>, // 0 for the interpolating splines
+    pub condition_number: Vec<f64>,
 }
 
 impl CoreCurve {
@@ -28,26 +132,39 @@ impl CoreCurve {
             timestamp: Vec::with_capacity(estimated_frames),
             is_reliable: Vec::with_capacity(estimated_frames),
             types_included: Vec::with_capacity(estimated_frames),
+            model: Vec::with_capacity(estimated_frames),
+            degree: Vec::with_capacity(estimated_frames),
+            segment: Vec::with_capacity(estimated_frames),
             x_coeffs: Vec::with_capacity(estimated_frames),
             y_coeffs: Vec::with_capacity(estimated_frames),
             z_coeffs: Vec::with_capacity(estimated_frames),
+            x_covariance: Vec::with_capacity(estimated_frames),
+            y_covariance: Vec::with_capacity(estimated_frames),
+            z_covariance: Vec::with_capacity(estimated_frames),
+            rms_residual: Vec::with_capacity(estimated_frames),
+            condition_number: Vec::with_capacity(estimated_frames),
         }
     }
 
     pub fn add_point(
         &mut self, frame: u32, timestamp: f32, is_reliable: bool,
-        types_included: String, 
-        x_coeffs: CurveCoefficients,
-        y_coeffs: CurveCoefficients,
-        z_coeffs: CurveCoefficients,
+        types_included: String, fit: CurveFit,
     ) {
         self.frame.push(frame);
         self.timestamp.push(timestamp);
         self.is_reliable.push(is_reliable);
         self.types_included.push(types_included);
-        self.x_coeffs.push(x_coeffs);
-        self.y_coeffs.push(y_coeffs);
-        self.z_coeffs.push(z_coeffs);
+        self.model.push(fit.model);
+        self.degree.push(fit.degree);
+        self.segment.push(fit.segment);
+        self.x_coeffs.push(fit.x_coeffs);
+        self.y_coeffs.push(fit.y_coeffs);
+        self.z_coeffs.push(fit.z_coeffs);
+        self.x_covariance.push(fit.x_covariance);
+        self.y_covariance.push(fit.y_covariance);
+        self.z_covariance.push(fit.z_covariance);
+        self.rms_residual.push(fit.rms_residual);
+        self.condition_number.push(fit.condition_number);
     }
 
     pub fn save_curve_to_parquet(curve: &CoreCurve, file_path: &str) -> PolarsResult<()> {
@@ -55,6 +172,9 @@ impl CoreCurve {
         let s_time = Series::new("timestamp", &curve.timestamp);
         let s_is_reliable = Series::new("is_reliable", &curve.is_reliable);
         let s_coord_1 = Series::new("types_included", &curve.types_included);
+        let s_model = Series::new("model", &curve.model);
+        let s_degree = Series::new("degree", &curve.degree);
+        let s_segment = Series::new("segment", &curve.segment);
 
         let s_x_a = Series::new("x_a", curve.x_coeffs.iter().map(|c| c.a).collect::<Vec<f64>>());
         let s_x_b = Series::new("x_b", curve.x_coeffs.iter().map(|c| c.b).collect::<Vec<f64>>());
@@ -71,13 +191,30 @@ impl CoreCurve {
         let s_z_c = Series::new("z_c", curve.z_coeffs.iter().map(|c| c.c).collect::<Vec<f64>>());
         let s_z_d = Series::new("z_d", curve.z_coeffs.iter().map(|c| c.d).collect::<Vec<f64>>());
 
-        let mut df = DataFrame::new(vec![ 
-            s_frame, s_time, s_coord_1, s_is_reliable, 
-            s_x_a, s_x_b, s_x_c, s_x_d, 
-            s_y_a, s_y_b, s_y_c, s_y_d, 
+        // higher order terms (t^n ... t^4) as list columns
+        let s_x_higher = Series::new("x_higher", curve.x_coeffs.iter().map(|c| Series::new("", &c.higher)).collect::<Vec<Series>>());
+        let s_y_higher = Series::new("y_higher", curve.y_coeffs.iter().map(|c| Series::new("", &c.higher)).collect::<Vec<Series>>());
+        let s_z_higher = Series::new("z_higher", curve.z_coeffs.iter().map(|c| Series::new("", &c.higher)).collect::<Vec<Series>>());
+
+        // coefficient covariance (a, b, c, d - 4x4 row major) as list columns
+        let s_x_cov = Series::new("x_covariance", curve.x_covariance.iter().map(|c| Series::new("", c)).collect::<Vec<Series>>());
+        let s_y_cov = Series::new("y_covariance", curve.y_covariance.iter().map(|c| Series::new("", c)).collect::<Vec<Series>>());
+        let s_z_cov = Series::new("z_covariance", curve.z_covariance.iter().map(|c| Series::new("", c)).collect::<Vec<Series>>());
+
+        let s_rms = Series::new("rms_residual", &curve.rms_residual);
+        let s_cond = Series::new("condition_number", &curve.condition_number);
+
+        let mut df = DataFrame::new(vec![
+            s_frame, s_time, s_coord_1, s_is_reliable,
+            s_model, s_degree, s_segment,
+            s_x_a, s_x_b, s_x_c, s_x_d,
+            s_y_a, s_y_b, s_y_c, s_y_d,
             s_z_a, s_z_b, s_z_c, s_z_d,
+            s_x_higher, s_y_higher, s_z_higher,
+            s_x_cov, s_y_cov, s_z_cov,
+            s_rms, s_cond,
         ])?;
-        
+
         let file = File::create(file_path).map_err(PolarsError::from)?;
         ParquetWriter::new(file).finish(&mut df)?;
         println!("Successfully exported curve data to: {}", file_path);
@@ -88,7 +225,12 @@ impl CoreCurve {
 pub struct CurveCalculator;
 
 impl CurveCalculator {
+    // default model: a single cubic per landmark set
     pub fn fit_curve(umd: &UMD, landmark_sets: &[&[&str]]) -> CoreCurve {
+        Self::fit_curve_with_model(umd, landmark_sets, CurveModel::Polynomial(3))
+    }
+
+    pub fn fit_curve_with_model(umd: &UMD, landmark_sets: &[&[&str]], model: CurveModel) -> CoreCurve {
         let total_entries = umd.frame.len();
         if total_entries == 0 { return CoreCurve::construction(0); }
 
@@ -102,31 +244,85 @@ impl CurveCalculator {
             while i < total_entries && umd.frame[i] == current_frame { i += 1; }
             let end_idx = i;
 
+            let index = FrameIndex::new(umd, start_idx, end_idx);
+
             for (k, set) in landmark_sets.iter().enumerate() {
                 let mut points = Vec::new();
+                let mut sigmas = Vec::new();
                 for &target in *set {
-                    for idx in start_idx..end_idx {
-                        if umd.types[idx] == target {
-                            points.push((umd.x_rotated[idx], umd.y_rotated[idx], umd.z_rotated[idx]));
-                            break; 
-                        }
+                    if let Some(idx) = index.get(target) {
+                        points.push((umd.x_rotated[idx], umd.y_rotated[idx], umd.z_rotated[idx]));
+                        sigmas.push(Self::point_sigma(umd, idx));
                     }
                 }
-                Self::process_frame(&mut curve_data, current_frame, umd.timestamp[start_idx], &points, &set_identifiers[k]);
+                Self::process_frame(&mut curve_data, current_frame, umd.timestamp[start_idx], &points, &sigmas, &set_identifiers[k], model);
             }
         }
         curve_data
     }
 
-    fn process_frame(data: &mut CoreCurve, frame: u32, ts: f32, points: &[(f64, f64, f64)], types_str: &str) {
-        if points.len() < 3 { return; } 
+    fn process_frame(
+        data: &mut CoreCurve, frame: u32, ts: f32,
+        points: &[(f64, f64, f64)], sigmas: &[(f64, f64, f64)],
+        types_str: &str, model: CurveModel
+    ) {
+        if points.len() < 2 { return; }
+
+        match model {
+            CurveModel::Polynomial(degree) => Self::fit_polynomial(data, frame, ts, points, sigmas, types_str, degree),
+            CurveModel::NaturalSpline => Self::fit_natural_spline(data, frame, ts, points, sigmas, types_str),
+            CurveModel::CatmullRom => Self::fit_catmull_rom(data, frame, ts, points, sigmas, types_str),
+        }
+    }
+
+    // rotated coordinate uncertainty of a landmark (0 when the UMD has no uncertainty)
+    pub(crate) fn point_sigma(umd: &UMD, idx: usize) -> (f64, f64, f64) {
+        (
+            if idx < umd.x_rotated_uncertainty.len() { umd.x_rotated_uncertainty[idx] } else { 0.0 },
+            if idx < umd.y_rotated_uncertainty.len() { umd.y_rotated_uncertainty[idx] } else { 0.0 },
+            if idx < umd.z_rotated_uncertainty.len() { umd.z_rotated_uncertainty[idx] } else { 0.0 },
+        )
+    }
+
+    // every model is linear in the landmark positions (for a fixed parameterisation) so the coefficients of each
+    // segment are sum_i w_i y_i. Fitting a unit vector e_i gives w_i for every segment: result[segment][point]
+    pub(crate) fn unit_responses<F>(n: usize, fit: F) -> Option<Vec<Vec<[f64; 4]>>>
+    where F: Fn(&[f64]) -> Option<Vec<CurveCoefficients>> {
+        let mut responses: Vec<Vec<[f64; 4]>> = Vec::new();
+        for i in 0..n {
+            let mut unit = vec![0.0; n];
+            unit[i] = 1.0;
+            let segments = fit(&unit)?;
+            if responses.is_empty() {
+                responses = vec![Vec::with_capacity(n); segments.len()];
+            }
+            for (s, c) in segments.iter().enumerate() {
+                responses[s].push([c.a, c.b, c.c, c.d]);
+            }
+        }
+        Some(responses)
+    }
 
-        let reliable = points.len() >= 4;
+    // Cov(a, b, c, d) = sum_i sigma_i^2 w_i w_i^T (landmarks are independent), 4x4 row major
+    pub(crate) fn coefficient_covariance(weights: &[[f64; 4]], sigma: &[f64]) -> Vec<f64> {
+        let mut cov = vec![0.0; 16];
+        for (w, s) in weights.iter().zip(sigma) {
+            let var = s * s;
+            for r in 0..4 {
+                for c in 0..4 {
+                    cov[r * 4 + c] += var * w[r] * w[c];
+                }
+            }
+        }
+        cov
+    }
 
+    // chord length parameterisation (0 - 1)
+    pub(crate) fn chord_length(points: &[(f64, f64, f64)]) -> Vec<f64> {
         let mut d = Vec::with_capacity(points.len() - 1);
         for i in 0..points.len() - 1 {
-            let dist = ((points[i+1].0 - points[i].0).powi(2) + 
-                        (points[i+1].1 - points[i].1).powi(2) + 
+            let dist = ((points[i+1].0 - points[i].0).powi(2) +
+                        (points[i+1].1 - points[i].1).powi(2) +
                         (points[i+1].2 - points[i].2).powi(2)).sqrt();
             d.push(dist);
         }
@@ -138,31 +334,205 @@ impl CurveCalculator {
             cumulative_d += dist;
             t.push(if l > 0.0 { cumulative_d / l } else { 0.0 });
         }
+        t
+    }
+
+    fn fit_polynomial(
+        data: &mut CoreCurve, frame: u32, ts: f32,
+        points: &[(f64, f64, f64)], sigmas: &[(f64, f64, f64)],
+        types_str: &str, requested_degree: usize
+    ) {
+        let requested_degree = requested_degree.max(1);
+        let degree = requested_degree.min(points.len() - 1);
+        let reliable = points.len() > requested_degree;
+
+        let t = Self::chord_length(points);
 
         let rows = points.len();
-        let mut m_data = Vec::with_capacity(rows * 4);
+        let cols = degree + 1;
+        let mut m_data = Vec::with_capacity(rows * cols);
         for val_t in &t {
-            m_data.push(val_t.powi(3));
-            m_data.push(val_t.powi(2));
-            m_data.push(*val_t);
-            m_data.push(1.0);
+            for p in (0..cols).rev() {
+                m_data.push(val_t.powi(p as i32));
+            }
         }
-        let m = DMatrix::from_row_slice(rows, 4, &m_data);
+        let m = DMatrix::from_row_slice(rows, cols, &m_data);
 
         let px = DVector::from_iterator(rows, points.iter().map(|p| p.0));
         let py = DVector::from_iterator(rows, points.iter().map(|p| p.1));
         let pz = DVector::from_iterator(rows, points.iter().map(|p| p.2));
 
-        let svd = m.svd(true, true);
-        let cx = svd.solve(&px, 1e-9).unwrap_or(DVector::from_element(4, 0.0));
-        let cy = svd.solve(&py, 1e-9).unwrap_or(DVector::from_element(4, 0.0));
-        let cz = svd.solve(&pz, 1e-9).unwrap_or(DVector::from_element(4, 0.0));
+        let svd = m.clone().svd(true, true);
+        let condition_number = Self::condition_number(&svd.singular_values);
+        let cx = svd.solve(&px, 1e-9).unwrap_or(DVector::from_element(cols, 0.0));
+        let cy = svd.solve(&py, 1e-9).unwrap_or(DVector::from_element(cols, 0.0));
+        let cz = svd.solve(&pz, 1e-9).unwrap_or(DVector::from_element(cols, 0.0));
+
+        // residuals in 3D
+        let rx = &m * &cx - &px;
+        let ry = &m * &cy - &py;
+        let rz = &m * &cz - &pz;
+        let rms_residual = ((rx.norm_squared() + ry.norm_squared() + rz.norm_squared()) / rows as f64).sqrt();
+
+        // coefficient covariance from the SVD: Cov = M+ diag(sigma^2) M+^T with M+ = V S^-1 U^T
+        // column i of M+ is the weight of point i - only the cubic block (a, b, c, d) is kept
+        let pinv = svd.pseudo_inverse(1e-9).unwrap_or(DMatrix::zeros(cols, rows));
+        let weights: Vec<[f64; 4]> = (0..rows).map(|i| {
+            let mut w = [0.0; 4];
+            for (r, w_r) in w.iter_mut().enumerate() {
+                let src = cols as isize - 4 + r as isize;
+                if src >= 0 { *w_r = pinv[(src as usize, i)]; }
+            }
+            w
+        }).collect();
+        let sx: Vec<f64> = sigmas.iter().map(|s| s.0).collect();
+        let sy: Vec<f64> = sigmas.iter().map(|s| s.1).collect();
+        let sz: Vec<f64> = sigmas.iter().map(|s| s.2).collect();
 
-        data.add_point(
-            frame, ts, reliable, types_str.to_string(),
-            CurveCoefficients { a: cx[0], b: cx[1], c: cx[2], d: cx[3] },
-            CurveCoefficients { a: cy[0], b: cy[1], c: cy[2], d: cy[3] },
-            CurveCoefficients { a: cz[0], b: cz[1], c: cz[2], d: cz[3] },
-        );
+        data.add_point(frame, ts, reliable, types_str.to_string(), CurveFit {
+            model: CurveModel::Polynomial(requested_degree).name(),
+            degree: degree as u32,
+            segment: 0,
+            x_coeffs: CurveCoefficients::from_power(cx.as_slice()),
+            y_coeffs: CurveCoefficients::from_power(cy.as_slice()),
+            z_coeffs: CurveCoefficients::from_power(cz.as_slice()),
+            x_covariance: Self::coefficient_covariance(&weights, &sx),
+            y_covariance: Self::coefficient_covariance(&weights, &sy),
+            z_covariance: Self::coefficient_covariance(&weights, &sz),
+            rms_residual,
+            condition_number,
+        });
     }
-}
\ No newline at end of file
+
+    fn fit_natural_spline(
+        data: &mut CoreCurve, frame: u32, ts: f32,
+        points: &[(f64, f64, f64)], sigmas: &[(f64, f64, f64)], types_str: &str
+    ) {
+        let t = Self::chord_length(points);
+        let n = points.len();
+        let reliable = n >= 3; // 2 points is just a straight line
+
+        let h: Vec<f64> = (0..n - 1).map(|i| t[i + 1] - t[i]).collect();
+        if h.iter().any(|&hi| hi <= 0.0) { return; } // repeated points
+
+        // tridiagonal system for the interior second derivatives (ends are 0 for a natural spline)
+        let interior = n.saturating_sub(2);
+        let mut a = DMatrix::<f64>::zeros(interior, interior);
+        for r in 0..interior {
+            a[(r, r)] = 2.0 * (h[r] + h[r + 1]);
+            if r > 0 { a[(r, r - 1)] = h[r]; }
+            if r + 1 < interior { a[(r, r + 1)] = h[r + 1]; }
+        }
+
+        let condition_number = if interior > 0 {
+            Self::condition_number(&a.clone().singular_values())
+        } else { 1.0 };
+
+        // each segment is re-parameterised to a local u = (t - t_i) / h_i in [0, 1]
+        let segment_coeffs = |y0: f64, y1: f64, m0: f64, m1: f64, hi: f64| -> CurveCoefficients {
+            let slope = (y1 - y0) / hi - hi * (2.0 * m0 + m1) / 6.0;
+            CurveCoefficients::cubic(
+                (m1 - m0) * hi * hi / 6.0,
+                m0 * hi * hi / 2.0,
+                slope * hi,
+                y0,
+            )
+        };
+
+        // segment coefficients for a single axis
+        let axis = |y: &[f64]| -> Option<Vec<CurveCoefficients>> {
+            let mut second = vec![0.0; n];
+            if interior > 0 {
+                let rhs = DVector::from_iterator(interior, (1..n - 1).map(|i| {
+                    6.0 * ((y[i + 1] - y[i]) / h[i] - (y[i] - y[i - 1]) / h[i - 1])
+                }));
+                let solved = a.clone().lu().solve(&rhs)?;
+                for r in 0..interior { second[r + 1] = solved[r]; }
+            }
+            Some((0..n - 1).map(|i| segment_coeffs(y[i], y[i + 1], second[i], second[i + 1], h[i])).collect())
+        };
+
+        let xs: Vec<f64> = points.iter().map(|p| p.0).collect();
+        let ys: Vec<f64> = points.iter().map(|p| p.1).collect();
+        let zs: Vec<f64> = points.iter().map(|p| p.2).collect();
+        let (Some(cx), Some(cy), Some(cz)) = (axis(&xs), axis(&ys), axis(&zs)) else { return; };
+        let Some(responses) = Self::unit_responses(n, axis) else { return; };
+
+        let sx: Vec<f64> = sigmas.iter().map(|s| s.0).collect();
+        let sy: Vec<f64> = sigmas.iter().map(|s| s.1).collect();
+        let sz: Vec<f64> = sigmas.iter().map(|s| s.2).collect();
+
+        for i in 0..n - 1 {
+            data.add_point(frame, ts, reliable, types_str.to_string(), CurveFit {
+                model: CurveModel::NaturalSpline.name(),
+                degree: 3,
+                segment: i as u32,
+                x_coeffs: cx[i].clone(),
+                y_coeffs: cy[i].clone(),
+                z_coeffs: cz[i].clone(),
+                x_covariance: Self::coefficient_covariance(&responses[i], &sx),
+                y_covariance: Self::coefficient_covariance(&responses[i], &sy),
+                z_covariance: Self::coefficient_covariance(&responses[i], &sz),
+                rms_residual: 0.0,
+                condition_number,
+            });
+        }
+    }
+
+    fn fit_catmull_rom(
+        data: &mut CoreCurve, frame: u32, ts: f32,
+        points: &[(f64, f64, f64)], sigmas: &[(f64, f64, f64)], types_str: &str
+    ) {
+        let n = points.len();
+        let reliable = n >= 3;
+
+        // 0.5 * [(-p0 + 3p1 - 3p2 + p3)u^3 + (2p0 - 5p1 + 4p2 - p3)u^2 + (-p0 + p2)u + 2p1]
+        let segment_coeffs = |p0: f64, p1: f64, p2: f64, p3: f64| -> CurveCoefficients {
+            CurveCoefficients::cubic(
+                0.5 * (-p0 + 3.0 * p1 - 3.0 * p2 + p3),
+                0.5 * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3),
+                0.5 * (-p0 + p2),
+                p1,
+            )
+        };
+
+        // end points are duplicated so the curve still passes through them
+        let axis = |y: &[f64]| -> Option<Vec<CurveCoefficients>> {
+            Some((0..n - 1).map(|i| {
+                segment_coeffs(y[i.saturating_sub(1)], y[i], y[i + 1], y[(i + 2).min(n - 1)])
+            }).collect())
+        };
+
+        let xs: Vec<f64> = points.iter().map(|p| p.0).collect();
+        let ys: Vec<f64> = points.iter().map(|p| p.1).collect();
+        let zs: Vec<f64> = points.iter().map(|p| p.2).collect();
+        let (Some(cx), Some(cy), Some(cz)) = (axis(&xs), axis(&ys), axis(&zs)) else { return; };
+        let Some(responses) = Self::unit_responses(n, axis) else { return; };
+
+        let sx: Vec<f64> = sigmas.iter().map(|s| s.0).collect();
+        let sy: Vec<f64> = sigmas.iter().map(|s| s.1).collect();
+        let sz: Vec<f64> = sigmas.iter().map(|s| s.2).collect();
+
+        for i in 0..n - 1 {
+            data.add_point(frame, ts, reliable, types_str.to_string(), CurveFit {
+                model: CurveModel::CatmullRom.name(),
+                degree: 3,
+                segment: i as u32,
+                x_coeffs: cx[i].clone(),
+                y_coeffs: cy[i].clone(),
+                z_coeffs: cz[i].clone(),
+                x_covariance: Self::coefficient_covariance(&responses[i], &sx),
+                y_covariance: Self::coefficient_covariance(&responses[i], &sy),
+                z_covariance: Self::coefficient_covariance(&responses[i], &sz),
+                rms_residual: 0.0,
+                condition_number: 1.0, // no system is solved so the condition number is always 1
+            });
+        }
+    }
+
+    fn condition_number(singular_values: &DVector<f64>) -> f64 {
+        let max = singular_values.iter().cloned().fold(0.0, f64::max);
+        let min = singular_values.iter().cloned().fold(f64::INFINITY, f64::min);
+        if min <= 0.0 { f64::INFINITY } else { max / min }
+    }
+}
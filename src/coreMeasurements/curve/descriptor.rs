@@ -0,0 +1,264 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Curve descriptors
+
+    Geometric quantities derived from the CoreCurve coefficients (arc length, curvature, tangent angles)
+    plus the tongue shape indices used in the literature. Rows of a CoreCurve that belong to the same
+    curve in the same frame (i.e. spline/contour segments) are joined and described as one curve.
+
+    Signed curvature, tangent angles and inflection points are measured in the x-y plane (pose corrected).
+*/
+
+use crate::coreMeasurements::curve::curve::{CoreCurve};
+use nalgebra::{Vector3};
+use polars::prelude::*;
+use std::fs::File;
+
+const SAMPLES_PER_SEGMENT: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct CurveDescriptor {
+    pub frame: Vec<u32>,
+    pub timestamp: Vec<f32>,
+    pub types_included: Vec<String>,
+    pub model: Vec<String>,
+
+    pub arc_length: Vec<f64>,
+    pub mean_curvature: Vec<f64>,
+    pub max_curvature: Vec<f64>,
+    pub midpoint_curvature: Vec<f64>,
+    pub peak_curvature_location: Vec<f64>, // proportion of the arc length (0 - 1)
+    pub start_tangent_angle: Vec<f64>, // rads
+    pub end_tangent_angle: Vec<f64>, // rads
+
+    // tongue shape indices
+    pub curvature_degree: Vec<f64>,
+    pub modified_curvature_index: Vec<f64>,
+    pub inflection_points: Vec<u32>,
+}
+
+impl CurveDescriptor {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            frame: Vec::with_capacity(estimated_entries),
+            timestamp: Vec::with_capacity(estimated_entries),
+            types_included: Vec::with_capacity(estimated_entries),
+            model: Vec::with_capacity(estimated_entries),
+
+            arc_length: Vec::with_capacity(estimated_entries),
+            mean_curvature: Vec::with_capacity(estimated_entries),
+            max_curvature: Vec::with_capacity(estimated_entries),
+            midpoint_curvature: Vec::with_capacity(estimated_entries),
+            peak_curvature_location: Vec::with_capacity(estimated_entries),
+            start_tangent_angle: Vec::with_capacity(estimated_entries),
+            end_tangent_angle: Vec::with_capacity(estimated_entries),
+
+            curvature_degree: Vec::with_capacity(estimated_entries),
+            modified_curvature_index: Vec::with_capacity(estimated_entries),
+            inflection_points: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn save_curve_descriptor_to_parquet(data: &CurveDescriptor, file_path: &str) -> PolarsResult<()> {
+        let s_frame = Series::new("frame", &data.frame);
+        let s_time = Series::new("timestamp", &data.timestamp);
+        let s_types = Series::new("types_included", &data.types_included);
+        let s_model = Series::new("model", &data.model);
+
+        let s_arc_length = Series::new("arc_length", &data.arc_length);
+        let s_mean_curvature = Series::new("mean_curvature", &data.mean_curvature);
+        let s_max_curvature = Series::new("max_curvature", &data.max_curvature);
+        let s_midpoint_curvature = Series::new("midpoint_curvature", &data.midpoint_curvature);
+        let s_peak_location = Series::new("peak_curvature_location", &data.peak_curvature_location);
+        let s_start_angle = Series::new("start_tangent_angle", &data.start_tangent_angle);
+        let s_end_angle = Series::new("end_tangent_angle", &data.end_tangent_angle);
+
+        let s_curvature_degree = Series::new("curvature_degree", &data.curvature_degree);
+        let s_mci = Series::new("modified_curvature_index", &data.modified_curvature_index);
+        let s_inflections = Series::new("inflection_points", &data.inflection_points);
+
+        let mut df = DataFrame::new(vec![
+            s_frame, s_time, s_types, s_model,
+            s_arc_length, s_mean_curvature, s_max_curvature, s_midpoint_curvature,
+            s_peak_location, s_start_angle, s_end_angle,
+            s_curvature_degree, s_mci, s_inflections,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported curve descriptor data to: {}", file_path);
+        Ok(())
+    }
+}
+
+// a single sample along the curve
+struct CurveSample {
+    position: Vector3<f64>,
+    first: Vector3<f64>,
+    curvature: f64,
+    signed_curvature: f64, // x-y plane
+}
+
+pub struct DescriptorCalculator;
+
+impl DescriptorCalculator {
+    pub fn describe(curve: &CoreCurve) -> CurveDescriptor {
+        let total_entries = curve.frame.len();
+        let mut descriptor_data = CurveDescriptor::construction(total_entries);
+
+        let mut i = 0;
+        while i < total_entries {
+            let start_idx = i;
+            // segments of the same curve are next to each other
+            while i < total_entries
+                && curve.frame[i] == curve.frame[start_idx]
+                && curve.types_included[i] == curve.types_included[start_idx] {
+                i += 1;
+            }
+            let end_idx = i;
+
+            let samples = Self::sample(curve, start_idx, end_idx);
+            if samples.len() < 3 { continue; }
+
+            Self::process_curve(&mut descriptor_data, curve, start_idx, &samples);
+        }
+
+        descriptor_data
+    }
+
+    fn sample(curve: &CoreCurve, start_idx: usize, end_idx: usize) -> Vec<CurveSample> {
+        let mut samples = Vec::with_capacity((end_idx - start_idx) * SAMPLES_PER_SEGMENT + 1);
+
+        for j in start_idx..end_idx {
+            let (x, y, z) = (&curve.x_coeffs[j], &curve.y_coeffs[j], &curve.z_coeffs[j]);
+            // the first sample of each segment after the first is the last sample of the previous one
+            let first_k = if j == start_idx { 0 } else { 1 };
+
+            for k in first_k..=SAMPLES_PER_SEGMENT {
+                let u = k as f64 / SAMPLES_PER_SEGMENT as f64;
+                let position = Vector3::new(x.eval(u), y.eval(u), z.eval(u));
+                let first = Vector3::new(x.derivative(u), y.derivative(u), z.derivative(u));
+                let second = Vector3::new(x.second_derivative(u), y.second_derivative(u), z.second_derivative(u));
+
+                let speed = first.norm();
+                let curvature = if speed > 0.0 { first.cross(&second).norm() / speed.powi(3) } else { 0.0 };
+
+                let speed_xy = (first.x.powi(2) + first.y.powi(2)).sqrt();
+                let signed_curvature = if speed_xy > 0.0 {
+                    (first.x * second.y - first.y * second.x) / speed_xy.powi(3)
+                } else { 0.0 };
+
+                samples.push(CurveSample { position, first, curvature, signed_curvature });
+            }
+        }
+        samples
+    }
+
+    fn process_curve(data: &mut CurveDescriptor, curve: &CoreCurve, row: usize, samples: &[CurveSample]) {
+        // cumulative arc length (trapezoid rule on the chord between samples)
+        let mut s = Vec::with_capacity(samples.len());
+        s.push(0.0);
+        for k in 1..samples.len() {
+            let ds = (samples[k].position - samples[k - 1].position).norm();
+            s.push(s[k - 1] + ds);
+        }
+        let arc_length = *s.last().unwrap();
+
+        let mut integral = 0.0;
+        for k in 1..samples.len() {
+            let ds = s[k] - s[k - 1];
+            integral += 0.5 * (samples[k].curvature + samples[k - 1].curvature) * ds;
+        }
+
+        let mean_curvature = if arc_length > 0.0 { integral / arc_length } else { 0.0 };
+
+        // MCI (Dawson et al., 2016) is defined on the 2D (midsagittal) contour: the integral of the absolute signed
+        // curvature along the contour, so it is taken in the pose corrected x-y plane with the x-y arc length
+        let mut modified_curvature_index = 0.0;
+        for k in 1..samples.len() {
+            let delta = samples[k].position - samples[k - 1].position;
+            let ds_xy = (delta.x.powi(2) + delta.y.powi(2)).sqrt();
+            modified_curvature_index += 0.5 * (samples[k].signed_curvature.abs() + samples[k - 1].signed_curvature.abs()) * ds_xy;
+        }
+
+        let (peak_idx, max_curvature) = samples.iter().enumerate()
+            .map(|(k, sample)| (k, sample.curvature))
+            .fold((0, f64::MIN), |best, current| if current.1 > best.1 { current } else { best });
+        let peak_curvature_location = if arc_length > 0.0 { s[peak_idx] / arc_length } else { 0.0 };
+
+        let midpoint_curvature = Self::interpolate_at(&s, samples, arc_length / 2.0);
+
+        let start = &samples[0];
+        let end = &samples[samples.len() - 1];
+        let start_tangent_angle = start.first.y.atan2(start.first.x);
+        let end_tangent_angle = end.first.y.atan2(end.first.x);
+
+        // curvature degree: max distance from the chord (first -> last point) relative to the chord length
+        let chord = end.position - start.position;
+        let chord_length = chord.norm();
+        let curvature_degree = if chord_length > 1e-12 {
+            let direction = chord / chord_length;
+            let max_height = samples.iter()
+                .map(|sample| (sample.position - start.position).cross(&direction).norm())
+                .fold(0.0, f64::max);
+            max_height / chord_length
+        } else {
+            f64::NAN // closed contours have no chord
+        };
+
+        // inflection points are sign changes of the signed curvature (ignoring values that are effectively 0)
+        let tolerance = 1e-6 * samples.iter().map(|sample| sample.signed_curvature.abs()).fold(0.0, f64::max);
+        let mut inflection_points = 0;
+        let mut last_sign = 0.0;
+        for sample in samples {
+            if sample.signed_curvature.abs() <= tolerance { continue; }
+            let sign = sample.signed_curvature.signum();
+            if last_sign != 0.0 && sign != last_sign { inflection_points += 1; }
+            last_sign = sign;
+        }
+
+        data.frame.push(curve.frame[row]);
+        data.timestamp.push(curve.timestamp[row]);
+        data.types_included.push(curve.types_included[row].clone());
+        data.model.push(curve.model[row].clone());
+
+        data.arc_length.push(arc_length);
+        data.mean_curvature.push(mean_curvature);
+        data.max_curvature.push(max_curvature);
+        data.midpoint_curvature.push(midpoint_curvature);
+        data.peak_curvature_location.push(peak_curvature_location);
+        data.start_tangent_angle.push(start_tangent_angle);
+        data.end_tangent_angle.push(end_tangent_angle);
+
+        data.curvature_degree.push(curvature_degree);
+        data.modified_curvature_index.push(modified_curvature_index);
+        data.inflection_points.push(inflection_points);
+    }
+
+    // linear interpolation of the curvature at arc length `target`
+    fn interpolate_at(s: &[f64], samples: &[CurveSample], target: f64) -> f64 {
+        for k in 1..s.len() {
+            if s[k] >= target {
+                let span = s[k] - s[k - 1];
+                if span <= 0.0 { return samples[k].curvature; }
+                let w = (target - s[k - 1]) / span;
+                return samples[k - 1].curvature * (1.0 - w) + samples[k].curvature * w;
+            }
+        }
+        samples[samples.len() - 1].curvature
+    }
+}
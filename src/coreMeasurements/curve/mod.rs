@@ -13,4 +13,5 @@ You should have received a copy of the GNU General Public License along with
 MOSAIC. If not, see <https://www.gnu.org/licenses/>.
 */
 
-pub mod curve;
\ No newline at end of file
+pub mod curve;
+pub mod descriptor;
\ No newline at end of file
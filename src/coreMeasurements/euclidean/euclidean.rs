@@ -18,6 +18,7 @@ MOSAIC. If not, see <https://www.gnu.org/licenses/>.
 */
 
 use crate::UMD::UMD::{UMD};
+use crate::coreMeasurements::pairs::pairs::{PairSpec, FrameIndex};
 use polars::prelude::*;
 use std::fs::File;
 
@@ -127,11 +128,11 @@ impl CoreEuclidean {
 
 pub struct EuclideanCalculator;
 
-struct DistanceCalc;
+pub(crate) struct DistanceCalc;
 
 impl EuclideanCalculator {
 
-    pub fn euclidean(umd: &UMD, pairs: &[String; 2]) -> CoreEuclidean {
+    pub fn euclidean(umd: &UMD, pairs: &[PairSpec]) -> CoreEuclidean {
         let total_points = umd.frame.len();
         if total_points == 0 {
             return CoreEuclidean::construction(0);
@@ -139,10 +140,9 @@ impl EuclideanCalculator {
 
         let estimated_frames = (total_points / 68) + 1;
         let mut euclidean_data = CoreEuclidean::construction(estimated_frames);
+        let mut frame_pairs: Vec<(Option<usize>, usize)> = Vec::new();
 
-        let p1_is_origin = pairs[0].to_lowercase() == "origin";
-        let target_all = pairs[1] == "*";
-
+        // every pair spec is resolved in the same pass over the frames
         let mut i = 0;
         while i < total_points {
             let start_idx = i;
@@ -154,39 +154,16 @@ impl EuclideanCalculator {
             }
             let end_idx = i;
 
-            if p1_is_origin {
-                if target_all {
-                    for k in start_idx..end_idx {
-                        Self::process_with_origin(&mut euclidean_data, umd, k);
-                    }
-                } else {
-                    for k in start_idx..end_idx {
-                        if &umd.types[k] == &pairs[1] {
-                            Self::process_with_origin(&mut euclidean_data, umd, k);
-                        }
-                    }
-                }
-            } else {
-                let mut p1_idx = None;
-                for k in start_idx..end_idx {
-                    if &umd.types[k] == &pairs[0] {
-                        p1_idx = Some(k);
-                        break;
-                    }
-                }
+            let index = FrameIndex::new(umd, start_idx, end_idx);
+            frame_pairs.clear();
+            for spec in pairs {
+                spec.resolve(umd, &index, &mut frame_pairs);
+            }
 
-                if let Some(idx1) = p1_idx {
-                    if target_all {
-                        for k in start_idx..end_idx {
-                            Self::process_pair(&mut euclidean_data, umd, idx1, k);
-                        }
-                    } else {
-                        for k in start_idx..end_idx {
-                            if &umd.types[k] == &pairs[1] {
-                                Self::process_pair(&mut euclidean_data, umd, idx1, k);
-                            }
-                        }
-                    }
+            for &(idx1, idx2) in &frame_pairs {
+                match idx1 {
+                    None => Self::process_with_origin(&mut euclidean_data, umd, idx2),
+                    Some(idx1) => Self::process_pair(&mut euclidean_data, umd, idx1, idx2),
                 }
             }
         }
@@ -279,11 +256,11 @@ impl EuclideanCalculator {
 
 
 impl DistanceCalc {
-    fn calculate(x: f64, y: f64, z: f64) -> f64 {
+    pub(crate) fn calculate(x: f64, y: f64, z: f64) -> f64 {
         (x.powi(2) + y.powi(2) + z.powi(2)).sqrt()
     }
 
-    fn calculate_uncertainty(r: f64, x: f64, y: f64, z: f64, sx: f64, sy: f64, sz: f64) -> f64 {
+    pub(crate) fn calculate_uncertainty(r: f64, x: f64, y: f64, z: f64, sx: f64, sy: f64, sz: f64) -> f64 {
         if r == 0.0 { 
             return 0.0;
         } 
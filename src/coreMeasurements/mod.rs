@@ -20,4 +20,8 @@ MOSAIC. If not, see <https://www.gnu.org/licenses/>.
 pub mod euclidean;
 pub mod curve;
 pub mod area;
-pub mod angle;
\ No newline at end of file
+pub mod angle;
+pub mod named;
+pub mod pairs;
+pub mod contour;
+pub mod volume;
\ No newline at end of file
@@ -0,0 +1,302 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Named measures
+
+    A catalogue of the measures clinicians actually ask for (lip aperture, lip width, protrusion, etc.)
+    built on top of the euclidean/angle logic. Each measure is defined by the driver (see
+    `drivers::OpenFace::defaultCommands::measures`) so the same measure name means the same thing
+    regardless of which landmarks the driver happens to use.
+
+    Output is a tidy table: one row per measure per frame.
+*/
+
+use crate::UMD::UMD::{UMD};
+use crate::coreMeasurements::euclidean::euclidean::{DistanceCalc};
+use crate::coreMeasurements::pairs::pairs::{FrameIndex};
+use polars::prelude::*;
+use std::fs::File;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Direction {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl Direction {
+    // (axis index, sign)
+    fn axis(&self) -> (usize, f64) {
+        match self {
+            Self::PositiveX => (0, 1.0),
+            Self::NegativeX => (0, -1.0),
+            Self::PositiveY => (1, 1.0),
+            Self::NegativeY => (1, -1.0),
+            Self::PositiveZ => (2, 1.0),
+            Self::NegativeZ => (2, -1.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MeasureKind {
+    // straight line distance between two landmarks (i.e. lip aperture, lip width)
+    Distance([&'static str; 2]),
+
+    // component of a landmark along the anterior direction relative to the centroid of the reference landmarks
+    // uses the pose corrected coordinates so the anterior direction is the same in every frame
+    Protrusion {
+        landmark: &'static str,
+        reference: &'static [&'static str],
+        anterior: Direction,
+    },
+
+    // signed distance of a landmark from the plane through 3 landmarks (for drivers with proper face planes, i.e. EMA)
+    PlaneDistance {
+        landmark: &'static str,
+        plane: [&'static str; 3],
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NamedMeasure {
+    pub name: &'static str,
+    pub kind: MeasureKind,
+}
+
+impl NamedMeasure {
+    pub fn landmarks(&self) -> Vec<&'static str> {
+        match self.kind {
+            MeasureKind::Distance(pair) => pair.to_vec(),
+            MeasureKind::Protrusion { landmark, reference, .. } => {
+                let mut out = vec![landmark];
+                out.extend_from_slice(reference);
+                out
+            }
+            MeasureKind::PlaneDistance { landmark, plane } => vec![landmark, plane[0], plane[1], plane[2]],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CoreNamedMeasure {
+    pub measure: Vec<String>,
+    pub frame: Vec<u32>,
+    pub timestamp: Vec<f32>,
+    pub types_included: Vec<String>,
+    pub value: Vec<f64>,
+    pub uncertainty: Vec<f64>,
+}
+
+impl CoreNamedMeasure {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            measure: Vec::with_capacity(estimated_entries),
+            frame: Vec::with_capacity(estimated_entries),
+            timestamp: Vec::with_capacity(estimated_entries),
+            types_included: Vec::with_capacity(estimated_entries),
+            value: Vec::with_capacity(estimated_entries),
+            uncertainty: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(
+        &mut self, measure: String, frame: u32, timestamp: f32,
+        types_included: String, value: f64, uncertainty: f64
+    ) {
+        self.measure.push(measure);
+        self.frame.push(frame);
+        self.timestamp.push(timestamp);
+        self.types_included.push(types_included);
+        self.value.push(value);
+        self.uncertainty.push(uncertainty);
+    }
+
+    pub fn save_named_measure_to_parquet(data: &CoreNamedMeasure, file_path: &str) -> PolarsResult<()> {
+        let s_measure = Series::new("measure", &data.measure);
+        let s_frame = Series::new("frame", &data.frame);
+        let s_time = Series::new("timestamp", &data.timestamp);
+        let s_types = Series::new("types_included", &data.types_included);
+        let s_value = Series::new("value", &data.value);
+        let s_uncertainty = Series::new("uncertainty", &data.uncertainty);
+
+        let mut df = DataFrame::new(vec![
+            s_measure, s_frame, s_time, s_types, s_value, s_uncertainty,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported named measure data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct NamedMeasureCalculator;
+
+impl NamedMeasureCalculator {
+    pub fn calculate(umd: &UMD, measures: &[NamedMeasure]) -> CoreNamedMeasure {
+        let total_points = umd.frame.len();
+        if total_points == 0 {
+            return CoreNamedMeasure::construction(0);
+        }
+
+        let mut measure_data = CoreNamedMeasure::construction((total_points / 68 + 1) * measures.len());
+        let identifiers: Vec<String> = measures.iter().map(|m| m.landmarks().join(",")).collect();
+
+        let mut i = 0;
+        while i < total_points {
+            let start_idx = i;
+            let current_frame = umd.frame[i];
+
+            // identify frame block
+            while i < total_points && umd.frame[i] == current_frame {
+                i += 1;
+            }
+            let end_idx = i;
+
+            let index = FrameIndex::new(umd, start_idx, end_idx);
+
+            for (k, measure) in measures.iter().enumerate() {
+                // frames missing any of the landmarks are skipped for that measure
+                let result = match measure.kind {
+                    MeasureKind::Distance(pair) => Self::distance(umd, &index, pair),
+                    MeasureKind::Protrusion { landmark, reference, anterior } =>
+                        Self::protrusion(umd, &index, landmark, reference, anterior),
+                    MeasureKind::PlaneDistance { landmark, plane } =>
+                        Self::plane_distance(umd, &index, landmark, plane),
+                };
+
+                if let Some((value, uncertainty)) = result {
+                    measure_data.add_point(
+                        measure.name.to_string(),
+                        current_frame,
+                        umd.timestamp[start_idx],
+                        identifiers[k].clone(),
+                        value,
+                        uncertainty,
+                    );
+                }
+            }
+        }
+
+        measure_data
+    }
+
+    fn position(umd: &UMD, idx: usize) -> [f64; 3] {
+        [umd.x_rotated[idx], umd.y_rotated[idx], umd.z_rotated[idx]]
+    }
+
+    fn sigma(umd: &UMD, idx: usize) -> [f64; 3] {
+        [
+            if idx < umd.x_rotated_uncertainty.len() { umd.x_rotated_uncertainty[idx] } else { 0.0 },
+            if idx < umd.y_rotated_uncertainty.len() { umd.y_rotated_uncertainty[idx] } else { 0.0 },
+            if idx < umd.z_rotated_uncertainty.len() { umd.z_rotated_uncertainty[idx] } else { 0.0 },
+        ]
+    }
+
+    fn distance(umd: &UMD, index: &FrameIndex, pair: [&str; 2]) -> Option<(f64, f64)> {
+        let idx1 = index.get(pair[0])?;
+        let idx2 = index.get(pair[1])?;
+
+        let p1 = Self::position(umd, idx1);
+        let p2 = Self::position(umd, idx2);
+        let s1 = Self::sigma(umd, idx1);
+        let s2 = Self::sigma(umd, idx2);
+
+        let v = [p2[0] - p1[0], p2[1] - p1[1], p2[2] - p1[2]];
+        let dv = [
+            (s1[0].powi(2) + s2[0].powi(2)).sqrt(),
+            (s1[1].powi(2) + s2[1].powi(2)).sqrt(),
+            (s1[2].powi(2) + s2[2].powi(2)).sqrt(),
+        ];
+
+        let r = DistanceCalc::calculate(v[0], v[1], v[2]);
+        let r_unc = DistanceCalc::calculate_uncertainty(r, v[0], v[1], v[2], dv[0], dv[1], dv[2]);
+        Some((r, r_unc))
+    }
+
+    fn protrusion(
+        umd: &UMD, index: &FrameIndex,
+        landmark: &str, reference: &[&str], anterior: Direction
+    ) -> Option<(f64, f64)> {
+        if reference.is_empty() { return None; }
+
+        let (axis, sign) = anterior.axis();
+        let idx = index.get(landmark)?;
+
+        let n = reference.len() as f64;
+        let mut centroid = 0.0;
+        let mut centroid_var = 0.0;
+        for &label in reference {
+            let r_idx = index.get(label)?;
+            centroid += Self::position(umd, r_idx)[axis] / n;
+            centroid_var += (Self::sigma(umd, r_idx)[axis] / n).powi(2);
+        }
+
+        let value = sign * (Self::position(umd, idx)[axis] - centroid);
+        let uncertainty = (Self::sigma(umd, idx)[axis].powi(2) + centroid_var).sqrt();
+        Some((value, uncertainty))
+    }
+
+    fn plane_distance(
+        umd: &UMD, index: &FrameIndex,
+        landmark: &str, plane: [&str; 3]
+    ) -> Option<(f64, f64)> {
+        let idx = index.get(landmark)?;
+        let a_idx = index.get(plane[0])?;
+        let b_idx = index.get(plane[1])?;
+        let c_idx = index.get(plane[2])?;
+
+        let p = Self::position(umd, idx);
+        let a = Self::position(umd, a_idx);
+        let b = Self::position(umd, b_idx);
+        let c = Self::position(umd, c_idx);
+
+        let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+        let normal = [
+            ab[1] * ac[2] - ab[2] * ac[1],
+            ab[2] * ac[0] - ab[0] * ac[2],
+            ab[0] * ac[1] - ab[1] * ac[0],
+        ];
+        let norm = DistanceCalc::calculate(normal[0], normal[1], normal[2]);
+        if norm < 1e-12 { return None; } // plane landmarks are collinear
+        let n = [normal[0] / norm, normal[1] / norm, normal[2] / norm];
+
+        // distance from the plane through the centroid of the 3 plane landmarks
+        let centroid = [
+            (a[0] + b[0] + c[0]) / 3.0,
+            (a[1] + b[1] + c[1]) / 3.0,
+            (a[2] + b[2] + c[2]) / 3.0,
+        ];
+        let value = n[0] * (p[0] - centroid[0]) + n[1] * (p[1] - centroid[1]) + n[2] * (p[2] - centroid[2]);
+
+        // first order: the plane orientation is treated as fixed, so the landmark contributes n and each
+        // plane landmark contributes -n/3 (it only shifts the plane along its normal)
+        let mut variance = 0.0;
+        let s = Self::sigma(umd, idx);
+        for axis in 0..3 { variance += (n[axis] * s[axis]).powi(2); }
+        for plane_idx in [a_idx, b_idx, c_idx] {
+            let s = Self::sigma(umd, plane_idx);
+            for axis in 0..3 { variance += (n[axis] * s[axis] / 3.0).powi(2); }
+        }
+
+        Some((value, variance.sqrt()))
+    }
+}
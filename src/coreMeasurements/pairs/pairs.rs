@@ -0,0 +1,296 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Pair specifications
+
+    Small DSL for telling the euclidean and angle calculators which landmark pairs to compute.
+
+        "origin -> *"             origin to every landmark
+        "OuterPhiltrum -> *"      one landmark to every other landmark
+        "Outer* -> Inner*"        every landmark in one group to every landmark in another group
+        "all(Inner*)"             every unordered pair within a group
+        "sym(Outer*)"             symmetric left/right pairs (OuterLeftCommissure -> OuterRightCommissure, etc.)
+
+    `*` can be used anywhere in a label as a wildcard. Labels without a `*` are exact matches.
+*/
+
+use crate::UMD::UMD::{UMD};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelPattern {
+    Origin,
+    Any,
+    Exact(String),
+    Glob(String),
+}
+
+impl LabelPattern {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err("Empty landmark pattern".to_string());
+        }
+        if pattern.to_lowercase() == "origin" { return Ok(Self::Origin); }
+        if pattern == "*" { return Ok(Self::Any); }
+        if pattern.contains('*') { return Ok(Self::Glob(pattern.to_string())); }
+        Ok(Self::Exact(pattern.to_string()))
+    }
+
+    pub fn matches(&self, label: &str) -> bool {
+        match self {
+            Self::Origin => false,
+            Self::Any => true,
+            Self::Exact(target) => target == label,
+            Self::Glob(pattern) => glob_match(pattern, label),
+        }
+    }
+
+    // a pattern that matches none of the landmarks is most likely a typo
+    fn check(&self, landmarks: &[&str]) -> Result<(), String> {
+        match self {
+            Self::Origin | Self::Any => Ok(()),
+            _ if landmarks.iter().any(|label| self.matches(label)) => Ok(()),
+            Self::Exact(label) | Self::Glob(label) => Err(format!("'{}' does not match any landmark", label)),
+        }
+    }
+
+    // every index in the frame block with a label matching the pattern
+    fn indices(&self, umd: &UMD, index: &FrameIndex) -> Vec<usize> {
+        match self {
+            Self::Origin => Vec::new(),
+            Self::Exact(target) => index.get(target).into_iter().collect(),
+            _ => (index.start..index.end).filter(|&k| self.matches(&umd.types[k])).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PairSpec {
+    Directed(LabelPattern, LabelPattern),
+    Within(LabelPattern),
+    Symmetric(LabelPattern),
+}
+
+impl PairSpec {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+
+        if let Some(inner) = strip_call(spec, "all") {
+            let pattern = LabelPattern::parse(inner)?;
+            if pattern == LabelPattern::Origin {
+                return Err(format!("'{}': origin can not be used inside all()", spec));
+            }
+            return Ok(Self::Within(pattern));
+        }
+
+        if let Some(inner) = strip_call(spec, "sym") {
+            let pattern = LabelPattern::parse(inner)?;
+            if pattern == LabelPattern::Origin {
+                return Err(format!("'{}': origin can not be used inside sym()", spec));
+            }
+            return Ok(Self::Symmetric(pattern));
+        }
+
+        let sides: Vec<&str> = spec.split("->").collect();
+        if sides.len() != 2 {
+            return Err(format!("'{}' is not a valid pair specification (expected 'A -> B', 'all(A)' or 'sym(A)')", spec));
+        }
+
+        let first = LabelPattern::parse(sides[0])?;
+        let second = LabelPattern::parse(sides[1])?;
+        if second == LabelPattern::Origin {
+            return Err(format!("'{}': origin can only be used as the first point", spec));
+        }
+        Ok(Self::Directed(first, second))
+    }
+
+    pub fn parse_all(specs: &[&str]) -> Result<Vec<Self>, String> {
+        specs.iter().map(|s| Self::parse(s)).collect()
+    }
+
+    // every pattern of every spec has to match at least one of the landmarks (i.e. the driver's labels)
+    pub fn check_landmarks(specs: &[Self], landmarks: &[&str]) -> Result<(), String> {
+        for spec in specs {
+            match spec {
+                Self::Directed(first, second) => { first.check(landmarks)?; second.check(landmarks)?; }
+                Self::Within(pattern) | Self::Symmetric(pattern) => pattern.check(landmarks)?,
+            }
+        }
+        Ok(())
+    }
+
+    // old style ["origin", "*"] pairs
+    pub fn from_pair(pair: &[String; 2]) -> Result<Self, String> {
+        Self::parse(&format!("{} -> {}", pair[0], pair[1]))
+    }
+
+    // resolves the spec against a single frame block. The first index is None when it is the origin
+    pub fn resolve(&self, umd: &UMD, index: &FrameIndex, out: &mut Vec<(Option<usize>, usize)>) {
+        match self {
+            Self::Directed(first, second) => {
+                let targets = second.indices(umd, index);
+                if *first == LabelPattern::Origin {
+                    out.extend(targets.iter().map(|&k| (None, k)));
+                    return;
+                }
+                for idx1 in first.indices(umd, index) {
+                    for &idx2 in &targets {
+                        if idx1 != idx2 { out.push((Some(idx1), idx2)); }
+                    }
+                }
+            }
+            Self::Within(pattern) => {
+                let members = pattern.indices(umd, index);
+                for a in 0..members.len() {
+                    for b in (a + 1)..members.len() {
+                        out.push((Some(members[a]), members[b]));
+                    }
+                }
+            }
+            Self::Symmetric(pattern) => {
+                for idx1 in pattern.indices(umd, index) {
+                    let label = &umd.types[idx1];
+                    if !label.contains("Left") { continue; }
+                    let mirrored = label.replacen("Left", "Right", 1);
+                    if !pattern.matches(&mirrored) { continue; }
+                    if let Some(idx2) = index.get(&mirrored) {
+                        out.push((Some(idx1), idx2));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// label -> index lookup for a single frame block so we don't have to rescan the block for every pair
+pub struct FrameIndex<'a> {
+    pub start: usize,
+    pub end: usize,
+    labels: HashMap<&'a str, usize>,
+}
+
+impl<'a> FrameIndex<'a> {
+    pub fn new(umd: &'a UMD, start: usize, end: usize) -> Self {
+        let mut labels = HashMap::with_capacity(end - start);
+        for k in start..end {
+            // first occurence wins (some drivers repeat labels, i.e. OpenFace jaw points)
+            labels.entry(umd.types[k].as_str()).or_insert(k);
+        }
+        Self { start, end, labels }
+    }
+
+    pub fn get(&self, label: &str) -> Option<usize> {
+        self.labels.get(label).copied()
+    }
+}
+
+fn strip_call<'s>(spec: &'s str, name: &str) -> Option<&'s str> {
+    let rest = spec.strip_prefix(name)?.trim_start();
+    let rest = rest.strip_prefix('(')?;
+    rest.strip_suffix(')')
+}
+
+// `*` matches any (possibly empty) run of characters
+fn glob_match(pattern: &str, label: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let mut remaining = label;
+
+    for (k, part) in parts.iter().enumerate() {
+        if part.is_empty() { continue; }
+
+        if k == 0 {
+            match remaining.strip_prefix(part) {
+                Some(rest) => remaining = rest,
+                None => return false,
+            }
+        } else if k == parts.len() - 1 {
+            return remaining.ends_with(part);
+        } else {
+            match remaining.find(part) {
+                Some(pos) => remaining = &remaining[pos + part.len()..],
+                None => return false,
+            }
+        }
+    }
+
+    // pattern ended with a `*` (or was only `*`s) so whatever is left is matched
+    pattern.ends_with('*') || remaining.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LANDMARKS: &[&str] = &[
+        "OuterLeftCommissure", "OuterRightCommissure", "OuterPhiltrum", "InnerPhiltrum", "InnerLowerVermillionBorder",
+    ];
+
+    fn exact(label: &str) -> LabelPattern {
+        LabelPattern::Exact(label.to_string())
+    }
+
+    fn glob(pattern: &str) -> LabelPattern {
+        LabelPattern::Glob(pattern.to_string())
+    }
+
+    #[test]
+    fn directed_pairs_parse() {
+        assert_eq!(PairSpec::parse("origin -> *").unwrap(), PairSpec::Directed(LabelPattern::Origin, LabelPattern::Any));
+        assert_eq!(PairSpec::parse(" InnerPhiltrum->InnerLowerVermillionBorder ").unwrap(),
+            PairSpec::Directed(exact("InnerPhiltrum"), exact("InnerLowerVermillionBorder")));
+        assert_eq!(PairSpec::parse("Outer* -> Inner*").unwrap(), PairSpec::Directed(glob("Outer*"), glob("Inner*")));
+        assert_eq!(PairSpec::from_pair(&["origin".to_string(), "*".to_string()]).unwrap(), PairSpec::parse("origin -> *").unwrap());
+
+        assert!(PairSpec::parse("OuterPhiltrum -> origin").is_err());
+        assert!(PairSpec::parse("OuterPhiltrum").is_err());
+        assert!(PairSpec::parse("A -> B -> C").is_err());
+        assert!(PairSpec::parse(" -> B").is_err());
+    }
+
+    #[test]
+    fn globs_match_anywhere_in_the_label() {
+        assert!(glob("Outer*").matches("OuterPhiltrum"));
+        assert!(glob("*Commissure").matches("OuterLeftCommissure"));
+        assert!(glob("*Left*").matches("OuterLeftCommissure"));
+        assert!(glob("Outer*Commissure").matches("OuterRightCommissure"));
+        assert!(!glob("Outer*").matches("InnerPhiltrum"));
+        assert!(!glob("*Commissure").matches("OuterCommissureLeft"));
+        assert!(!exact("Outer").matches("OuterPhiltrum"));
+    }
+
+    #[test]
+    fn group_calls_parse() {
+        assert_eq!(PairSpec::parse("all(Inner*)").unwrap(), PairSpec::Within(glob("Inner*")));
+        assert_eq!(PairSpec::parse("all (*)").unwrap(), PairSpec::Within(LabelPattern::Any));
+        assert_eq!(PairSpec::parse("sym(Outer*)").unwrap(), PairSpec::Symmetric(glob("Outer*")));
+        assert!(PairSpec::parse("all(origin)").is_err());
+        assert!(PairSpec::parse("sym(origin)").is_err());
+        assert!(PairSpec::parse("sym()").is_err());
+    }
+
+    #[test]
+    fn unknown_landmarks_are_rejected() {
+        let specs = PairSpec::parse_all(&["origin -> *", "Outer* -> InnerPhiltrum", "sym(Outer*)"]).unwrap();
+        assert!(PairSpec::check_landmarks(&specs, LANDMARKS).is_ok());
+
+        for spec in ["OuterPhiltrum -> InnerPhiltum", "Lip* -> *", "all(Jaw*)", "sym(OuterLeftComissure)"] {
+            let specs = PairSpec::parse_all(&[spec]).unwrap();
+            let error = PairSpec::check_landmarks(&specs, LANDMARKS).unwrap_err();
+            assert!(error.contains("does not match any landmark"), "{}: {}", spec, error);
+        }
+    }
+}
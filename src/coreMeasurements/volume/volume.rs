@@ -0,0 +1,278 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    3D volume and surface measures
+
+    Only available for 3D drivers (Metadata::dimension == "3D" with real z values). For each closed lip contour
+    (ordered landmark loop) and the commissure/philtrum basis plane (same landmarks as the area basis):
+
+        - surface_area: area of a spanning surface of the contour. The surface is a fan of triangles from an apex
+          which is moved to minimise the total area (a planar contour gives the planar area)
+        - projected_area: area of the contour projected onto the basis plane
+        - max_protrusion / mean_protrusion: signed distance of the contour points from the basis plane
+        - volume: volume between the spanning surface and the basis plane
+
+    Distances from the plane are positive towards the front of the face for drivers where z points away from the
+    camera (i.e. OpenFace).
+*/
+
+use crate::UMD::UMD::{UMD};
+use crate::UMD::metadata::{Metadata};
+use crate::errors::{MosaicError, MeasurementError};
+use crate::coreMeasurements::pairs::pairs::{FrameIndex};
+use nalgebra::{Vector3};
+use polars::prelude::*;
+use std::fs::File;
+
+const APEX_ITERATIONS: usize = 100;
+
+// measures of one contour loop in one frame
+#[derive(Debug, Clone, Copy)]
+pub struct VolumeMeasures {
+    pub surface_area: f64,
+    pub projected_area: f64,
+    pub max_protrusion: f64,
+    pub mean_protrusion: f64,
+    pub volume: f64,
+}
+
+impl VolumeMeasures {
+    fn undefined() -> Self {
+        let nan = f64::NAN;
+        Self { surface_area: nan, projected_area: nan, max_protrusion: nan, mean_protrusion: nan, volume: nan }
+    }
+}
+
+pub struct CoreVolume {
+    pub frame: Vec<u32>,
+    pub timestamp: Vec<f32>,
+    pub types_included: Vec<String>,
+    pub status: Vec<String>, // "ok" or why the frame was skipped
+    pub surface_area: Vec<f64>,
+    pub projected_area: Vec<f64>,
+    pub max_protrusion: Vec<f64>,
+    pub mean_protrusion: Vec<f64>,
+    pub volume: Vec<f64>,
+}
+
+impl CoreVolume {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            frame: Vec::with_capacity(estimated_entries),
+            timestamp: Vec::with_capacity(estimated_entries),
+            types_included: Vec::with_capacity(estimated_entries),
+            status: Vec::with_capacity(estimated_entries),
+            surface_area: Vec::with_capacity(estimated_entries),
+            projected_area: Vec::with_capacity(estimated_entries),
+            max_protrusion: Vec::with_capacity(estimated_entries),
+            mean_protrusion: Vec::with_capacity(estimated_entries),
+            volume: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(
+        &mut self, frame: u32, timestamp: f32, types_included: String, status: String,
+        measures: VolumeMeasures
+    ) {
+        self.frame.push(frame);
+        self.timestamp.push(timestamp);
+        self.types_included.push(types_included);
+        self.status.push(status);
+        self.surface_area.push(measures.surface_area);
+        self.projected_area.push(measures.projected_area);
+        self.max_protrusion.push(measures.max_protrusion);
+        self.mean_protrusion.push(measures.mean_protrusion);
+        self.volume.push(measures.volume);
+    }
+
+    pub fn save_volume_to_parquet(data: &CoreVolume, file_path: &str) -> PolarsResult<()> {
+        let s_frame = Series::new("frame", &data.frame);
+        let s_time = Series::new("timestamp", &data.timestamp);
+        let s_types = Series::new("types_included", &data.types_included);
+        let s_status = Series::new("status", &data.status);
+        let s_surface = Series::new("surface_area", &data.surface_area);
+        let s_projected = Series::new("projected_area", &data.projected_area);
+        let s_max_protrusion = Series::new("max_protrusion", &data.max_protrusion);
+        let s_mean_protrusion = Series::new("mean_protrusion", &data.mean_protrusion);
+        let s_volume = Series::new("volume", &data.volume);
+
+        let mut df = DataFrame::new(vec![
+            s_frame, s_time, s_types, s_status,
+            s_surface, s_projected, s_max_protrusion, s_mean_protrusion, s_volume,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported volume data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct VolumeCalculator;
+
+impl VolumeCalculator {
+    pub fn calculate_volume(
+        umd: &UMD,
+        metadata: &Metadata,
+        contour_loops: &[&[&str]],
+        basis_sets: &[&[&str; 4]]
+    ) -> Result<CoreVolume, MosaicError> {
+        if metadata.dimension != "3D" {
+            return Err(MeasurementError::RequiresThreeDimensions.into());
+        }
+        if umd.z_rotated.iter().all(|z| z.abs() < 1e-12) {
+            return Err(MeasurementError::MissingDepth.into());
+        }
+
+        let total_entries = umd.frame.len();
+        let mut volume_data = CoreVolume::construction((total_entries / 68 + 1) * contour_loops.len());
+
+        let mut i = 0;
+        while i < total_entries {
+            let current_frame = umd.frame[i];
+            let start_idx = i;
+            while i < total_entries && umd.frame[i] == current_frame { i += 1; }
+            let end_idx = i;
+
+            let index = FrameIndex::new(umd, start_idx, end_idx);
+            let timestamp = umd.timestamp[start_idx];
+
+            for (k, contour_loop) in contour_loops.iter().enumerate() {
+                let types_included = contour_loop.join(",");
+                match Self::measure(umd, &index, contour_loop, basis_sets[k]) {
+                    Ok(measures) => volume_data.add_point(
+                        current_frame, timestamp, types_included, "ok".to_string(), measures,
+                    ),
+                    Err(reason) => volume_data.add_point(
+                        current_frame, timestamp, types_included, reason, VolumeMeasures::undefined(),
+                    ),
+                }
+            }
+        }
+        Ok(volume_data)
+    }
+
+    fn position(umd: &UMD, index: &FrameIndex, label: &str) -> Result<Vector3<f64>, String> {
+        let idx = index.get(label).ok_or_else(|| format!("missing landmark: {}", label))?;
+        Ok(Vector3::new(umd.x_rotated[idx], umd.y_rotated[idx], umd.z_rotated[idx]))
+    }
+
+    fn measure(
+        umd: &UMD, index: &FrameIndex, contour_loop: &[&str], basis_names: &[&str; 4]
+    ) -> Result<VolumeMeasures, String> {
+        let points = contour_loop.iter()
+            .map(|label| Self::position(umd, index, label))
+            .collect::<Result<Vec<Vector3<f64>>, String>>()?;
+        if points.len() < 3 { return Err("too few points".to_string()); }
+
+        // basis plane: commissure line and the philtrum -> lower lip line, origin between the commissures
+        let p_lc = Self::position(umd, index, basis_names[0])?;
+        let p_rc = Self::position(umd, index, basis_names[1])?;
+        let p_lm = Self::position(umd, index, basis_names[2])?;
+        let p_ph = Self::position(umd, index, basis_names[3])?;
+
+        let v1 = p_lc - p_rc;
+        let v2 = p_lm - p_ph;
+        let normal = v2.cross(&v1);
+        if v1.norm() < 1e-12 || normal.norm() < 1e-12 { return Err("Singular basis".to_string()); }
+
+        let n = normal.normalize();
+        let e1 = v1.normalize();
+        let e2 = n.cross(&e1);
+        let origin = (p_lc + p_rc) / 2.0;
+
+        let height = |p: &Vector3<f64>| n.dot(&(p - origin));
+        let plane = |p: &Vector3<f64>| ((p - origin).dot(&e1), (p - origin).dot(&e2));
+
+        let heights: Vec<f64> = points.iter().map(height).collect();
+        let max_protrusion = heights.iter().cloned().fold(f64::MIN, f64::max);
+        let mean_protrusion = heights.iter().sum::<f64>() / heights.len() as f64;
+
+        let apex = Self::minimal_apex(&points);
+        let surface_area = Self::fan_area(&points, apex);
+
+        // each fan triangle contributes its signed projected area * mean height (prism above the plane)
+        let count = points.len();
+        let (uc, vc) = plane(&apex);
+        let hc = height(&apex);
+        let mut projected = 0.0;
+        let mut volume = 0.0;
+        for k in 0..count {
+            let next = (k + 1) % count;
+            let (u_k, v_k) = plane(&points[k]);
+            let (u_next, v_next) = plane(&points[next]);
+            let triangle = 0.5 * ((u_k - uc) * (v_next - vc) - (u_next - uc) * (v_k - vc));
+            projected += triangle;
+            volume += triangle * (hc + heights[k] + heights[next]) / 3.0;
+        }
+
+        // orientation of the loop should not change the sign of the volume
+        let orientation = projected.signum();
+        Ok(VolumeMeasures {
+            surface_area,
+            projected_area: projected.abs(),
+            max_protrusion,
+            mean_protrusion,
+            volume: orientation * volume,
+        })
+    }
+
+    fn fan_area(points: &[Vector3<f64>], apex: Vector3<f64>) -> f64 {
+        let count = points.len();
+        (0..count).map(|k| {
+            let a = points[k] - apex;
+            let b = points[(k + 1) % count] - apex;
+            0.5 * a.cross(&b).norm()
+        }).sum()
+    }
+
+    // gradient descent on the fan area starting at the centroid
+    // d(area)/d(apex) = 1/2 sum (p_k - p_k+1) x m_k where m_k is the unit normal of triangle k
+    fn minimal_apex(points: &[Vector3<f64>]) -> Vector3<f64> {
+        let count = points.len();
+        let mut apex = points.iter().fold(Vector3::zeros(), |acc, p| acc + p) / count as f64;
+        let mut area = Self::fan_area(points, apex);
+
+        let scale = points.iter().map(|p| (p - apex).norm()).fold(0.0, f64::max);
+        let mut step = 0.1 * scale;
+
+        for _ in 0..APEX_ITERATIONS {
+            let mut gradient = Vector3::zeros();
+            for k in 0..count {
+                let next = (k + 1) % count;
+                let cross = (points[k] - apex).cross(&(points[next] - apex));
+                let norm = cross.norm();
+                if norm < 1e-12 { continue; }
+                gradient += 0.5 * (points[k] - points[next]).cross(&(cross / norm));
+            }
+
+            let gradient_norm = gradient.norm();
+            if gradient_norm < 1e-12 || step < 1e-9 * scale { break; }
+
+            // backtracking: only accept steps that reduce the area
+            let candidate = apex - gradient / gradient_norm * step;
+            let candidate_area = Self::fan_area(points, candidate);
+            if candidate_area < area {
+                apex = candidate;
+                area = candidate_area;
+                step *= 1.2;
+            } else {
+                step *= 0.5;
+            }
+        }
+        apex
+    }
+}
@@ -0,0 +1,55 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    angles.rs
+    Default joint angle settings for the OpenFace driver.
+*/
+
+use crate::coreMeasurements::angle::angle::{JointAngleSpec};
+
+// commissure - philtrum - commissure (how "peaked" the upper lip is)
+pub const upper_lip_angle: JointAngleSpec = JointAngleSpec::Vertex([
+    "OuterRightCommissure", "OuterPhiltrum", "OuterLeftCommissure",
+]);
+
+// commissure - lower vermillion border - commissure
+pub const lower_lip_angle: JointAngleSpec = JointAngleSpec::Vertex([
+    "OuterRightCommissure", "OuterLowerVermillionBorder", "OuterLeftCommissure",
+]);
+
+// lip corner opening angle at each commissure
+pub const right_lip_corner_angle: JointAngleSpec = JointAngleSpec::Vertex([
+    "OuterRightUpperLip_com", "OuterRightCommissure", "OuterRightLowerLip_com",
+]);
+pub const left_lip_corner_angle: JointAngleSpec = JointAngleSpec::Vertex([
+    "OuterLeftUpperLip_com", "OuterLeftCommissure", "OuterLeftLowerLip_com",
+]);
+
+// angle between the outer and inner commissure lines (lip corner asymmetry/tilt)
+pub const commissure_line_angle: JointAngleSpec = JointAngleSpec::Segments(
+    ["OuterRightCommissure", "OuterLeftCommissure"],
+    ["InnerRightCommissure", "InnerLeftCommissure"],
+);
+
+// Note: jaw opening angles need the jaw and nose points which the OpenFace driver does not currently read (only points 48-67)
+
+pub const default_joint_angles: &[JointAngleSpec] = &[
+    upper_lip_angle,
+    lower_lip_angle,
+    right_lip_corner_angle,
+    left_lip_corner_angle,
+    commissure_line_angle,
+];
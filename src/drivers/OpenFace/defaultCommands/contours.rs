@@ -0,0 +1,47 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    contours.rs
+    Default closed contours for the OpenFace driver. Points are in loop order (clockwise, same as the landmark map).
+*/
+
+// all 12 outer lip points (48 - 59)
+pub const outer_lip_contour: &[&str] = &[
+    "OuterRightCommissure",
+    "OuterRightUpperLip_com",
+    "OuterRightUpperLip_phil",
+    "OuterPhiltrum",
+    "OuterLeftUpperLip_phil",
+    "OuterLeftUpperLip_com",
+    "OuterLeftCommissure",
+    "OuterLeftLowerLip_com",
+    "OuterLeftLowerLip_phil",
+    "OuterLowerVermillionBorder",
+    "OuterRightLowerLip_phil",
+    "OuterRightLowerLip_com",
+];
+
+// all 8 inner lip points (60 - 67)
+pub const inner_lip_contour: &[&str] = &[
+    "InnerRightCommissure",
+    "InnerRightUpperLip",
+    "InnerPhiltrum",
+    "InnerLeftUpperLip",
+    "InnerLeftCommissure",
+    "InnerLeftLowerLip",
+    "InnerLowerVermillionBorder",
+    "InnerRightLowerLip",
+];
@@ -0,0 +1,71 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    measures.rs
+    Default named measures for the OpenFace driver.
+*/
+
+use crate::coreMeasurements::named::named::{NamedMeasure, MeasureKind, Direction};
+
+// aperture (upper/lower vermillion distance)
+pub const lip_aperture: NamedMeasure = NamedMeasure {
+    name: "lip_aperture",
+    kind: MeasureKind::Distance(["OuterPhiltrum", "OuterLowerVermillionBorder"]),
+};
+pub const inner_lip_aperture: NamedMeasure = NamedMeasure {
+    name: "inner_lip_aperture",
+    kind: MeasureKind::Distance(["InnerPhiltrum", "InnerLowerVermillionBorder"]),
+};
+
+// width (commissure distance)
+pub const lip_width: NamedMeasure = NamedMeasure {
+    name: "lip_width",
+    kind: MeasureKind::Distance(["OuterLeftCommissure", "OuterRightCommissure"]),
+};
+pub const inner_lip_width: NamedMeasure = NamedMeasure {
+    name: "inner_lip_width",
+    kind: MeasureKind::Distance(["InnerLeftCommissure", "InnerRightCommissure"]),
+};
+
+// protrusion
+// OpenFace only gives us lip points so the commissures act as the face plane. Z (depth) gets larger the
+// further away from the camera you are so anterior is -Z.
+// Warning: with 2D OpenFace data Z is 0 so protrusion will always be 0
+pub const upper_lip_protrusion: NamedMeasure = NamedMeasure {
+    name: "upper_lip_protrusion",
+    kind: MeasureKind::Protrusion {
+        landmark: "OuterPhiltrum",
+        reference: &["OuterLeftCommissure", "OuterRightCommissure"],
+        anterior: Direction::NegativeZ,
+    },
+};
+pub const lower_lip_protrusion: NamedMeasure = NamedMeasure {
+    name: "lower_lip_protrusion",
+    kind: MeasureKind::Protrusion {
+        landmark: "OuterLowerVermillionBorder",
+        reference: &["OuterLeftCommissure", "OuterRightCommissure"],
+        anterior: Direction::NegativeZ,
+    },
+};
+
+pub const default_measures: &[NamedMeasure] = &[
+    lip_aperture,
+    inner_lip_aperture,
+    lip_width,
+    inner_lip_width,
+    upper_lip_protrusion,
+    lower_lip_protrusion,
+];
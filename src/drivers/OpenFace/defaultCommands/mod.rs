@@ -14,4 +14,8 @@ MOSAIC. If not, see <https://www.gnu.org/licenses/>.
 */
 
 pub mod curves;
-pub mod areas;
\ No newline at end of file
+pub mod areas;
+pub mod measures;
+pub mod pairs;
+pub mod angles;
+pub mod contours;
\ No newline at end of file
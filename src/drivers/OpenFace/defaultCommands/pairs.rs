@@ -0,0 +1,36 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    pairs.rs
+    Default pair specifications for the euclidean and angle commands (see coreMeasurements/pairs for the syntax).
+*/
+
+pub const origin_to_all: &[&str] = &["origin -> *"];
+
+// outer lip contour to the inner lip contour
+pub const outer_to_inner: &[&str] = &["Outer* -> Inner*"];
+
+// every pair of points around each lip contour
+pub const outer_lip_pairs: &[&str] = &["all(Outer*)"];
+pub const inner_lip_pairs: &[&str] = &["all(Inner*)"];
+
+// left/right mirrored points (i.e. OuterLeftCommissure -> OuterRightCommissure)
+pub const lip_symmetry: &[&str] = &["sym(Outer*)", "sym(Inner*)"];
+
+// inner lip aperture (lip closure detection)
+pub const inner_aperture: &[&str] = &["InnerPhiltrum -> InnerLowerVermillionBorder"];
+
+pub const default_pairs: &[&str] = &["origin -> *"];
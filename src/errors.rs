@@ -26,6 +26,9 @@ pub enum MosaicError {
     Anchor(AnchorError),
     Io(std::io::Error),
     File(FileError),
+    Measurement(MeasurementError),
+    Audio(AudioError),
+    Annotation(AnnotationError),
 }
 
 // PORJECT LEVEL ERRORS
@@ -91,6 +94,56 @@ pub enum AnchorError{
     testError,
 }
 
+// MEASUREMENT ERRORS
+#[derive(Debug)]
+pub enum MeasurementError{
+    RequiresThreeDimensions, // measurement only makes sense for 3D drivers (Metadata::dimension)
+    MissingDepth, // UMD says 3D but every z value is 0
+}
+
+// AUDIO ERRORS
+#[derive(Debug)]
+pub enum AudioError{
+    MalformedWav(String), // RIFF/WAVE structure is broken (missing chunk, truncated, etc)
+    UnsupportedFormat(String), // valid WAV but an encoding we can't read
+    EmptyAudio, // no samples
+    Python(String), // parselmouth bridge failed
+    MalformedContainer(String), // MP4/MOV box structure is broken
+    NoAudioTrack, // video has no sound track
+    UnsupportedCodec(String), // codec we can't decode natively (i.e. AAC) and ffmpeg isn't available
+    DecoderUnavailable(String), // ffmpeg/ffprobe missing or failed
+}
+
+// ANNOTATION ERRORS
+#[derive(Debug)]
+pub enum AnnotationError{
+    MalformedTextGrid(String), // value of the wrong type where the TextGrid structure expects another
+    UnexpectedEndOfFile, // file ends before every tier / interval has been read
+    UnknownTierClass(String), // tier class that isn't IntervalTier or TextTier
+    MissingTier(String), // no tier with that name
+    WrongTierClass(String), // point tier where an interval tier is needed
+    MalformedPartitur(String), // BAS Partitur line with missing or non numeric fields
+    MissingSampleRate, // BAS Partitur timed tier before (or without) the SAM header
+}
+
+impl From<MeasurementError> for MosaicError {
+    fn from(error: MeasurementError) -> Self {
+        MosaicError::Measurement(error)
+    }
+}
+
+impl From<AudioError> for MosaicError {
+    fn from(error: AudioError) -> Self {
+        MosaicError::Audio(error)
+    }
+}
+
+impl From<AnnotationError> for MosaicError {
+    fn from(error: AnnotationError) -> Self {
+        MosaicError::Annotation(error)
+    }
+}
+
 impl From<FileError> for MosaicError {
     fn from(error: FileError) -> Self {
         MosaicError::File(error)
@@ -166,6 +219,60 @@ impl fmt::Display for MosaicError {
             
                 
 
+            // MEASUREMENT ERRORS
+            MosaicError::Measurement(MeasurementError::RequiresThreeDimensions) =>
+                write!(f, "This measurement requires 3D data. The UMD metadata dimension is not '3D'."),
+
+            MosaicError::Measurement(MeasurementError::MissingDepth) =>
+                write!(f, "This measurement requires 3D data but every z value in the UMD is 0."),
+
+            // AUDIO ERRORS
+            MosaicError::Audio(AudioError::MalformedWav(reason)) =>
+                write!(f, "Malformed WAV file: {}.", reason),
+
+            MosaicError::Audio(AudioError::UnsupportedFormat(format)) =>
+                write!(f, "Unsupported audio format: {}.", format),
+
+            MosaicError::Audio(AudioError::EmptyAudio) =>
+                write!(f, "The audio file has no samples."),
+
+            MosaicError::Audio(AudioError::Python(e)) =>
+                write!(f, "Python acoustic analysis failed: {}", e),
+
+            MosaicError::Audio(AudioError::MalformedContainer(reason)) =>
+                write!(f, "Malformed video container: {}.", reason),
+
+            MosaicError::Audio(AudioError::NoAudioTrack) =>
+                write!(f, "The video file has no audio track."),
+
+            MosaicError::Audio(AudioError::UnsupportedCodec(codec)) =>
+                write!(f, "Audio codec '{}' can't be decoded natively and ffmpeg / ffprobe weren't found on the PATH. Install ffmpeg (https://ffmpeg.org/download.html) to extract it.", codec),
+
+            MosaicError::Audio(AudioError::DecoderUnavailable(reason)) =>
+                write!(f, "ffmpeg could not extract the audio: {}", reason),
+
+            // ANNOTATION ERRORS
+            MosaicError::Annotation(AnnotationError::MalformedTextGrid(reason)) =>
+                write!(f, "Malformed TextGrid: {}.", reason),
+
+            MosaicError::Annotation(AnnotationError::UnexpectedEndOfFile) =>
+                write!(f, "The annotation file ended before all of its tiers were read."),
+
+            MosaicError::Annotation(AnnotationError::UnknownTierClass(class)) =>
+                write!(f, "Unknown tier class '{}'. Only IntervalTier and TextTier are supported.", class),
+
+            MosaicError::Annotation(AnnotationError::MissingTier(name)) =>
+                write!(f, "The annotation has no tier named '{}'.", name),
+
+            MosaicError::Annotation(AnnotationError::WrongTierClass(name)) =>
+                write!(f, "Tier '{}' is a point tier but an interval tier is needed.", name),
+
+            MosaicError::Annotation(AnnotationError::MalformedPartitur(line)) =>
+                write!(f, "Malformed BAS Partitur file at {}.", line),
+
+            MosaicError::Annotation(AnnotationError::MissingSampleRate) =>
+                write!(f, "The BAS Partitur file has no SAM (sample rate) header before its timed tiers."),
+
             // LAST CASE ERRORS
             _ => write!(f, "{:?}", self)
             
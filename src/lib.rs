@@ -10,6 +10,7 @@ You should have received a copy of the GNU General Public License along with Foo
 */
 
 pub mod coreMeasurements;
+pub mod complexMeasurements;
 
 pub mod shell;
 pub mod errors;
@@ -20,4 +21,6 @@ pub mod drivers;
 
 pub mod analysis;
 
-pub mod praatAnalysis;
\ No newline at end of file
+pub mod praatAnalysis;
+
+pub mod annotation;
\ No newline at end of file
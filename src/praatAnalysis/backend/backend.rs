@@ -0,0 +1,165 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Speech backends
+
+    Both backends produce the same SpeechUMD:
+
+        - Python: parselmouth through the pyo3 bridge (needs the bundled Python environment)
+        - Native: pure Rust (autocorrelation pitch, Kaiser windowed intensity, Burg formants, spectral moments)
+
+    `compare` checks the native backend against parselmouth frame by frame (used to validate it on fixture audio).
+*/
+
+use crate::errors::{AudioError, MosaicError};
+use crate::praatAnalysis::setup::PythonEnvironment;
+use crate::praatAnalysis::features::features::{FeatureExtractor};
+use crate::praatAnalysis::native::native::{NativeExtractor};
+use crate::praatAnalysis::speechUMD::speechUMD::{SpeechUMD, SpeechParameters};
+use polars::prelude::*;
+use pyo3::Python;
+use std::fs::File;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeechBackend {
+    Python,
+    Native,
+}
+
+// one row per feature
+#[derive(Debug, Clone)]
+pub struct BackendComparison {
+    pub feature: Vec<String>,
+    pub frames: Vec<u32>, // defined in both
+    pub definedness_agreement: Vec<f64>, // proportion of frames where both or neither are defined (voicing agreement for f0)
+    pub mean_difference: Vec<f64>, // candidate - reference
+    pub mean_absolute_difference: Vec<f64>,
+    pub rmse: Vec<f64>,
+    pub correlation: Vec<f64>,
+}
+
+impl BackendComparison {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            feature: Vec::with_capacity(estimated_entries),
+            frames: Vec::with_capacity(estimated_entries),
+            definedness_agreement: Vec::with_capacity(estimated_entries),
+            mean_difference: Vec::with_capacity(estimated_entries),
+            mean_absolute_difference: Vec::with_capacity(estimated_entries),
+            rmse: Vec::with_capacity(estimated_entries),
+            correlation: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn save_backend_comparison_to_parquet(data: &BackendComparison, file_path: &str) -> PolarsResult<()> {
+        let s_feature = Series::new("feature", &data.feature);
+        let s_frames = Series::new("frames", &data.frames);
+        let s_agreement = Series::new("definedness_agreement", &data.definedness_agreement);
+        let s_mean = Series::new("mean_difference", &data.mean_difference);
+        let s_mad = Series::new("mean_absolute_difference", &data.mean_absolute_difference);
+        let s_rmse = Series::new("rmse", &data.rmse);
+        let s_correlation = Series::new("correlation", &data.correlation);
+
+        let mut df = DataFrame::new(vec![
+            s_feature, s_frames, s_agreement, s_mean, s_mad, s_rmse, s_correlation,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported backend comparison data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct SpeechExtractor;
+
+impl SpeechExtractor {
+    pub fn extract(wav_path: &str, backend: SpeechBackend, parameters: &SpeechParameters) -> Result<SpeechUMD, MosaicError> {
+        match backend {
+            SpeechBackend::Native => NativeExtractor::extract(wav_path, parameters),
+            SpeechBackend::Python => {
+                PythonEnvironment::ensure_python_bridge()?;
+                Python::attach(|py| FeatureExtractor::extract(py, wav_path, parameters))
+                    .map_err(|e| AudioError::Python(e.to_string()).into())
+            }
+        }
+    }
+
+    // frames are matched by frame number
+    pub fn compare(reference: &SpeechUMD, candidate: &SpeechUMD) -> BackendComparison {
+        let features: [(&str, &Vec<f64>, &Vec<f64>); 11] = [
+            ("f0", &reference.f0, &candidate.f0),
+            ("intensity", &reference.intensity, &candidate.intensity),
+            ("f1", &reference.f1, &candidate.f1),
+            ("f2", &reference.f2, &candidate.f2),
+            ("f3", &reference.f3, &candidate.f3),
+            ("f4", &reference.f4, &candidate.f4),
+            ("hnr", &reference.hnr, &candidate.hnr),
+            ("spectral_cog", &reference.spectral_cog, &candidate.spectral_cog),
+            ("spectral_sd", &reference.spectral_sd, &candidate.spectral_sd),
+            ("spectral_skewness", &reference.spectral_skewness, &candidate.spectral_skewness),
+            ("spectral_kurtosis", &reference.spectral_kurtosis, &candidate.spectral_kurtosis),
+        ];
+
+        // (reference row, candidate row) with the same frame
+        let mut pairs = Vec::with_capacity(reference.len());
+        let mut j = 0;
+        for i in 0..reference.len() {
+            while j < candidate.len() && candidate.frame[j] < reference.frame[i] { j += 1; }
+            if j < candidate.len() && candidate.frame[j] == reference.frame[i] { pairs.push((i, j)); }
+        }
+
+        let mut comparison = BackendComparison::construction(features.len());
+        for (name, a, b) in features.iter() {
+            let mut agree = 0;
+            let mut both = Vec::with_capacity(pairs.len());
+            for &(i, j) in &pairs {
+                let (x, y) = (a[i], b[j]);
+                if x.is_nan() == y.is_nan() { agree += 1; }
+                if !x.is_nan() && !y.is_nan() { both.push((x, y)); }
+            }
+
+            let n = both.len() as f64;
+            let (mean_difference, mean_absolute_difference, rmse, correlation) = if both.is_empty() {
+                (f64::NAN, f64::NAN, f64::NAN, f64::NAN)
+            } else {
+                let mean_x = both.iter().map(|p| p.0).sum::<f64>() / n;
+                let mean_y = both.iter().map(|p| p.1).sum::<f64>() / n;
+                let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+                for (x, y) in &both {
+                    sxy += (x - mean_x) * (y - mean_y);
+                    sxx += (x - mean_x).powi(2);
+                    syy += (y - mean_y).powi(2);
+                }
+                (
+                    both.iter().map(|(x, y)| y - x).sum::<f64>() / n,
+                    both.iter().map(|(x, y)| (y - x).abs()).sum::<f64>() / n,
+                    (both.iter().map(|(x, y)| (y - x).powi(2)).sum::<f64>() / n).sqrt(),
+                    if sxx > 0.0 && syy > 0.0 { sxy / (sxx * syy).sqrt() } else { f64::NAN },
+                )
+            };
+
+            comparison.feature.push(name.to_string());
+            comparison.frames.push(both.len() as u32);
+            comparison.definedness_agreement.push(if pairs.is_empty() { f64::NAN } else { agree as f64 / pairs.len() as f64 });
+            comparison.mean_difference.push(mean_difference);
+            comparison.mean_absolute_difference.push(mean_absolute_difference);
+            comparison.rmse.push(rmse);
+            comparison.correlation.push(correlation);
+        }
+        comparison
+    }
+}
@@ -0,0 +1,173 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Audio extraction
+
+    Pulls the audio track out of the recorded video (the same file OpenFace ran on) into a mono WAV at a chosen
+    sample rate for the acoustic analysis.
+
+        - uncompressed PCM in MP4/MOV is demuxed and decoded natively (mp4.rs)
+        - anything else (AAC, other containers) goes through ffmpeg/ffprobe, which are needed for it since
+          AAC is never decoded natively (`ensure_ffmpeg`)
+
+    The offset of the audio relative to video frame 0 (from the edit lists, or ffprobe's stream start times) is
+    returned and written to a JSON sidecar next to the WAV (<wav>.json) so the acoustic and kinematic timelines can be
+    lined up. A positive offset means the audio starts after frame 0.
+*/
+
+use crate::errors::{AudioError, MosaicError};
+use crate::praatAnalysis::native::wav::{Audio};
+use crate::praatAnalysis::extraction::mp4::{Movie};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedAudio {
+    pub source: String,
+    pub wav_path: String,
+    pub codec: String,
+    pub method: String, // "native" / "ffmpeg"
+    pub original_sample_rate: Option<f64>, // Hz, None when ffprobe doesn't report it
+    pub sample_rate: f64,
+    pub duration: f64, // s
+    pub offset: f64, // s, audio start relative to video frame 0
+}
+
+pub struct AudioExtractor;
+
+impl AudioExtractor {
+    pub fn extract(video_path: &str, wav_path: &str, sample_rate: f64) -> Result<ExtractedAudio, MosaicError> {
+        if !Path::new(video_path).exists() {
+            return Err(MosaicError::InvalidPath(video_path.to_string()));
+        }
+
+        let extracted = match Self::extract_native(video_path, wav_path, sample_rate) {
+            Ok(extracted) => extracted,
+            // compressed audio or a container we don't parse
+            Err(MosaicError::Audio(AudioError::UnsupportedCodec(codec))) => {
+                // AAC is never decoded natively, so without ffmpeg there's nothing to fall back on
+                Self::ensure_ffmpeg().map_err(|_| AudioError::UnsupportedCodec(codec))?;
+                Self::extract_ffmpeg(video_path, wav_path, sample_rate)?
+            }
+            Err(MosaicError::Audio(AudioError::MalformedContainer(_))) => Self::extract_ffmpeg(video_path, wav_path, sample_rate)?,
+            Err(e) => return Err(e),
+        };
+
+        Self::write_sidecar(&extracted)?;
+        println!("Successfully extracted audio ({}) to: {}", extracted.method, wav_path);
+        Ok(extracted)
+    }
+
+    fn extract_native(video_path: &str, wav_path: &str, sample_rate: f64) -> Result<ExtractedAudio, MosaicError> {
+        let bytes = fs::read(video_path)?;
+        let movie = Movie::parse(&bytes)?;
+        let audio_track = movie.audio_track().ok_or(AudioError::NoAudioTrack)?;
+
+        let audio = Movie::decode_pcm(&bytes, audio_track)?;
+        let video_start = movie.video_track().map(|t| t.start_time(movie.timescale)).unwrap_or(0.0);
+        let offset = audio_track.start_time(movie.timescale) - video_start;
+
+        let resampled = audio.resample(sample_rate);
+        resampled.write_wav(Path::new(wav_path))?;
+
+        Ok(ExtractedAudio {
+            source: video_path.to_string(),
+            wav_path: wav_path.to_string(),
+            codec: audio_track.codec.clone(),
+            method: "native".to_string(),
+            original_sample_rate: Some(audio.sample_rate),
+            sample_rate,
+            duration: resampled.duration(),
+            offset,
+        })
+    }
+
+    fn extract_ffmpeg(video_path: &str, wav_path: &str, sample_rate: f64) -> Result<ExtractedAudio, MosaicError> {
+        let probe = Self::run("ffprobe", &[
+            "-v", "error",
+            "-show_entries", "stream=codec_type,codec_name,sample_rate,start_time",
+            "-of", "json",
+            video_path,
+        ])?;
+        let probe: serde_json::Value = serde_json::from_str(&probe)
+            .map_err(|e| AudioError::DecoderUnavailable(format!("could not read ffprobe output: {}", e)))?;
+
+        let streams = probe["streams"].as_array().cloned().unwrap_or_default();
+        let stream = |kind: &str| streams.iter().find(|s| s["codec_type"] == kind).cloned();
+        let audio_stream = stream("audio").ok_or(AudioError::NoAudioTrack)?;
+        let start_time = |s: &serde_json::Value| s["start_time"].as_str().and_then(|t| t.parse::<f64>().ok()).unwrap_or(0.0);
+        let video_start = stream("video").map(|s| start_time(&s)).unwrap_or(0.0);
+
+        Self::run("ffmpeg", &[
+            "-v", "error", "-y",
+            "-i", video_path,
+            "-map", "0:a:0", "-vn",
+            "-ac", "1",
+            "-ar", &format!("{}", sample_rate.round() as u32),
+            "-acodec", "pcm_s16le",
+            wav_path,
+        ])?;
+
+        let audio = Audio::read_wav(Path::new(wav_path))?;
+        Ok(ExtractedAudio {
+            source: video_path.to_string(),
+            wav_path: wav_path.to_string(),
+            codec: audio_stream["codec_name"].as_str().unwrap_or("unknown").to_string(),
+            method: "ffmpeg".to_string(),
+            original_sample_rate: audio_stream["sample_rate"].as_str().and_then(|r| r.parse().ok()),
+            sample_rate: audio.sample_rate,
+            duration: audio.duration(),
+            offset: start_time(&audio_stream) - video_start,
+        })
+    }
+
+    // ffmpeg and ffprobe are on the PATH (checked before falling back to them for audio that isn't uncompressed PCM)
+    pub fn ensure_ffmpeg() -> Result<(), MosaicError> {
+        for program in ["ffmpeg", "ffprobe"] {
+            Self::run(program, &["-version"])?;
+        }
+        Ok(())
+    }
+
+    // stdout of a command, DecoderUnavailable if it is missing or fails
+    fn run(program: &str, args: &[&str]) -> Result<String, MosaicError> {
+        let output = Command::new(program).args(args).output()
+            .map_err(|e| AudioError::DecoderUnavailable(format!("{} not found ({})", program, e)))?;
+        if !output.status.success() {
+            return Err(AudioError::DecoderUnavailable(
+                format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim())
+            ).into());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    pub fn sidecar_path(wav_path: &str) -> String {
+        format!("{}.json", wav_path)
+    }
+
+    fn write_sidecar(extracted: &ExtractedAudio) -> Result<(), MosaicError> {
+        let json = serde_json::to_string_pretty(extracted).map_err(|e| MosaicError::Io(std::io::Error::other(e)))?;
+        fs::write(Self::sidecar_path(&extracted.wav_path), json)?;
+        Ok(())
+    }
+
+    pub fn read_sidecar(wav_path: &str) -> Result<ExtractedAudio, MosaicError> {
+        let contents = fs::read_to_string(Self::sidecar_path(wav_path))?;
+        serde_json::from_str(&contents).map_err(|e| MosaicError::Io(std::io::Error::other(e)))
+    }
+}
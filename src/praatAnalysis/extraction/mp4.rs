@@ -0,0 +1,332 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    MP4 / MOV
+
+    Just enough of the ISO base media file format (and QuickTime) to pull uncompressed PCM audio out of a video
+    and work out where the audio and video tracks start:
+
+        moov/mvhd                  movie timescale
+        moov/trak/edts/elst        edit list (empty edits delay the track, media_time skips the start of it)
+        moov/trak/mdia/mdhd        track timescale
+        moov/trak/mdia/hdlr        'soun' / 'vide'
+        moov/trak/mdia/minf/stbl   stsd (codec), stsc, stsz, stco/co64 (where the samples are)
+
+    Compressed audio (AAC etc.) is identified but not decoded (see extraction.rs for the ffmpeg fallback).
+*/
+
+use crate::errors::{AudioError, MosaicError};
+use crate::praatAnalysis::native::wav::{Audio};
+
+// (type, body) of a box
+type Boxes<'a> = Vec<([u8; 4], &'a [u8])>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Track {
+    pub handler: String, // "soun" / "vide"
+    pub timescale: u32,
+    pub codec: String, // sample entry fourcc (i.e. "mp4a", "sowt", "lpcm")
+    pub channels: u16,
+    pub bits: u16,
+    pub sample_rate: f64,
+    pub float: bool,
+    pub big_endian: bool,
+    pub edits: Vec<(u64, i64)>, // (segment duration in movie timescale, media time in track timescale, -1 for empty)
+    pub samples_per_chunk: Vec<(u32, u32)>, // (first chunk (1 based), samples per chunk)
+    pub sample_count: u32,
+    pub chunk_offsets: Vec<u64>,
+}
+
+impl Track {
+    // presentation time (s) of the first presented sample
+    pub fn start_time(&self, movie_timescale: u32) -> f64 {
+        let empty: u64 = self.edits.iter().take_while(|e| e.1 == -1).map(|e| e.0).sum();
+        empty as f64 / movie_timescale.max(1) as f64
+    }
+
+    // media time (s) skipped at the start by the edit list
+    pub fn skipped_time(&self) -> f64 {
+        self.edits.iter().find(|e| e.1 >= 0).map(|e| e.1 as f64 / self.timescale.max(1) as f64).unwrap_or(0.0)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Movie {
+    pub timescale: u32,
+    pub tracks: Vec<Track>,
+}
+
+impl Movie {
+    pub fn parse(bytes: &[u8]) -> Result<Movie, MosaicError> {
+        let mut movie = Movie::default();
+        let moov = Self::children(bytes)?.into_iter().find(|(kind, _)| kind == b"moov")
+            .ok_or_else(|| AudioError::MalformedContainer("no moov box".to_string()))?;
+
+        for (kind, body) in Self::children(moov.1)? {
+            match &kind {
+                b"mvhd" => {
+                    let version = *body.first().ok_or_else(|| Self::short("mvhd"))?;
+                    movie.timescale = Self::u32_at(body, if version == 1 { 20 } else { 12 })?;
+                }
+                b"trak" => movie.tracks.push(Self::parse_track(body)?),
+                _ => {}
+            }
+        }
+        Ok(movie)
+    }
+
+    pub fn audio_track(&self) -> Option<&Track> {
+        self.tracks.iter().find(|t| t.handler == "soun")
+    }
+
+    pub fn video_track(&self) -> Option<&Track> {
+        self.tracks.iter().find(|t| t.handler == "vide")
+    }
+
+    fn parse_track(trak: &[u8]) -> Result<Track, MosaicError> {
+        let mut track = Track::default();
+        for (kind, body) in Self::children(trak)? {
+            match &kind {
+                b"edts" => {
+                    for (kind, elst) in Self::children(body)? {
+                        if &kind == b"elst" { track.edits = Self::parse_elst(elst)?; }
+                    }
+                }
+                b"mdia" => {
+                    for (kind, body) in Self::children(body)? {
+                        match &kind {
+                            b"mdhd" => {
+                                let version = *body.first().ok_or_else(|| Self::short("mdhd"))?;
+                                track.timescale = Self::u32_at(body, if version == 1 { 20 } else { 12 })?;
+                            }
+                            b"hdlr" => {
+                                track.handler = String::from_utf8_lossy(Self::slice(body, 8, 4)?).to_string();
+                            }
+                            b"minf" => {
+                                for (kind, stbl) in Self::children(body)? {
+                                    if &kind == b"stbl" { Self::parse_stbl(stbl, &mut track)?; }
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(track)
+    }
+
+    fn parse_elst(body: &[u8]) -> Result<Vec<(u64, i64)>, MosaicError> {
+        let version = *body.first().ok_or_else(|| Self::short("elst"))?;
+        let count = Self::u32_at(body, 4)? as usize;
+        let entry = if version == 1 { 20 } else { 12 };
+        (0..count).map(|k| {
+            let at = 8 + k * entry;
+            Ok(if version == 1 {
+                (Self::u64_at(body, at)?, Self::u64_at(body, at + 8)? as i64)
+            } else {
+                (Self::u32_at(body, at)? as u64, Self::u32_at(body, at + 4)? as i32 as i64)
+            })
+        }).collect()
+    }
+
+    fn parse_stbl(stbl: &[u8], track: &mut Track) -> Result<(), MosaicError> {
+        for (kind, body) in Self::children(stbl)? {
+            match &kind {
+                b"stsd" => Self::parse_stsd(body, track)?,
+                b"stsc" => {
+                    let count = Self::u32_at(body, 4)? as usize;
+                    track.samples_per_chunk = (0..count)
+                        .map(|k| Ok((Self::u32_at(body, 8 + 12 * k)?, Self::u32_at(body, 12 + 12 * k)?)))
+                        .collect::<Result<_, MosaicError>>()?;
+                }
+                b"stsz" => track.sample_count = Self::u32_at(body, 8)?,
+                b"stco" => {
+                    let count = Self::u32_at(body, 4)? as usize;
+                    track.chunk_offsets = (0..count)
+                        .map(|k| Ok(Self::u32_at(body, 8 + 4 * k)? as u64))
+                        .collect::<Result<_, MosaicError>>()?;
+                }
+                b"co64" => {
+                    let count = Self::u32_at(body, 4)? as usize;
+                    track.chunk_offsets = (0..count)
+                        .map(|k| Self::u64_at(body, 8 + 8 * k))
+                        .collect::<Result<_, MosaicError>>()?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // first sample entry of the sample description
+    fn parse_stsd(body: &[u8], track: &mut Track) -> Result<(), MosaicError> {
+        let entry = Self::slice(body, 8, body.len().saturating_sub(8))?;
+        track.codec = String::from_utf8_lossy(Self::slice(entry, 4, 4)?).to_string();
+        if track.handler != "soun" { return Ok(()); }
+
+        // sound sample entry: 8 byte header + 8 reserved/data reference, then the QuickTime sound description
+        let version = Self::u16_at(entry, 16)?;
+        track.channels = Self::u16_at(entry, 24)?;
+        track.bits = Self::u16_at(entry, 26)?;
+        track.sample_rate = Self::u32_at(entry, 32)? as f64 / 65536.0;
+
+        match track.codec.as_str() {
+            "sowt" => track.big_endian = false,
+            "twos" | "in24" | "in32" => track.big_endian = true,
+            "fl32" | "fl64" => { track.float = true; track.big_endian = true; }
+            "raw " => { track.big_endian = false; track.bits = 8; }
+            _ => {}
+        }
+        match track.codec.as_str() {
+            "in24" => track.bits = 24,
+            "in32" | "fl32" => track.bits = 32,
+            "fl64" => track.bits = 64,
+            _ => {}
+        }
+
+        // QuickTime version 2 (lpcm): rate, channels, bits and flags after the standard description
+        if version == 2 {
+            track.sample_rate = f64::from_bits(Self::u64_at(entry, 40)?);
+            track.channels = Self::u32_at(entry, 48)? as u16;
+            track.bits = Self::u32_at(entry, 56)? as u16;
+            let flags = Self::u32_at(entry, 60)?;
+            track.float = flags & 1 != 0;
+            track.big_endian = flags & 2 != 0;
+        }
+
+        // ISO 'ipcm' / 'fpcm': endianness is in the pcmC box
+        if track.codec == "ipcm" || track.codec == "fpcm" {
+            track.float = track.codec == "fpcm";
+            let boxes_start = if version == 1 { 52 } else { 36 };
+            if let Ok(children) = Self::children(Self::slice(entry, boxes_start, entry.len().saturating_sub(boxes_start))?)
+                && let Some((_, pcmc)) = children.iter().find(|(kind, _)| kind == b"pcmC")
+            {
+                track.big_endian = pcmc.get(4).map(|f| f & 1 == 0).unwrap_or(true);
+                if let Some(size) = pcmc.get(5) { track.bits = *size as u16; }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_pcm(track: &Track) -> bool {
+        matches!(track.codec.as_str(), "sowt" | "twos" | "in24" | "in32" | "fl32" | "fl64" | "raw " | "lpcm" | "ipcm" | "fpcm")
+    }
+
+    // decode an uncompressed PCM track to mono (the edit list skip is applied)
+    pub fn decode_pcm(bytes: &[u8], track: &Track) -> Result<Audio, MosaicError> {
+        if !Self::is_pcm(track) { return Err(AudioError::UnsupportedCodec(track.codec.clone()).into()); }
+        if track.channels == 0 || track.sample_rate <= 0.0 {
+            return Err(AudioError::MalformedContainer("sound description has no channels / sample rate".to_string()).into());
+        }
+
+        let width = (track.bits as usize).div_ceil(8);
+        let decode: fn(&[u8], bool) -> f64 = match (track.float, width) {
+            // 8 bit `raw ` is unsigned (offset binary), 8 bit `twos` is signed
+            (false, 1) if track.codec == "raw " => |b, _| (b[0] as f64 - 128.0) / 128.0,
+            (false, 1) => |b, _| b[0] as i8 as f64 / 128.0,
+            (false, 2) => |b, be| (if be { i16::from_be_bytes([b[0], b[1]]) } else { i16::from_le_bytes([b[0], b[1]]) }) as f64 / 32768.0,
+            (false, 3) => |b, be| {
+                let v = if be { i32::from_be_bytes([b[0], b[1], b[2], 0]) } else { i32::from_le_bytes([0, b[0], b[1], b[2]]) };
+                (v >> 8) as f64 / 8388608.0
+            },
+            (false, 4) => |b, be| {
+                let raw = [b[0], b[1], b[2], b[3]];
+                (if be { i32::from_be_bytes(raw) } else { i32::from_le_bytes(raw) }) as f64 / 2147483648.0
+            },
+            (true, 4) => |b, be| {
+                let raw = [b[0], b[1], b[2], b[3]];
+                (if be { f32::from_be_bytes(raw) } else { f32::from_le_bytes(raw) }) as f64
+            },
+            (true, 8) => |b, be| {
+                let raw: [u8; 8] = b[0..8].try_into().unwrap();
+                if be { f64::from_be_bytes(raw) } else { f64::from_le_bytes(raw) }
+            },
+            _ => return Err(AudioError::UnsupportedCodec(format!("{} ({} bit)", track.codec, track.bits)).into()),
+        };
+
+        // every stsz sample of an uncompressed track is one PCM frame
+        let frame_width = width * track.channels as usize;
+        let mut samples = Vec::with_capacity(track.sample_count as usize);
+        let mut remaining = track.sample_count as usize;
+        for (chunk, &offset) in track.chunk_offsets.iter().enumerate() {
+            let chunk_number = chunk as u32 + 1;
+            let per_chunk = track.samples_per_chunk.iter()
+                .take_while(|(first, _)| *first <= chunk_number)
+                .last()
+                .map(|(_, n)| *n as usize)
+                .unwrap_or(0)
+                .min(remaining);
+            remaining -= per_chunk;
+
+            let start = offset as usize;
+            let data = bytes.get(start..start + per_chunk * frame_width)
+                .ok_or_else(|| AudioError::MalformedContainer("chunk outside the file".to_string()))?;
+            for frame in data.chunks_exact(frame_width) {
+                samples.push(frame.chunks_exact(width).map(|b| decode(b, track.big_endian)).sum::<f64>() / track.channels as f64);
+            }
+        }
+
+        let skip = ((track.skipped_time() * track.sample_rate).round() as usize).min(samples.len());
+        samples.drain(..skip);
+        if samples.is_empty() { return Err(AudioError::EmptyAudio.into()); }
+
+        Ok(Audio { sample_rate: track.sample_rate, samples })
+    }
+
+    // boxes in a container
+    fn children(bytes: &[u8]) -> Result<Boxes<'_>, MosaicError> {
+        let mut out = Vec::new();
+        let mut offset = 0;
+        while offset + 8 <= bytes.len() {
+            let size = Self::u32_at(bytes, offset)? as u64;
+            let kind: [u8; 4] = bytes[offset + 4..offset + 8].try_into().unwrap();
+            let (header, size) = match size {
+                0 => (8, (bytes.len() - offset) as u64), // to the end of the file
+                1 => (16, Self::u64_at(bytes, offset + 8)?), // 64 bit size
+                _ => (8, size),
+            };
+            let end = offset as u64 + size;
+            if size < header as u64 || end > bytes.len() as u64 {
+                return Err(AudioError::MalformedContainer(format!("box '{}' runs past its parent", String::from_utf8_lossy(&kind))).into());
+            }
+            out.push((kind, &bytes[offset + header..end as usize]));
+            offset = end as usize;
+        }
+        Ok(out)
+    }
+
+    fn short(name: &str) -> MosaicError {
+        AudioError::MalformedContainer(format!("{} box too short", name)).into()
+    }
+
+    fn slice(bytes: &[u8], at: usize, length: usize) -> Result<&[u8], MosaicError> {
+        bytes.get(at..at + length).ok_or_else(|| Self::short("sample table"))
+    }
+
+    fn u16_at(bytes: &[u8], at: usize) -> Result<u16, MosaicError> {
+        Ok(u16::from_be_bytes(Self::slice(bytes, at, 2)?.try_into().unwrap()))
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> Result<u32, MosaicError> {
+        Ok(u32::from_be_bytes(Self::slice(bytes, at, 4)?.try_into().unwrap()))
+    }
+
+    fn u64_at(bytes: &[u8], at: usize) -> Result<u64, MosaicError> {
+        Ok(u64::from_be_bytes(Self::slice(bytes, at, 8)?.try_into().unwrap()))
+    }
+}
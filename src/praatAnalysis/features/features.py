@@ -0,0 +1,81 @@
+"""
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+"""
+
+
+"""
+    Acoustic feature extraction for the SpeechUMD.
+
+    Every feature is sampled on the same grid (t = k * time_step from 0, frame = k + 1) so the rows line up with
+    the UMD frame/timestamp conventions. Undefined values (unvoiced frames, edges) are returned as NaN.
+"""
+
+import math
+import parselmouth
+
+
+def _defined(value):
+    # praat uses -200 dB for undefined harmonicity
+    if value is None or math.isnan(value) or value <= -200.0:
+        return math.nan
+    return float(value)
+
+
+def _moments(sound, t, window):
+    start, end = t - window / 2, t + window / 2
+    if start < sound.xmin or end > sound.xmax:
+        return [math.nan] * 4
+    spectrum = sound.extract_part(from_time=start, to_time=end, window_shape=parselmouth.WindowShape.HANNING).to_spectrum()
+    return [
+        _defined(spectrum.get_center_of_gravity(power=2.0)),
+        _defined(spectrum.get_standard_deviation(power=2.0)),
+        _defined(spectrum.get_skewness(power=2.0)),
+        _defined(spectrum.get_kurtosis(power=2.0)),
+    ]
+
+
+def extract_features(path, time_step, pitch_floor, pitch_ceiling, max_formant, number_of_formants, formant_window, spectral_window):
+    sound = parselmouth.Sound(path)
+    if sound.n_channels > 1:
+        sound = sound.convert_to_mono()
+
+    pitch = sound.to_pitch(time_step=time_step, pitch_floor=pitch_floor, pitch_ceiling=pitch_ceiling)
+    intensity = sound.to_intensity(minimum_pitch=pitch_floor, time_step=time_step)
+    formant = sound.to_formant_burg(
+        time_step=time_step,
+        max_number_of_formants=number_of_formants,
+        maximum_formant=max_formant,
+        window_length=formant_window,
+    )
+    harmonicity = sound.to_harmonicity_cc(time_step=time_step, minimum_pitch=pitch_floor)
+
+    frames = int(math.floor((sound.xmax - sound.xmin) / time_step)) + 1
+    out = {key: [] for key in ("timestamp", "f0", "intensity", "f1", "f2", "f3", "f4", "hnr",
+                              "spectral_cog", "spectral_sd", "spectral_skewness", "spectral_kurtosis")}
+
+    for k in range(frames):
+        t = sound.xmin + k * time_step
+        out["timestamp"].append(k * time_step)
+        out["f0"].append(_defined(pitch.get_value_at_time(t)))
+        out["intensity"].append(_defined(intensity.get_value(t)))
+        for n in range(1, 5):
+            out[f"f{n}"].append(_defined(formant.get_value_at_time(n, t)))
+        out["hnr"].append(_defined(harmonicity.get_value(t)))
+        cog, sd, skewness, kurtosis = _moments(sound, t, spectral_window)
+        out["spectral_cog"].append(cog)
+        out["spectral_sd"].append(sd)
+        out["spectral_skewness"].append(skewness)
+        out["spectral_kurtosis"].append(kurtosis)
+
+    return out
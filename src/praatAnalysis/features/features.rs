@@ -0,0 +1,81 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Feature extraction (parselmouth)
+
+    Runs features.py through the pyo3 bridge to get pitch, intensity, formants (Burg), harmonicity (cc) and
+    spectral moments from a WAV file and builds the SpeechUMD from them.
+*/
+
+use crate::praatAnalysis::speechUMD::speechUMD::{SpeechUMD, SpeechFrame, SpeechParameters};
+use pyo3::prelude::*;
+use pyo3::types::PyModule;
+use std::ffi::CString;
+
+pub struct FeatureExtractor;
+
+impl FeatureExtractor {
+    pub fn extract(py: Python<'_>, wav_path: &str, parameters: &SpeechParameters) -> PyResult<SpeechUMD> {
+        let code_rust_str = include_str!("features.py");
+
+        let code_c_string = CString::new(code_rust_str)
+            .expect("Python script contained a null byte!");
+
+        let module = PyModule::from_code(
+            py,
+            &code_c_string,
+            c"features.py",
+            c"features"
+        )?;
+
+        let func = module.getattr("extract_features")?;
+        let result = func.call1((
+            wav_path,
+            parameters.time_step,
+            parameters.pitch_floor,
+            parameters.pitch_ceiling,
+            parameters.max_formant,
+            parameters.number_of_formants,
+            parameters.formant_window,
+            parameters.spectral_window,
+        ))?;
+
+        let timestamp: Vec<f64> = result.get_item("timestamp")?.extract()?;
+        let f0: Vec<f64> = result.get_item("f0")?.extract()?;
+        let intensity: Vec<f64> = result.get_item("intensity")?.extract()?;
+        let f1: Vec<f64> = result.get_item("f1")?.extract()?;
+        let f2: Vec<f64> = result.get_item("f2")?.extract()?;
+        let f3: Vec<f64> = result.get_item("f3")?.extract()?;
+        let f4: Vec<f64> = result.get_item("f4")?.extract()?;
+        let hnr: Vec<f64> = result.get_item("hnr")?.extract()?;
+        let cog: Vec<f64> = result.get_item("spectral_cog")?.extract()?;
+        let sd: Vec<f64> = result.get_item("spectral_sd")?.extract()?;
+        let skewness: Vec<f64> = result.get_item("spectral_skewness")?.extract()?;
+        let kurtosis: Vec<f64> = result.get_item("spectral_kurtosis")?.extract()?;
+
+        let mut speech = SpeechUMD::construction(timestamp.len());
+        for k in 0..timestamp.len() {
+            speech.add_point(k as u32 + 1, timestamp[k] as f32, SpeechFrame {
+                f0: f0[k],
+                intensity: intensity[k],
+                formants: [f1[k], f2[k], f3[k], f4[k]],
+                hnr: hnr[k],
+                moments: [cog[k], sd[k], skewness[k], kurtosis[k]],
+            });
+        }
+        Ok(speech)
+    }
+}
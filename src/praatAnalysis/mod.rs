@@ -14,4 +14,13 @@ MOSAIC. If not, see <https://www.gnu.org/licenses/>.
 */
 
 pub mod testing;
-pub mod setup;
\ No newline at end of file
+pub mod setup;
+pub mod speechUMD;
+pub mod features;
+pub mod native;
+pub mod backend;
+pub mod extraction;
+pub mod sync;
+pub mod voice;
+pub mod rate;
+pub mod vowel;
\ No newline at end of file
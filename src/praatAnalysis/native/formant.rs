@@ -0,0 +1,145 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Formants (Burg LPC)
+
+    Same steps as Praat's `To Formant (burg)`:
+
+        1. resample to twice the maximum formant
+        2. pre-emphasis from 50 Hz
+        3. Gaussian window of twice the (effective) window length
+        4. Burg LPC of order 2 * number of formants
+        5. roots of the LPC polynomial (eigenvalues of the companion matrix). Each root in the upper half plane is
+           a formant, frequency = arg(z) fs / 2pi, bandwidth = -ln|z| fs / pi. Formants below 50 Hz or above
+           (max formant - 50 Hz) are dropped.
+*/
+
+use crate::praatAnalysis::native::wav::{Audio};
+use nalgebra::{DMatrix};
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Formant {
+    pub frequency: f64, // Hz
+    pub bandwidth: f64, // Hz
+}
+
+pub struct FormantAnalysis;
+
+impl FormantAnalysis {
+    // formants at each frame time (lowest first, empty when the window doesn't fit in the sound)
+    pub fn analyse(
+        audio: &Audio, times: &[f64],
+        max_formant: f64, number_of_formants: f64, window_length: f64
+    ) -> Vec<Vec<Formant>> {
+        let resampled = audio.resample(2.0 * max_formant);
+        let sample_rate = resampled.sample_rate;
+        let samples = Self::pre_emphasis(&resampled.samples, sample_rate, 50.0);
+
+        let order = (2.0 * number_of_formants).round() as usize;
+        let length = (2.0 * window_length * sample_rate).round() as usize;
+        let half = length / 2;
+
+        // gaussian window as Praat (edges at 0)
+        let edge = (-12.0_f64).exp();
+        let window: Vec<f64> = (0..length).map(|i| {
+            let x = (i as f64 - 0.5 * (length as f64 - 1.0)) / (length as f64 + 1.0);
+            ((-48.0 * x * x).exp() - edge) / (1.0 - edge)
+        }).collect();
+
+        times.iter().map(|&t| {
+            let centre = (t * sample_rate).round() as isize;
+            let start = centre - half as isize;
+            if order == 0 || length <= order + 1 || start < 0 || start as usize + length > samples.len() {
+                return Vec::new();
+            }
+            let segment: Vec<f64> = samples[start as usize..start as usize + length].iter()
+                .zip(&window).map(|(s, w)| s * w).collect();
+
+            match Self::burg(&segment, order) {
+                Some(coefficients) => Self::roots_to_formants(&coefficients, sample_rate, max_formant),
+                None => Vec::new(),
+            }
+        }).collect()
+    }
+
+    // x[i] - exp(-2 pi F dt) x[i-1]
+    fn pre_emphasis(samples: &[f64], sample_rate: f64, from: f64) -> Vec<f64> {
+        let alpha = (-2.0 * PI * from / sample_rate).exp();
+        let mut out = samples.to_vec();
+        for i in (1..out.len()).rev() {
+            out[i] -= alpha * samples[i - 1];
+        }
+        out
+    }
+
+    // Burg's method (Numerical Recipes memcof). Returns a where x[n] ~ sum a[k] x[n - 1 - k]
+    pub fn burg(x: &[f64], order: usize) -> Option<Vec<f64>> {
+        let n = x.len();
+        if n <= order + 1 { return None; }
+
+        let mut wk1: Vec<f64> = x[..n - 1].to_vec();
+        let mut wk2: Vec<f64> = x[1..].to_vec();
+        let mut a = vec![0.0; order];
+        let mut wkm = vec![0.0; order];
+
+        for k in 1..=order {
+            let mut num = 0.0;
+            let mut denom = 0.0;
+            for j in 0..(n - k) {
+                num += wk1[j] * wk2[j];
+                denom += wk1[j] * wk1[j] + wk2[j] * wk2[j];
+            }
+            if denom <= 0.0 { return None; } // silence
+            a[k - 1] = 2.0 * num / denom;
+            for i in 1..k {
+                a[i - 1] = wkm[i - 1] - a[k - 1] * wkm[k - i - 1];
+            }
+            if k == order { break; }
+
+            wkm[..k].copy_from_slice(&a[..k]);
+            for j in 0..(n - k - 1) {
+                wk1[j] -= wkm[k - 1] * wk2[j];
+                wk2[j] = wk2[j + 1] - wkm[k - 1] * wk1[j + 1];
+            }
+        }
+        Some(a)
+    }
+
+    fn roots_to_formants(a: &[f64], sample_rate: f64, max_formant: f64) -> Vec<Formant> {
+        let p = a.len();
+        // companion matrix of z^p - a1 z^(p-1) - ... - ap
+        let mut companion = DMatrix::<f64>::zeros(p, p);
+        for k in 0..p { companion[(0, k)] = a[k]; }
+        for k in 1..p { companion[(k, k - 1)] = 1.0; }
+
+        let mut formants: Vec<Formant> = companion.complex_eigenvalues().iter()
+            .filter(|z| z.im > 0.0)
+            .map(|z| {
+                // roots outside the unit circle are reflected inside (same frequency)
+                let radius = z.norm();
+                let radius = if radius > 1.0 { 1.0 / radius } else { radius };
+                Formant {
+                    frequency: z.im.atan2(z.re) * sample_rate / (2.0 * PI),
+                    bandwidth: -radius.ln() * sample_rate / PI,
+                }
+            })
+            .filter(|f| f.frequency >= 50.0 && f.frequency <= max_formant - 50.0)
+            .collect();
+        formants.sort_by(|x, y| x.frequency.partial_cmp(&y.frequency).unwrap());
+        formants
+    }
+}
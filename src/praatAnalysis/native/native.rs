@@ -0,0 +1,63 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Native backend
+
+    Pure Rust version of the parselmouth feature extraction (no Python needed). Uses the same frame grid as
+    features.py (t = k * time_step from 0, frame = k + 1) so the two backends can be compared frame by frame.
+*/
+
+use crate::errors::{MosaicError};
+use crate::praatAnalysis::native::wav::{Audio};
+use crate::praatAnalysis::native::pitch::{PitchAnalysis, PitchSettings};
+use crate::praatAnalysis::native::formant::{FormantAnalysis};
+use crate::praatAnalysis::native::spectral::{SpectralAnalysis};
+use crate::praatAnalysis::speechUMD::speechUMD::{SpeechUMD, SpeechFrame, SpeechParameters};
+use std::path::Path;
+
+pub struct NativeExtractor;
+
+impl NativeExtractor {
+    pub fn extract(wav_path: &str, parameters: &SpeechParameters) -> Result<SpeechUMD, MosaicError> {
+        let audio = Audio::read_wav(Path::new(wav_path))?;
+        Ok(Self::extract_audio(&audio, parameters))
+    }
+
+    pub fn extract_audio(audio: &Audio, parameters: &SpeechParameters) -> SpeechUMD {
+        let frames = (audio.duration() / parameters.time_step).floor() as usize + 1;
+        let times: Vec<f64> = (0..frames).map(|k| k as f64 * parameters.time_step).collect();
+
+        let settings = PitchSettings::new(parameters.pitch_floor, parameters.pitch_ceiling);
+        let (f0, hnr) = PitchAnalysis::analyse(&audio.samples, audio.sample_rate, &times, &settings);
+        let intensity = SpectralAnalysis::intensity(&audio.samples, audio.sample_rate, &times, parameters.pitch_floor);
+        let moments = SpectralAnalysis::moments(&audio.samples, audio.sample_rate, &times, parameters.spectral_window);
+        let formants = FormantAnalysis::analyse(
+            audio, &times,
+            parameters.max_formant, parameters.number_of_formants, parameters.formant_window,
+        );
+
+        let mut speech = SpeechUMD::construction(frames);
+        for k in 0..frames {
+            let mut f = [f64::NAN; 4];
+            for (n, formant) in formants[k].iter().take(4).enumerate() { f[n] = formant.frequency; }
+
+            speech.add_point(k as u32 + 1, times[k] as f32, SpeechFrame {
+                f0: f0[k], intensity: intensity[k], formants: f, hnr: hnr[k], moments: moments[k],
+            });
+        }
+        speech
+    }
+}
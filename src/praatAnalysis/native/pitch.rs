@@ -0,0 +1,222 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Pitch (autocorrelation)
+
+    Boersma (1993) "Accurate short-term analysis of the fundamental frequency and the harmonics-to-noise ratio of
+    a sampled sound", the same method as Praat's `To Pitch (ac)`:
+
+        1. Hanning windowed frame of 3 periods of the pitch floor (local mean removed)
+        2. normalised autocorrelation divided by the autocorrelation of the window
+        3. candidates at the local maxima between 1/ceiling and 1/floor (parabolic interpolation) plus an
+           unvoiced candidate whose strength depends on the local intensity
+        4. Viterbi path through the candidates with octave jump and voiced/unvoiced costs
+
+    The strength r of the chosen voiced candidate also gives the harmonicity, HNR = 10 log10(r / (1 - r)).
+*/
+
+use crate::complexMeasurements::signal::dsp::{fft};
+use nalgebra::{Complex};
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PitchSettings {
+    pub pitch_floor: f64,
+    pub pitch_ceiling: f64,
+    pub max_candidates: usize,
+    pub silence_threshold: f64,
+    pub voicing_threshold: f64,
+    pub octave_cost: f64,
+    pub octave_jump_cost: f64,
+    pub voiced_unvoiced_cost: f64,
+}
+
+impl PitchSettings {
+    // Praat's defaults for `To Pitch (ac)`
+    pub fn new(pitch_floor: f64, pitch_ceiling: f64) -> Self {
+        Self {
+            pitch_floor,
+            pitch_ceiling,
+            max_candidates: 15,
+            silence_threshold: 0.03,
+            voicing_threshold: 0.45,
+            octave_cost: 0.01,
+            octave_jump_cost: 0.35,
+            voiced_unvoiced_cost: 0.14,
+        }
+    }
+}
+
+// frequency 0 is the unvoiced candidate
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    frequency: f64,
+    strength: f64, // for the path search (includes the octave cost)
+    correlation: f64, // r at the peak
+}
+
+pub struct PitchAnalysis;
+
+impl PitchAnalysis {
+    // (f0, hnr) at each frame time (NaN when unvoiced or when the window doesn't fit in the sound)
+    pub fn analyse(samples: &[f64], sample_rate: f64, times: &[f64], settings: &PitchSettings) -> (Vec<f64>, Vec<f64>) {
+        let window_length = ((3.0 / settings.pitch_floor) * sample_rate).round() as usize;
+        let half = window_length / 2;
+        let min_lag = (sample_rate / settings.pitch_ceiling).floor().max(2.0) as usize;
+        let max_lag = ((sample_rate / settings.pitch_floor).ceil() as usize).min(window_length / 2);
+
+        let global_peak = samples.iter().fold(0.0_f64, |acc, s| acc.max(s.abs()));
+
+        // window and its normalised autocorrelation
+        let window: Vec<f64> = (0..window_length)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * (i as f64 + 0.5) / window_length as f64).cos())
+            .collect();
+        let fft_size = (2 * window_length).next_power_of_two();
+        let window_autocorrelation = Self::autocorrelation(&window, fft_size);
+
+        let mut frames: Vec<Option<Vec<Candidate>>> = Vec::with_capacity(times.len());
+        for &t in times {
+            let centre = (t * sample_rate).round() as isize;
+            let start = centre - half as isize;
+            if start < 0 || start as usize + window_length > samples.len() || window_length < 4 || max_lag <= min_lag {
+                frames.push(None);
+                continue;
+            }
+            let segment = &samples[start as usize..start as usize + window_length];
+
+            let mean = segment.iter().sum::<f64>() / window_length as f64;
+            let local_peak = segment.iter().fold(0.0_f64, |acc, s| acc.max((s - mean).abs()));
+            let windowed: Vec<f64> = segment.iter().zip(&window).map(|(s, w)| (s - mean) * w).collect();
+
+            let mut candidates = Vec::with_capacity(settings.max_candidates);
+
+            // unvoiced candidate
+            let relative_intensity = if global_peak > 0.0 { local_peak / global_peak } else { 0.0 };
+            let unvoiced_strength = settings.voicing_threshold + (2.0 - relative_intensity
+                / (settings.silence_threshold / (1.0 + settings.voicing_threshold))).max(0.0);
+            candidates.push(Candidate { frequency: 0.0, strength: unvoiced_strength, correlation: 0.0 });
+
+            let autocorrelation = Self::autocorrelation(&windowed, fft_size);
+            if autocorrelation[0] > 0.0 {
+                // both normalised to 1 at lag 0 so r is a correlation (1 for a perfectly periodic frame)
+                let r: Vec<f64> = (0..=max_lag + 1)
+                    .map(|lag| (autocorrelation[lag] / autocorrelation[0])
+                        / (window_autocorrelation[lag] / window_autocorrelation[0]))
+                    .collect();
+
+                let mut voiced = Vec::new();
+                for lag in min_lag.max(1)..=max_lag {
+                    if r[lag] > r[lag - 1] && r[lag] >= r[lag + 1] && r[lag] > 0.5 * settings.voicing_threshold {
+                        // parabolic interpolation of the peak
+                        let (a, b, c) = (r[lag - 1], r[lag], r[lag + 1]);
+                        let denominator = a - 2.0 * b + c;
+                        let shift = if denominator != 0.0 { (0.5 * (a - c) / denominator).clamp(-0.5, 0.5) } else { 0.0 };
+                        let peak = (b - 0.25 * (a - c) * shift).min(1.0);
+                        let period = (lag as f64 + shift) / sample_rate;
+
+                        let strength = peak - settings.octave_cost * (settings.pitch_floor * period).log2();
+                        voiced.push(Candidate { frequency: 1.0 / period, strength, correlation: peak });
+                    }
+                }
+                voiced.sort_by(|x, y| y.strength.partial_cmp(&x.strength).unwrap());
+                voiced.truncate(settings.max_candidates.saturating_sub(1));
+                candidates.extend(voiced);
+            }
+
+            frames.push(Some(candidates));
+        }
+
+        let path = Self::viterbi(&frames, times, settings);
+
+        let mut f0 = Vec::with_capacity(times.len());
+        let mut hnr = Vec::with_capacity(times.len());
+        for (frame, choice) in frames.iter().zip(path) {
+            match (frame, choice) {
+                (Some(candidates), Some(k)) if candidates[k].frequency > 0.0 => {
+                    let r = candidates[k].correlation.min(1.0 - 1e-12);
+                    f0.push(candidates[k].frequency);
+                    hnr.push(if r > 0.0 { 10.0 * (r / (1.0 - r)).log10() } else { f64::NAN });
+                }
+                _ => {
+                    f0.push(f64::NAN);
+                    hnr.push(f64::NAN);
+                }
+            }
+        }
+        (f0, hnr)
+    }
+
+    // autocorrelation via FFT (zero padded to fft_size so there's no wrap around)
+    fn autocorrelation(values: &[f64], fft_size: usize) -> Vec<f64> {
+        let mut spectrum: Vec<Complex<f64>> = (0..fft_size)
+            .map(|i| Complex::new(if i < values.len() { values[i] } else { 0.0 }, 0.0))
+            .collect();
+        fft(&mut spectrum, false);
+        for value in spectrum.iter_mut() { *value = Complex::new(value.norm_sqr(), 0.0); }
+        fft(&mut spectrum, true);
+        spectrum.iter().map(|v| v.re).collect()
+    }
+
+    // best path through consecutive analysed frames (frames without analysis break the path)
+    fn viterbi(frames: &[Option<Vec<Candidate>>], times: &[f64], settings: &PitchSettings) -> Vec<Option<usize>> {
+        let time_step = if times.len() > 1 { times[1] - times[0] } else { 0.01 };
+        let correction = 0.01 / time_step;
+        let octave_jump_cost = settings.octave_jump_cost * correction;
+        let voiced_unvoiced_cost = settings.voiced_unvoiced_cost * correction;
+
+        let mut path = vec![None; frames.len()];
+        let mut k = 0;
+        while k < frames.len() {
+            if frames[k].is_none() { k += 1; continue; }
+
+            // run of analysed frames
+            let start = k;
+            while k < frames.len() && frames[k].is_some() { k += 1; }
+            let run: Vec<&Vec<Candidate>> = frames[start..k].iter().map(|f| f.as_ref().unwrap()).collect();
+
+            let mut score: Vec<f64> = run[0].iter().map(|c| c.strength).collect();
+            let mut back: Vec<Vec<usize>> = vec![Vec::new()];
+            for j in 1..run.len() {
+                let mut next_score = Vec::with_capacity(run[j].len());
+                let mut next_back = Vec::with_capacity(run[j].len());
+                for current in run[j] {
+                    let (best, from) = run[j - 1].iter().enumerate()
+                        .map(|(i, previous)| {
+                            let transition = match (previous.frequency > 0.0, current.frequency > 0.0) {
+                                (false, false) => 0.0,
+                                (true, true) => octave_jump_cost * (previous.frequency / current.frequency).log2().abs(),
+                                _ => voiced_unvoiced_cost,
+                            };
+                            (score[i] - transition, i)
+                        })
+                        .fold((f64::MIN, 0), |acc, x| if x.0 > acc.0 { x } else { acc });
+                    next_score.push(best + current.strength);
+                    next_back.push(from);
+                }
+                score = next_score;
+                back.push(next_back);
+            }
+
+            let mut choice = score.iter().enumerate()
+                .fold((0, f64::MIN), |acc, (i, s)| if *s > acc.1 { (i, *s) } else { acc }).0;
+            for j in (0..run.len()).rev() {
+                path[start + j] = Some(choice);
+                if j > 0 { choice = back[j][choice]; }
+            }
+        }
+        path
+    }
+}
@@ -0,0 +1,103 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Intensity and spectral moments
+
+    Intensity follows Praat's `To Intensity`: the local mean is removed and the squared signal is averaged with a
+    Kaiser (beta = 20) window of 6.4 / pitch floor (effective duration 3.2 / pitch floor), in dB re 2e-5 Pa.
+
+    Spectral moments follow Praat's `Spectrum: Get centre of gravity / standard deviation / skewness / kurtosis`
+    with power 2, on a Hanning windowed frame.
+*/
+
+use crate::complexMeasurements::signal::dsp::{fft};
+use nalgebra::{Complex};
+use std::f64::consts::PI;
+
+pub struct SpectralAnalysis;
+
+impl SpectralAnalysis {
+    // intensity (dB) at each frame time (NaN when the window doesn't fit in the sound)
+    pub fn intensity(samples: &[f64], sample_rate: f64, times: &[f64], pitch_floor: f64) -> Vec<f64> {
+        let length = (6.4 / pitch_floor * sample_rate).round() as usize;
+        let half = length / 2;
+
+        let beta = 20.0;
+        let normaliser = Self::bessel_i0(beta);
+        let window: Vec<f64> = (0..length).map(|i| {
+            let x = 2.0 * i as f64 / (length as f64 - 1.0).max(1.0) - 1.0;
+            Self::bessel_i0(beta * (1.0 - x * x).max(0.0).sqrt()) / normaliser
+        }).collect();
+        let window_sum: f64 = window.iter().sum();
+
+        times.iter().map(|&t| {
+            let start = (t * sample_rate).round() as isize - half as isize;
+            if length < 2 || start < 0 || start as usize + length > samples.len() { return f64::NAN; }
+            let segment = &samples[start as usize..start as usize + length];
+
+            let mean = segment.iter().zip(&window).map(|(s, w)| s * w).sum::<f64>() / window_sum;
+            let power = segment.iter().zip(&window).map(|(s, w)| (s - mean).powi(2) * w).sum::<f64>() / window_sum;
+            if power > 0.0 { 10.0 * (power / 4e-10).log10() } else { f64::NAN }
+        }).collect()
+    }
+
+    // [centre of gravity (Hz), standard deviation (Hz), skewness, kurtosis] of a window_length frame at each time
+    pub fn moments(samples: &[f64], sample_rate: f64, times: &[f64], window_length: f64) -> Vec<[f64; 4]> {
+        let length = (window_length * sample_rate).round() as usize;
+        let half = length / 2;
+        let fft_size = length.next_power_of_two();
+        let window: Vec<f64> = (0..length)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / (length as f64 - 1.0).max(1.0)).cos())
+            .collect();
+
+        times.iter().map(|&t| {
+            let start = (t * sample_rate).round() as isize - half as isize;
+            if length < 2 || start < 0 || start as usize + length > samples.len() { return [f64::NAN; 4]; }
+
+            let mut spectrum: Vec<Complex<f64>> = (0..fft_size).map(|i| {
+                let value = if i < length { samples[start as usize + i] * window[i] } else { 0.0 };
+                Complex::new(value, 0.0)
+            }).collect();
+            fft(&mut spectrum, false);
+
+            let bins = fft_size / 2 + 1;
+            let frequency = |k: usize| k as f64 * sample_rate / fft_size as f64;
+            let weights: Vec<f64> = spectrum[..bins].iter().map(|v| v.norm_sqr()).collect();
+            let total: f64 = weights.iter().sum();
+            if total <= 0.0 { return [f64::NAN; 4]; }
+
+            let centre = (0..bins).map(|k| frequency(k) * weights[k]).sum::<f64>() / total;
+            let central = |n: i32| (0..bins).map(|k| (frequency(k) - centre).powi(n) * weights[k]).sum::<f64>() / total;
+            let (m2, m3, m4) = (central(2), central(3), central(4));
+            if m2 <= 0.0 { return [centre, 0.0, f64::NAN, f64::NAN]; }
+
+            [centre, m2.sqrt(), m3 / m2.powf(1.5), m4 / (m2 * m2) - 3.0]
+        }).collect()
+    }
+
+    // modified Bessel function of the first kind, order 0 (power series)
+    fn bessel_i0(x: f64) -> f64 {
+        let mut sum = 1.0;
+        let mut term = 1.0;
+        let quarter_square = 0.25 * x * x;
+        for k in 1..100 {
+            term *= quarter_square / (k * k) as f64;
+            sum += term;
+            if term < 1e-16 * sum { break; }
+        }
+        sum
+    }
+}
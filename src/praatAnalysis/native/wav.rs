@@ -0,0 +1,176 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    WAV
+
+    Minimal RIFF/WAVE reader (PCM 8/16/24/32 bit, IEEE float 32/64 bit, WAVE_FORMAT_EXTENSIBLE) so the native
+    backend doesn't need an audio crate. Channels are averaged to mono and samples are scaled to [-1, 1].
+*/
+
+use crate::errors::{AudioError, MosaicError};
+use std::f64::consts::PI;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct Audio {
+    pub sample_rate: f64, // Hz
+    pub samples: Vec<f64>,
+}
+
+impl Audio {
+    pub fn read_wav(path: &Path) -> Result<Audio, MosaicError> {
+        let bytes = fs::read(path)?;
+        Self::parse_wav(&bytes)
+    }
+
+    pub fn parse_wav(bytes: &[u8]) -> Result<Audio, MosaicError> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(AudioError::MalformedWav("missing RIFF/WAVE header".to_string()).into());
+        }
+
+        let mut format: Option<(u16, u16, u32, u16)> = None; // (format tag, channels, sample rate, bits)
+        let mut data: Option<&[u8]> = None;
+
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let id = &bytes[offset..offset + 4];
+            let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap()) as usize;
+            let start = offset + 8;
+            // a truncated chunk is read up to the end of the file
+            let end = start.saturating_add(size).min(bytes.len());
+            let chunk = &bytes[start..end];
+
+            match id {
+                b"fmt " => {
+                    if chunk.len() < 16 {
+                        return Err(AudioError::MalformedWav("fmt chunk too short".to_string()).into());
+                    }
+                    let mut tag = u16::from_le_bytes([chunk[0], chunk[1]]);
+                    let channels = u16::from_le_bytes([chunk[2], chunk[3]]);
+                    let sample_rate = u32::from_le_bytes(chunk[4..8].try_into().unwrap());
+                    let bits = u16::from_le_bytes([chunk[14], chunk[15]]);
+                    // WAVE_FORMAT_EXTENSIBLE: the real format is the first 2 bytes of the sub format GUID
+                    if tag == 0xFFFE && chunk.len() >= 26 {
+                        tag = u16::from_le_bytes([chunk[24], chunk[25]]);
+                    }
+                    format = Some((tag, channels, sample_rate, bits));
+                }
+                b"data" => data = Some(chunk),
+                _ => {}
+            }
+
+            // chunks are word aligned
+            offset = start.saturating_add(size).saturating_add(size % 2);
+        }
+
+        let (tag, channels, sample_rate, bits) = format
+            .ok_or_else(|| AudioError::MalformedWav("missing fmt chunk".to_string()))?;
+        let data = data.ok_or_else(|| AudioError::MalformedWav("missing data chunk".to_string()))?;
+        if channels == 0 || sample_rate == 0 {
+            return Err(AudioError::MalformedWav("0 channels or 0 Hz sample rate".to_string()).into());
+        }
+
+        let width = (bits as usize).div_ceil(8);
+        let decode: fn(&[u8]) -> f64 = match (tag, bits) {
+            (1, 8) => |b| (b[0] as f64 - 128.0) / 128.0,
+            (1, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f64 / 32768.0,
+            (1, 24) => |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f64 / 8388608.0,
+            (1, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64 / 2147483648.0,
+            (3, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            (3, 64) => |b| f64::from_le_bytes(b[0..8].try_into().unwrap()),
+            _ => return Err(AudioError::UnsupportedFormat(format!("format tag {} with {} bits", tag, bits)).into()),
+        };
+
+        let frame_width = width * channels as usize;
+        let frames = data.len() / frame_width;
+        if frames == 0 { return Err(AudioError::EmptyAudio.into()); }
+
+        let samples = (0..frames).map(|k| {
+            let frame = &data[k * frame_width..(k + 1) * frame_width];
+            frame.chunks_exact(width).map(decode).sum::<f64>() / channels as f64
+        }).collect();
+
+        Ok(Audio { sample_rate: sample_rate as f64, samples })
+    }
+
+    // mono 16 bit PCM (samples are clipped to [-1, 1])
+    pub fn write_wav(&self, path: &Path) -> Result<(), MosaicError> {
+        let sample_rate = self.sample_rate.round() as u32;
+        let data_size = (self.samples.len() * 2) as u32;
+
+        let mut bytes = Vec::with_capacity(44 + data_size as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+        bytes.extend_from_slice(&2u16.to_le_bytes()); // block align
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        for s in &self.samples {
+            let value = (s.clamp(-1.0, 1.0) * 32767.0).round() as i16;
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.samples.len() as f64 / self.sample_rate
+    }
+
+    // windowed sinc resampling (Hann window, 16 zero crossings each side). Downsampling low pass filters at the
+    // new Nyquist frequency
+    pub fn resample(&self, sample_rate: f64) -> Audio {
+        if (sample_rate - self.sample_rate).abs() < 1e-9 || self.samples.is_empty() {
+            return self.clone();
+        }
+
+        let ratio = sample_rate / self.sample_rate;
+        let cutoff = ratio.min(1.0);
+        let half_width = 16.0 / cutoff;
+
+        let length = (self.samples.len() as f64 * ratio).round() as usize;
+        let samples = (0..length).map(|m| {
+            let position = m as f64 / ratio;
+            let first = (position - half_width).ceil().max(0.0) as usize;
+            let last = ((position + half_width).floor() as usize).min(self.samples.len() - 1);
+
+            let mut acc = 0.0;
+            for k in first..=last {
+                let x = position - k as f64;
+                let window = 0.5 * (1.0 + (PI * x / half_width).cos());
+                acc += self.samples[k] * cutoff * Self::sinc(cutoff * x) * window;
+            }
+            acc
+        }).collect();
+
+        Audio { sample_rate, samples }
+    }
+
+    fn sinc(x: f64) -> f64 {
+        if x.abs() < 1e-12 { 1.0 } else { (PI * x).sin() / (PI * x) }
+    }
+}
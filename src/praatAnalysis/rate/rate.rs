@@ -0,0 +1,320 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Speech rate
+
+    Syllable nuclei from the SpeechUMD intensity track (de Jong & Wempe, 2009) and the rate / pause statistics
+    built on them:
+
+        1. threshold = 99th percentile of the intensity + silence_db (-25 dB)
+        2. sounding / silent intervals: silences shorter than min_pause are bridged, sounding stretches shorter
+           than min_sounding are dropped
+        3. candidate nuclei: intensity peaks above the threshold inside sounding intervals
+        4. a peak is kept if the intensity dips at least min_dip (2 dB) below it before the next peak
+        5. unvoiced peaks (no f0) are dropped
+
+    Rates follow de Jong & Wempe: speech rate over the whole recording, articulation rate over the phonation time
+    (sounding intervals). Pauses are the silent intervals between the first and last sounding interval, so
+    leading / trailing silence isn't counted.
+
+    The nuclei can be checked against kinematic DDK cycles (same clock, so sync the SpeechUMD first): a nucleus
+    matches when a DDK opening peak is within `match_tolerance`.
+*/
+
+use crate::complexMeasurements::ddk::ddk::{DDKCycles};
+use crate::praatAnalysis::speechUMD::speechUMD::{SpeechUMD};
+use polars::prelude::*;
+use std::fs::File;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SpeechRateOptions {
+    pub silence_db: f64, // dB relative to the 99th percentile
+    pub min_dip: f64, // dB
+    pub min_pause: f64, // s
+    pub min_sounding: f64, // s
+    pub match_tolerance: f64, // s, nucleus to DDK peak
+}
+
+impl Default for SpeechRateOptions {
+    // de Jong & Wempe's defaults
+    fn default() -> Self {
+        Self { silence_db: -25.0, min_dip: 2.0, min_pause: 0.3, min_sounding: 0.1, match_tolerance: 0.1 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyllableNuclei {
+    pub trial: Vec<String>,
+    pub nucleus: Vec<u32>,
+    pub frame: Vec<u32>,
+    pub timestamp: Vec<f64>, // s
+    pub intensity: Vec<f64>, // dB
+    pub f0: Vec<f64>, // Hz
+}
+
+impl SyllableNuclei {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            trial: Vec::with_capacity(estimated_entries),
+            nucleus: Vec::with_capacity(estimated_entries),
+            frame: Vec::with_capacity(estimated_entries),
+            timestamp: Vec::with_capacity(estimated_entries),
+            intensity: Vec::with_capacity(estimated_entries),
+            f0: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(&mut self, trial: String, frame: u32, timestamp: f64, intensity: f64, f0: f64) {
+        self.nucleus.push(self.nucleus.len() as u32 + 1);
+        self.trial.push(trial);
+        self.frame.push(frame);
+        self.timestamp.push(timestamp);
+        self.intensity.push(intensity);
+        self.f0.push(f0);
+    }
+
+    pub fn save_syllable_nuclei_to_parquet(data: &SyllableNuclei, file_path: &str) -> PolarsResult<()> {
+        let s_trial = Series::new("trial", &data.trial);
+        let s_nucleus = Series::new("nucleus", &data.nucleus);
+        let s_frame = Series::new("frame", &data.frame);
+        let s_time = Series::new("timestamp", &data.timestamp);
+        let s_intensity = Series::new("intensity", &data.intensity);
+        let s_f0 = Series::new("f0", &data.f0);
+
+        let mut df = DataFrame::new(vec![
+            s_trial, s_nucleus, s_frame, s_time, s_intensity, s_f0,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported syllable nuclei data to: {}", file_path);
+        Ok(())
+    }
+}
+
+// one row per trial
+#[derive(Debug, Clone)]
+pub struct SpeechRateSummary {
+    pub trial: Vec<String>,
+    pub syllables: Vec<u32>,
+    pub duration: Vec<f64>, // s, whole recording
+    pub phonation_time: Vec<f64>, // s, sounding intervals
+    pub speech_rate: Vec<f64>, // syllables/s
+    pub articulation_rate: Vec<f64>, // syllables/s of phonation
+    pub average_syllable_duration: Vec<f64>, // s
+    pub pause_count: Vec<u32>,
+    pub mean_pause_duration: Vec<f64>, // s
+    pub pause_proportion: Vec<f64>, // of the time from the first to the last sounding interval
+    pub threshold: Vec<f64>, // dB
+    pub ddk_cycles: Vec<u32>, // 0 when not checked
+    pub ddk_rate: Vec<f64>, // cycles/s over the same span as the speech rate
+    pub ddk_match: Vec<f64>, // proportion of nuclei with a DDK peak within the tolerance
+}
+
+impl SpeechRateSummary {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            trial: Vec::with_capacity(estimated_entries),
+            syllables: Vec::with_capacity(estimated_entries),
+            duration: Vec::with_capacity(estimated_entries),
+            phonation_time: Vec::with_capacity(estimated_entries),
+            speech_rate: Vec::with_capacity(estimated_entries),
+            articulation_rate: Vec::with_capacity(estimated_entries),
+            average_syllable_duration: Vec::with_capacity(estimated_entries),
+            pause_count: Vec::with_capacity(estimated_entries),
+            mean_pause_duration: Vec::with_capacity(estimated_entries),
+            pause_proportion: Vec::with_capacity(estimated_entries),
+            threshold: Vec::with_capacity(estimated_entries),
+            ddk_cycles: Vec::with_capacity(estimated_entries),
+            ddk_rate: Vec::with_capacity(estimated_entries),
+            ddk_match: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn save_speech_rate_to_parquet(data: &SpeechRateSummary, file_path: &str) -> PolarsResult<()> {
+        let s_trial = Series::new("trial", &data.trial);
+        let s_syllables = Series::new("syllables", &data.syllables);
+        let s_duration = Series::new("duration", &data.duration);
+        let s_phonation = Series::new("phonation_time", &data.phonation_time);
+        let s_speech_rate = Series::new("speech_rate", &data.speech_rate);
+        let s_articulation_rate = Series::new("articulation_rate", &data.articulation_rate);
+        let s_asd = Series::new("average_syllable_duration", &data.average_syllable_duration);
+        let s_pause_count = Series::new("pause_count", &data.pause_count);
+        let s_pause_duration = Series::new("mean_pause_duration", &data.mean_pause_duration);
+        let s_pause_proportion = Series::new("pause_proportion", &data.pause_proportion);
+        let s_threshold = Series::new("threshold", &data.threshold);
+        let s_ddk_cycles = Series::new("ddk_cycles", &data.ddk_cycles);
+        let s_ddk_rate = Series::new("ddk_rate", &data.ddk_rate);
+        let s_ddk_match = Series::new("ddk_match", &data.ddk_match);
+
+        let mut df = DataFrame::new(vec![
+            s_trial, s_syllables, s_duration, s_phonation, s_speech_rate, s_articulation_rate, s_asd,
+            s_pause_count, s_pause_duration, s_pause_proportion, s_threshold,
+            s_ddk_cycles, s_ddk_rate, s_ddk_match,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported speech rate data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct SpeechRateCalculator;
+
+impl SpeechRateCalculator {
+    pub fn calculate(
+        trial: &str, speech: &SpeechUMD, ddk: Option<&DDKCycles>, options: &SpeechRateOptions
+    ) -> (SyllableNuclei, SpeechRateSummary) {
+        let mut nuclei = SyllableNuclei::construction(0);
+        let mut summary = SpeechRateSummary::construction(1);
+
+        let n = speech.len();
+        let times: Vec<f64> = speech.timestamp.iter().map(|&t| t as f64).collect();
+        let step = if n > 1 { (times[n - 1] - times[0]) / (n - 1) as f64 } else { 0.0 };
+        // undefined intensity (edges) counts as silence
+        let intensity: Vec<f64> = speech.intensity.iter().map(|&db| if db.is_nan() { f64::NEG_INFINITY } else { db }).collect();
+
+        let mut defined: Vec<f64> = speech.intensity.iter().copied().filter(|db| !db.is_nan()).collect();
+        defined.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let threshold = if defined.is_empty() {
+            f64::NAN
+        } else {
+            defined[((defined.len() - 1) as f64 * 0.99).round() as usize] + options.silence_db
+        };
+
+        let sounding = if threshold.is_nan() { Vec::new() } else { Self::sounding(&intensity, threshold, step, options) };
+        let is_sounding = |k: usize| sounding.iter().any(|&(a, b)| k >= a && k <= b);
+
+        // candidate peaks (plateaus count once, at their first frame)
+        let peaks: Vec<usize> = (1..n.saturating_sub(1))
+            .filter(|&k| intensity[k] > threshold && intensity[k] > intensity[k - 1] && intensity[k] >= intensity[k + 1])
+            .filter(|&k| is_sounding(k))
+            .collect();
+
+        // dip before the next peak, then voicing
+        for (i, &k) in peaks.iter().enumerate() {
+            if let Some(&next) = peaks.get(i + 1) {
+                let dip = intensity[k..=next].iter().copied().fold(f64::INFINITY, f64::min);
+                if intensity[k] - dip < options.min_dip { continue; }
+            }
+            if speech.f0[k].is_nan() { continue; }
+            nuclei.add_point(trial.to_string(), speech.frame[k], times[k], speech.intensity[k], speech.f0[k]);
+        }
+
+        let duration = if n > 1 { times[n - 1] - times[0] } else { 0.0 };
+        let phonation_time: f64 = sounding.iter().map(|&(a, b)| (b - a + 1) as f64 * step).sum();
+        let pauses: Vec<f64> = sounding.windows(2).map(|w| (w[1].0 - w[0].1 - 1) as f64 * step).collect();
+        let span = match (sounding.first(), sounding.last()) {
+            (Some(first), Some(last)) => (last.1 - first.0 + 1) as f64 * step,
+            _ => 0.0,
+        };
+
+        let syllables = nuclei.nucleus.len();
+        let ratio = |a: f64, b: f64| if b > 0.0 { a / b } else { f64::NAN };
+
+        let (ddk_cycles, ddk_rate, ddk_match) = match ddk {
+            Some(ddk) => {
+                let matched = nuclei.timestamp.iter()
+                    .filter(|&&t| ddk.peak_time.iter().any(|&p| (p - t).abs() <= options.match_tolerance))
+                    .count();
+                let start = times.first().copied().unwrap_or(0.0);
+                let end = times.last().copied().unwrap_or(0.0);
+                let cycles = ddk.peak_time.iter().filter(|&&p| p >= start && p <= end).count();
+                (cycles as u32, ratio(cycles as f64, duration), ratio(matched as f64, syllables as f64))
+            }
+            None => (0, f64::NAN, f64::NAN),
+        };
+
+        summary.trial.push(trial.to_string());
+        summary.syllables.push(syllables as u32);
+        summary.duration.push(duration);
+        summary.phonation_time.push(phonation_time);
+        summary.speech_rate.push(ratio(syllables as f64, duration));
+        summary.articulation_rate.push(ratio(syllables as f64, phonation_time));
+        summary.average_syllable_duration.push(ratio(phonation_time, syllables as f64));
+        summary.pause_count.push(pauses.len() as u32);
+        summary.mean_pause_duration.push(ratio(pauses.iter().sum(), pauses.len() as f64));
+        summary.pause_proportion.push(ratio(pauses.iter().sum(), span));
+        summary.threshold.push(threshold);
+        summary.ddk_cycles.push(ddk_cycles);
+        summary.ddk_rate.push(ddk_rate);
+        summary.ddk_match.push(ddk_match);
+
+        (nuclei, summary)
+    }
+
+    // (first, last) frame of each sounding interval
+    fn sounding(intensity: &[f64], threshold: f64, step: f64, options: &SpeechRateOptions) -> Vec<(usize, usize)> {
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        let mut start = None;
+        for (k, &db) in intensity.iter().enumerate() {
+            match (db >= threshold, start) {
+                (true, None) => start = Some(k),
+                (false, Some(s)) => { runs.push((s, k - 1)); start = None; }
+                _ => {}
+            }
+        }
+        if let Some(s) = start { runs.push((s, intensity.len() - 1)); }
+
+        // bridge short silences, then drop short sounding stretches
+        let mut bridged: Vec<(usize, usize)> = Vec::with_capacity(runs.len());
+        for run in runs {
+            match bridged.last_mut() {
+                Some(last) if ((run.0 - last.1 - 1) as f64) * step < options.min_pause => last.1 = run.1,
+                _ => bridged.push(run),
+            }
+        }
+        bridged.into_iter().filter(|&(a, b)| ((b - a + 1) as f64) * step >= options.min_sounding).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::praatAnalysis::speechUMD::speechUMD::{SpeechFrame};
+    use std::f64::consts::PI;
+
+    // 3 s at 0.01 s: silence (40 dB), 5 syllables 0.2 s apart, a 0.6 s pause, 3 more syllables with the last one
+    // unvoiced, then silence. Syllables are 60 +- 10 dB cosine bumps, so every dip is 20 dB
+    fn synthetic_speech() -> SpeechUMD {
+        let mut speech = SpeechUMD::construction(301);
+        for k in 0..=300 {
+            let t = k as f64 * 0.01;
+            let sounding = (0.5..=1.5).contains(&t) || (2.1..=2.7).contains(&t);
+            let intensity = if sounding { 60.0 + 10.0 * (2.0 * PI * (t - 0.6) / 0.2).cos() } else { 40.0 };
+            let f0 = if sounding && !(2.5..=2.7).contains(&t) { 120.0 } else { f64::NAN };
+            speech.add_point(k + 1, t as f32, SpeechFrame {
+                f0, intensity, formants: [f64::NAN; 4], hnr: f64::NAN, moments: [f64::NAN; 4],
+            });
+        }
+        speech
+    }
+
+    #[test]
+    fn counts_voiced_syllable_nuclei() {
+        let (nuclei, summary) = SpeechRateCalculator::calculate("test", &synthetic_speech(), None, &SpeechRateOptions::default());
+
+        let expected = [0.6, 0.8, 1.0, 1.2, 1.4, 2.2, 2.4];
+        assert_eq!(summary.syllables[0], expected.len() as u32);
+        for (t, e) in nuclei.timestamp.iter().zip(expected) {
+            assert!((t - e).abs() < 0.011, "nucleus at {t}, expected {e}");
+        }
+        assert_eq!(summary.pause_count[0], 1);
+        assert!((summary.mean_pause_duration[0] - 0.59).abs() < 0.011);
+    }
+}
@@ -0,0 +1,386 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    SpeechUMD
+
+    The acoustic counterpart of the UMD: one row per analysis frame with the same `frame` / `timestamp`
+    conventions (frames start at 1, timestamp in seconds from the start of the recording as f32) so the acoustic
+    and kinematic tables can be joined.
+
+    Unvoiced / undefined values are NaN.
+
+    Voice quality (jitter, shimmer, HNR, CPPS) only makes sense over a stretch of phonation, so it is kept per file
+    / segment in `voice_quality` rather than per frame.
+*/
+
+use crate::errors::{MosaicError};
+use crate::praatAnalysis::sync::sync::{SyncCorrection};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SpeechParameters {
+    pub time_step: f64, // s
+    pub pitch_floor: f64, // Hz
+    pub pitch_ceiling: f64, // Hz
+    pub max_formant: f64, // Hz (5000 for men, 5500 for women)
+    pub number_of_formants: f64, // Praat allows half formants (i.e. 5.5)
+    pub formant_window: f64, // s
+    pub spectral_window: f64, // s, Hanning window for the spectral moments
+}
+
+impl Default for SpeechParameters {
+    // Praat's defaults except the time step, which is fixed so every feature shares the same frames
+    fn default() -> Self {
+        Self {
+            time_step: 0.01,
+            pitch_floor: 75.0,
+            pitch_ceiling: 600.0,
+            max_formant: 5500.0,
+            number_of_formants: 5.0,
+            formant_window: 0.025,
+            spectral_window: 0.025,
+        }
+    }
+}
+
+impl SpeechParameters {
+    pub fn to_kv_vec(&self) -> Vec<(String, String)> {
+        vec![
+            ("time_step".to_string(), self.time_step.to_string()),
+            ("pitch_floor".to_string(), self.pitch_floor.to_string()),
+            ("pitch_ceiling".to_string(), self.pitch_ceiling.to_string()),
+            ("max_formant".to_string(), self.max_formant.to_string()),
+            ("number_of_formants".to_string(), self.number_of_formants.to_string()),
+            ("formant_window".to_string(), self.formant_window.to_string()),
+            ("spectral_window".to_string(), self.spectral_window.to_string()),
+        ]
+    }
+}
+
+// Praat's defaults for the voice report ("To PointProcess (periodic, cc)", "Get jitter/shimmer", "To Harmonicity (cc)")
+// and for CPPS ("To PowerCepstrogram", "Get CPPS")
+#[derive(Debug, Clone, Copy)]
+pub struct VoiceParameters {
+    pub pitch_floor: f64, // Hz
+    pub pitch_ceiling: f64, // Hz
+    pub period_floor: f64, // s, shortest period counted for jitter / shimmer
+    pub period_ceiling: f64, // s, longest period counted
+    pub max_period_factor: f64, // largest ratio of consecutive periods
+    pub max_amplitude_factor: f64, // largest ratio of consecutive amplitudes (shimmer)
+    pub hnr_time_step: f64, // s
+    pub silence_threshold: f64,
+    pub periods_per_window: f64,
+    pub cepstrum_time_step: f64, // s
+    pub cepstrum_max_frequency: f64, // Hz
+    pub cepstrum_pre_emphasis: f64, // Hz
+    pub cpps_time_averaging: f64, // s
+    pub cpps_quefrency_averaging: f64, // s
+}
+
+impl Default for VoiceParameters {
+    fn default() -> Self {
+        Self {
+            pitch_floor: 75.0,
+            pitch_ceiling: 600.0,
+            period_floor: 0.0001,
+            period_ceiling: 0.02,
+            max_period_factor: 1.3,
+            max_amplitude_factor: 1.6,
+            hnr_time_step: 0.01,
+            silence_threshold: 0.1,
+            periods_per_window: 1.0,
+            cepstrum_time_step: 0.002,
+            cepstrum_max_frequency: 5000.0,
+            cepstrum_pre_emphasis: 50.0,
+            cpps_time_averaging: 0.02,
+            cpps_quefrency_averaging: 0.0005,
+        }
+    }
+}
+
+impl VoiceParameters {
+    pub fn to_kv_vec(&self) -> Vec<(String, String)> {
+        vec![
+            ("voice_pitch_floor".to_string(), self.pitch_floor.to_string()),
+            ("voice_pitch_ceiling".to_string(), self.pitch_ceiling.to_string()),
+            ("period_floor".to_string(), self.period_floor.to_string()),
+            ("period_ceiling".to_string(), self.period_ceiling.to_string()),
+            ("max_period_factor".to_string(), self.max_period_factor.to_string()),
+            ("max_amplitude_factor".to_string(), self.max_amplitude_factor.to_string()),
+            ("hnr_time_step".to_string(), self.hnr_time_step.to_string()),
+            ("silence_threshold".to_string(), self.silence_threshold.to_string()),
+            ("periods_per_window".to_string(), self.periods_per_window.to_string()),
+            ("cepstrum_time_step".to_string(), self.cepstrum_time_step.to_string()),
+            ("cepstrum_max_frequency".to_string(), self.cepstrum_max_frequency.to_string()),
+            ("cepstrum_pre_emphasis".to_string(), self.cepstrum_pre_emphasis.to_string()),
+            ("cpps_time_averaging".to_string(), self.cpps_time_averaging.to_string()),
+            ("cpps_quefrency_averaging".to_string(), self.cpps_quefrency_averaging.to_string()),
+        ]
+    }
+}
+
+// what produced a SpeechUMD and how its timestamps were corrected. Saved as a JSON file next to the parquet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeechMetadata {
+    pub source: String,
+    pub backend: String,
+    pub parameters: Vec<(String, String)>,
+    pub sync: SyncCorrection,
+    #[serde(default)]
+    pub voice_parameters: Vec<(String, String)>, // empty when no voice quality was measured
+}
+
+impl SpeechMetadata {
+    pub fn new(source: String, backend: String, parameters: &SpeechParameters, sync: SyncCorrection) -> Self {
+        Self { source, backend, parameters: parameters.to_kv_vec(), sync, voice_parameters: Vec::new() }
+    }
+
+    pub fn to_kv_vec(&self) -> Vec<(String, String)> {
+        let mut kv = vec![
+            ("source".to_string(), self.source.clone()),
+            ("backend".to_string(), self.backend.clone()),
+        ];
+        kv.extend(self.parameters.iter().cloned());
+        kv.extend(self.voice_parameters.iter().cloned());
+        kv.push(("sync_method".to_string(), self.sync.method.clone()));
+        kv.push(("sync_offset".to_string(), self.sync.offset.to_string()));
+        kv.push(("sync_drift".to_string(), self.sync.drift.to_string()));
+        if let Some(r) = self.sync.correlation {
+            kv.push(("sync_correlation".to_string(), r.to_string()));
+        }
+        if let Some(r) = self.sync.rejected_correlation {
+            kv.push(("sync_rejected_correlation".to_string(), r.to_string()));
+        }
+        kv
+    }
+
+    pub fn save_speech_metadata(&self, file_path: &str) -> Result<(), MosaicError> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| MosaicError::Io(std::io::Error::other(e)))?;
+        fs::write(file_path, json)?;
+        println!("Successfully exported SpeechUMD metadata to: {}", file_path);
+        Ok(())
+    }
+
+    pub fn read_speech_metadata(file_path: &str) -> Result<SpeechMetadata, MosaicError> {
+        let contents = fs::read_to_string(file_path)?;
+        serde_json::from_str(&contents).map_err(|e| MosaicError::Io(std::io::Error::other(e)))
+    }
+}
+
+// voice measures of one segment
+#[derive(Debug, Clone, Copy)]
+pub struct VoiceMeasures {
+    pub jitter: [f64; 3], // local, rap, ppq5
+    pub shimmer: [f64; 4], // local, apq3, apq5, apq11
+    pub hnr: f64, // dB
+    pub cpps: f64, // dB
+}
+
+// one row per segment, segment_id 0 is the whole file (empty tier / label)
+#[derive(Debug, Clone)]
+pub struct VoiceQuality {
+    pub segment_id: Vec<u32>,
+    pub tier: Vec<String>,
+    pub label: Vec<String>,
+    pub start: Vec<f64>, // s
+    pub end: Vec<f64>,
+    pub jitter_local: Vec<f64>, // fraction
+    pub jitter_rap: Vec<f64>,
+    pub jitter_ppq5: Vec<f64>,
+    pub shimmer_local: Vec<f64>, // fraction
+    pub shimmer_apq3: Vec<f64>,
+    pub shimmer_apq5: Vec<f64>,
+    pub shimmer_apq11: Vec<f64>,
+    pub hnr: Vec<f64>, // dB
+    pub cpps: Vec<f64>, // dB
+}
+
+impl VoiceQuality {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            segment_id: Vec::with_capacity(estimated_entries),
+            tier: Vec::with_capacity(estimated_entries),
+            label: Vec::with_capacity(estimated_entries),
+            start: Vec::with_capacity(estimated_entries),
+            end: Vec::with_capacity(estimated_entries),
+            jitter_local: Vec::with_capacity(estimated_entries),
+            jitter_rap: Vec::with_capacity(estimated_entries),
+            jitter_ppq5: Vec::with_capacity(estimated_entries),
+            shimmer_local: Vec::with_capacity(estimated_entries),
+            shimmer_apq3: Vec::with_capacity(estimated_entries),
+            shimmer_apq5: Vec::with_capacity(estimated_entries),
+            shimmer_apq11: Vec::with_capacity(estimated_entries),
+            hnr: Vec::with_capacity(estimated_entries),
+            cpps: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(
+        &mut self, segment_id: u32, tier: String, label: String, start: f64, end: f64,
+        measures: VoiceMeasures
+    ) {
+        self.segment_id.push(segment_id);
+        self.tier.push(tier);
+        self.label.push(label);
+        self.start.push(start);
+        self.end.push(end);
+        self.jitter_local.push(measures.jitter[0]);
+        self.jitter_rap.push(measures.jitter[1]);
+        self.jitter_ppq5.push(measures.jitter[2]);
+        self.shimmer_local.push(measures.shimmer[0]);
+        self.shimmer_apq3.push(measures.shimmer[1]);
+        self.shimmer_apq5.push(measures.shimmer[2]);
+        self.shimmer_apq11.push(measures.shimmer[3]);
+        self.hnr.push(measures.hnr);
+        self.cpps.push(measures.cpps);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segment_id.is_empty()
+    }
+
+    pub fn save_voice_quality_to_parquet(data: &VoiceQuality, file_path: &str) -> PolarsResult<()> {
+        let s_id = Series::new("segment_id", &data.segment_id);
+        let s_tier = Series::new("tier", &data.tier);
+        let s_label = Series::new("label", &data.label);
+        let s_start = Series::new("start", &data.start);
+        let s_end = Series::new("end", &data.end);
+        let s_jitter_local = Series::new("jitter_local", &data.jitter_local);
+        let s_jitter_rap = Series::new("jitter_rap", &data.jitter_rap);
+        let s_jitter_ppq5 = Series::new("jitter_ppq5", &data.jitter_ppq5);
+        let s_shimmer_local = Series::new("shimmer_local", &data.shimmer_local);
+        let s_shimmer_apq3 = Series::new("shimmer_apq3", &data.shimmer_apq3);
+        let s_shimmer_apq5 = Series::new("shimmer_apq5", &data.shimmer_apq5);
+        let s_shimmer_apq11 = Series::new("shimmer_apq11", &data.shimmer_apq11);
+        let s_hnr = Series::new("hnr", &data.hnr);
+        let s_cpps = Series::new("cpps", &data.cpps);
+
+        let mut df = DataFrame::new(vec![
+            s_id, s_tier, s_label, s_start, s_end,
+            s_jitter_local, s_jitter_rap, s_jitter_ppq5,
+            s_shimmer_local, s_shimmer_apq3, s_shimmer_apq5, s_shimmer_apq11,
+            s_hnr, s_cpps,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported voice quality data to: {}", file_path);
+        Ok(())
+    }
+}
+
+// acoustic measures of one analysis frame
+#[derive(Debug, Clone, Copy)]
+pub struct SpeechFrame {
+    pub f0: f64, // Hz
+    pub intensity: f64, // dB
+    pub formants: [f64; 4], // F1-F4 (Hz)
+    pub hnr: f64, // dB
+    pub moments: [f64; 4], // spectral cog, sd, skewness, kurtosis
+}
+
+#[derive(Debug, Clone)]
+pub struct SpeechUMD {
+    pub frame: Vec<u32>,
+    pub timestamp: Vec<f32>,
+    pub f0: Vec<f64>, // Hz
+    pub intensity: Vec<f64>, // dB
+    pub f1: Vec<f64>, // Hz
+    pub f2: Vec<f64>,
+    pub f3: Vec<f64>,
+    pub f4: Vec<f64>,
+    pub hnr: Vec<f64>, // dB
+    pub spectral_cog: Vec<f64>, // Hz
+    pub spectral_sd: Vec<f64>, // Hz
+    pub spectral_skewness: Vec<f64>,
+    pub spectral_kurtosis: Vec<f64>,
+    pub voice_quality: VoiceQuality, // per file / segment, empty unless measured (Python backend)
+}
+
+impl SpeechUMD {
+    pub fn construction(estimated_frames: usize) -> Self {
+        Self {
+            frame: Vec::with_capacity(estimated_frames),
+            timestamp: Vec::with_capacity(estimated_frames),
+            f0: Vec::with_capacity(estimated_frames),
+            intensity: Vec::with_capacity(estimated_frames),
+            f1: Vec::with_capacity(estimated_frames),
+            f2: Vec::with_capacity(estimated_frames),
+            f3: Vec::with_capacity(estimated_frames),
+            f4: Vec::with_capacity(estimated_frames),
+            hnr: Vec::with_capacity(estimated_frames),
+            spectral_cog: Vec::with_capacity(estimated_frames),
+            spectral_sd: Vec::with_capacity(estimated_frames),
+            spectral_skewness: Vec::with_capacity(estimated_frames),
+            spectral_kurtosis: Vec::with_capacity(estimated_frames),
+            voice_quality: VoiceQuality::construction(0),
+        }
+    }
+
+    pub fn add_point(&mut self, frame: u32, timestamp: f32, measures: SpeechFrame) {
+        self.frame.push(frame);
+        self.timestamp.push(timestamp);
+        self.f0.push(measures.f0);
+        self.intensity.push(measures.intensity);
+        self.f1.push(measures.formants[0]);
+        self.f2.push(measures.formants[1]);
+        self.f3.push(measures.formants[2]);
+        self.f4.push(measures.formants[3]);
+        self.hnr.push(measures.hnr);
+        self.spectral_cog.push(measures.moments[0]);
+        self.spectral_sd.push(measures.moments[1]);
+        self.spectral_skewness.push(measures.moments[2]);
+        self.spectral_kurtosis.push(measures.moments[3]);
+    }
+
+    pub fn len(&self) -> usize {
+        self.frame.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frame.is_empty()
+    }
+
+    pub fn save_speech_umd_to_parquet(data: &SpeechUMD, file_path: &str) -> PolarsResult<()> {
+        let s_frame = Series::new("frame", &data.frame);
+        let s_timestamp = Series::new("timestamp", &data.timestamp);
+        let s_f0 = Series::new("f0", &data.f0);
+        let s_intensity = Series::new("intensity", &data.intensity);
+        let s_f1 = Series::new("f1", &data.f1);
+        let s_f2 = Series::new("f2", &data.f2);
+        let s_f3 = Series::new("f3", &data.f3);
+        let s_f4 = Series::new("f4", &data.f4);
+        let s_hnr = Series::new("hnr", &data.hnr);
+        let s_cog = Series::new("spectral_cog", &data.spectral_cog);
+        let s_sd = Series::new("spectral_sd", &data.spectral_sd);
+        let s_skewness = Series::new("spectral_skewness", &data.spectral_skewness);
+        let s_kurtosis = Series::new("spectral_kurtosis", &data.spectral_kurtosis);
+
+        let mut df = DataFrame::new(vec![
+            s_frame, s_timestamp, s_f0, s_intensity,
+            s_f1, s_f2, s_f3, s_f4, s_hnr,
+            s_cog, s_sd, s_skewness, s_kurtosis,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported SpeechUMD data to: {}", file_path);
+        Ok(())
+    }
+}
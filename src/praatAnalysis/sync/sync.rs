@@ -0,0 +1,254 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Sync
+
+    Lines the SpeechUMD (audio clock) up with the UMD (video clock). A correction maps an acoustic timestamp t to
+    the kinematic timeline:
+
+        t' = t + offset + drift * t
+
+    The correction can come from:
+
+        - the container (audio start relative to video frame 0, see extraction.rs)
+        - one or more events seen in both recordings (i.e. a clap or beep). Two or more events also give the drift
+        - cross-correlation of a kinematic signal (i.e. lip aperture speed) with the acoustic amplitude envelope.
+          With `drift_windows` > 1 the lag is estimated in each window and a line is fitted through them. The
+          residual is only applied when its peak correlation reaches `min_correlation`
+*/
+
+use crate::complexMeasurements::signal::signal::{Signal, z_normalise};
+use crate::praatAnalysis::speechUMD::speechUMD::{SpeechUMD};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncCorrection {
+    pub method: String,
+    pub offset: f64, // s
+    pub drift: f64, // s per s
+    pub correlation: Option<f64>, // peak correlation (cross-correlation only)
+    #[serde(default)]
+    pub rejected_correlation: Option<f64>, // peak correlation of a cross-correlation below `min_correlation` (not applied)
+}
+
+impl SyncCorrection {
+    pub fn identity() -> Self {
+        Self { method: "none".to_string(), offset: 0.0, drift: 0.0, correlation: None, rejected_correlation: None }
+    }
+
+    pub fn map(&self, t: f64) -> f64 {
+        t + self.offset + self.drift * t
+    }
+
+    // kinematic time back to the audio clock (i.e. segment times for an analysis of the WAV)
+    pub fn inverse(&self, t: f64) -> f64 {
+        (t - self.offset) / (1.0 + self.drift)
+    }
+
+    // corrects every SpeechUMD timestamp (frame numbers are kept)
+    pub fn apply(&self, speech: &mut SpeechUMD) {
+        for t in speech.timestamp.iter_mut() {
+            *t = self.map(*t as f64) as f32;
+        }
+    }
+
+    // applying `other` after `self`
+    pub fn then(&self, other: &SyncCorrection) -> SyncCorrection {
+        // t'' = (t + o1 + d1 t)(1 + d2) + o2
+        SyncCorrection {
+            method: format!("{} + {}", self.method, other.method),
+            offset: self.offset * (1.0 + other.drift) + other.offset,
+            drift: (1.0 + self.drift) * (1.0 + other.drift) - 1.0,
+            correlation: other.correlation.or(self.correlation),
+            rejected_correlation: other.rejected_correlation.or(self.rejected_correlation),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SyncOptions {
+    pub max_lag: f64, // s
+    pub sample_step: f64, // s, both signals are resampled to this grid
+    pub drift_windows: usize, // 1 for offset only
+    pub min_correlation: f64, // cross-correlation residuals with a lower peak correlation are not applied
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self { max_lag: 0.5, sample_step: 0.01, drift_windows: 1, min_correlation: 0.3 }
+    }
+}
+
+pub struct SyncCalculator;
+
+impl SyncCalculator {
+    // offset from the container (audio start relative to video frame 0)
+    pub fn from_offset(offset: f64) -> SyncCorrection {
+        SyncCorrection { method: "container offset".to_string(), offset, drift: 0.0, correlation: None, rejected_correlation: None }
+    }
+
+    // container offset first, then the residual lag (and drift) by cross-correlation against the kinematic signal.
+    // A residual with a peak correlation below `min_correlation` is rejected: the container offset is kept and the
+    // correlation is recorded in `rejected_correlation`
+    pub fn synchronise(
+        speech: &SpeechUMD, kinematic: Option<&Signal>, container_offset: Option<f64>, options: &SyncOptions
+    ) -> SyncCorrection {
+        let mut correction = match container_offset {
+            Some(offset) => Self::from_offset(offset),
+            None => SyncCorrection::identity(),
+        };
+
+        if let Some(kinematic) = kinematic {
+            let mut shifted = speech.clone();
+            correction.apply(&mut shifted);
+            let envelope = Self::envelope(&shifted);
+            if let Some(residual) = Self::cross_correlate(&Self::speed(kinematic), &envelope, options) {
+                let r = residual.correlation.unwrap_or(f64::NAN);
+                if r >= options.min_correlation {
+                    correction = if container_offset.is_some() { correction.then(&residual) } else { residual };
+                } else {
+                    correction.method = format!("{} (cross-correlation rejected)", correction.method);
+                    correction.rejected_correlation = Some(r);
+                }
+            }
+        }
+        correction
+    }
+
+    // (kinematic time, acoustic time) of events seen in both. One event gives the offset, more give a least
+    // squares line (offset + drift)
+    pub fn from_events(events: &[(f64, f64)]) -> Option<SyncCorrection> {
+        match events.len() {
+            0 => None,
+            1 => Some(SyncCorrection {
+                method: "event".to_string(),
+                offset: events[0].0 - events[0].1,
+                drift: 0.0,
+                correlation: None,
+                rejected_correlation: None,
+            }),
+            _ => {
+                let acoustic: Vec<f64> = events.iter().map(|e| e.1).collect();
+                let lags: Vec<f64> = events.iter().map(|e| e.0 - e.1).collect();
+                let (offset, drift) = Self::line(&acoustic, &lags)?;
+                Some(SyncCorrection { method: "events".to_string(), offset, drift, correlation: None, rejected_correlation: None })
+            }
+        }
+    }
+
+    // absolute speed of a kinematic signal (central differences)
+    pub fn speed(signal: &Signal) -> Signal {
+        let mut speed = Signal::new(format!("speed({})", signal.label));
+        let n = signal.len();
+        for k in 0..n {
+            let (a, b) = (k.saturating_sub(1), (k + 1).min(n - 1));
+            let dt = signal.timestamp[b] - signal.timestamp[a];
+            if dt > 0.0 {
+                speed.push(signal.frame[k], signal.timestamp[k], ((signal.value[b] - signal.value[a]) / dt).abs());
+            }
+        }
+        speed
+    }
+
+    // linear amplitude envelope from the SpeechUMD intensity (undefined frames are silence)
+    pub fn envelope(speech: &SpeechUMD) -> Signal {
+        let mut envelope = Signal::new("envelope".to_string());
+        for k in 0..speech.len() {
+            let db = speech.intensity[k];
+            envelope.push(speech.frame[k], speech.timestamp[k] as f64, if db.is_nan() { 0.0 } else { 10f64.powf(db / 20.0) });
+        }
+        envelope
+    }
+
+    // lag maximising the correlation of the kinematic signal with the acoustic envelope
+    pub fn cross_correlate(kinematic: &Signal, acoustic: &Signal, options: &SyncOptions) -> Option<SyncCorrection> {
+        if kinematic.is_empty() || acoustic.is_empty() { return None; }
+        let start = kinematic.timestamp[0].min(acoustic.timestamp[0]);
+        let end = kinematic.timestamp[kinematic.len() - 1].max(acoustic.timestamp[acoustic.len() - 1]);
+
+        let windows = options.drift_windows.max(1);
+        let width = (end - start) / windows as f64;
+
+        let mut centres = Vec::with_capacity(windows);
+        let mut lags = Vec::with_capacity(windows);
+        let mut correlations = Vec::with_capacity(windows);
+        for w in 0..windows {
+            let (window_start, window_end) = (start + w as f64 * width, start + (w + 1) as f64 * width);
+            if let Some((lag, r)) = Self::window_lag(kinematic, acoustic, window_start, window_end, options) {
+                centres.push(0.5 * (window_start + window_end) - lag); // acoustic time of the window centre
+                lags.push(lag);
+                correlations.push(r);
+            }
+        }
+        if lags.is_empty() { return None; }
+
+        let correlation = correlations.iter().sum::<f64>() / correlations.len() as f64;
+        let (offset, drift) = if lags.len() >= 2 { Self::line(&centres, &lags)? } else { (lags[0], 0.0) };
+        Some(SyncCorrection { method: "cross-correlation".to_string(), offset, drift, correlation: Some(correlation), rejected_correlation: None })
+    }
+
+    // (lag, r): kinematic(t) best matches acoustic(t - lag)
+    fn window_lag(kinematic: &Signal, acoustic: &Signal, start: f64, end: f64, options: &SyncOptions) -> Option<(f64, f64)> {
+        let step = options.sample_step;
+        let max_shift = (options.max_lag / step).round() as isize;
+        let points = ((end - start) / step).floor() as isize + 1;
+        if points < 3 { return None; }
+
+        let grid = |signal: &Signal, shift: f64| -> Vec<f64> {
+            (0..points).map(|k| signal.interpolate(start + k as f64 * step - shift)).collect()
+        };
+        let kinematic_values = grid(kinematic, 0.0);
+
+        let mut scores = Vec::with_capacity((2 * max_shift + 1) as usize);
+        for shift in -max_shift..=max_shift {
+            let acoustic_values = grid(acoustic, shift as f64 * step);
+            let pairs: Vec<(f64, f64)> = kinematic_values.iter().zip(&acoustic_values)
+                .filter(|(a, b)| !a.is_nan() && !b.is_nan())
+                .map(|(a, b)| (*a, *b))
+                .collect();
+            // need a reasonable overlap for the correlation to mean anything
+            if pairs.len() < (points as usize / 2).max(3) { scores.push(f64::NAN); continue; }
+
+            let a = z_normalise(&pairs.iter().map(|p| p.0).collect::<Vec<_>>());
+            let b = z_normalise(&pairs.iter().map(|p| p.1).collect::<Vec<_>>());
+            scores.push(a.iter().zip(&b).map(|(x, y)| x * y).sum::<f64>() / (pairs.len() - 1) as f64);
+        }
+
+        let best = (0..scores.len()).filter(|k| !scores[*k].is_nan())
+            .max_by(|x, y| scores[*x].partial_cmp(&scores[*y]).unwrap())?;
+
+        // parabolic refinement between grid lags
+        let mut shift = best as f64;
+        if best > 0 && best + 1 < scores.len() && !scores[best - 1].is_nan() && !scores[best + 1].is_nan() {
+            let (a, b, c) = (scores[best - 1], scores[best], scores[best + 1]);
+            let denominator = a - 2.0 * b + c;
+            if denominator != 0.0 { shift += (0.5 * (a - c) / denominator).clamp(-0.5, 0.5); }
+        }
+        Some(((shift - max_shift as f64) * step, scores[best]))
+    }
+
+    // least squares y = a + b x
+    fn line(x: &[f64], y: &[f64]) -> Option<(f64, f64)> {
+        let n = x.len() as f64;
+        let mean_x = x.iter().sum::<f64>() / n;
+        let mean_y = y.iter().sum::<f64>() / n;
+        let sxx: f64 = x.iter().map(|v| (v - mean_x).powi(2)).sum();
+        if sxx <= 0.0 { return None; }
+        let sxy: f64 = x.iter().zip(y).map(|(a, b)| (a - mean_x) * (b - mean_y)).sum();
+        let slope = sxy / sxx;
+        Some((mean_y - slope * mean_x, slope))
+    }
+}
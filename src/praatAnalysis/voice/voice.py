@@ -0,0 +1,97 @@
+"""
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+"""
+
+
+"""
+    Voice quality through Praat's own commands (parselmouth.praat.call) so the numbers match Praat's voice report.
+
+    The first row is always the whole file, then one row per (start, end) span (clipped to the file). Jitter and shimmer use the
+    periodic point process of the whole file limited to the span, CPPS is measured on the extracted span.
+    Anything Praat can't measure (too few periods, span shorter than the analysis window) is NaN.
+"""
+
+import math
+import parselmouth
+from parselmouth.praat import call
+
+
+def _defined(value):
+    if value is None or math.isnan(value) or value <= -200.0:
+        return math.nan
+    return float(value)
+
+
+def _measure(function):
+    # praat raises for spans it can't analyse
+    try:
+        return _defined(function())
+    except parselmouth.PraatError:
+        return math.nan
+
+
+def _cpps(sound, parameters):
+    cepstrogram = call(
+        sound, "To PowerCepstrogram",
+        parameters["pitch_floor"], parameters["cepstrum_time_step"],
+        parameters["cepstrum_max_frequency"], parameters["cepstrum_pre_emphasis"],
+    )
+    # Praat's defaults: trend subtracted, peak searched between 60 and 330 Hz, straight trend line fitted
+    # robustly between 0.001 s and 0.05 s quefrency
+    return call(
+        cepstrogram, "Get CPPS", "yes",
+        parameters["cpps_time_averaging"], parameters["cpps_quefrency_averaging"],
+        60.0, 330.0, 0.05, "parabolic", 0.001, 0.05, "Straight", "Robust",
+    )
+
+
+def voice_quality(path, spans, parameters):
+    sound = parselmouth.Sound(path)
+    if sound.n_channels > 1:
+        sound = sound.convert_to_mono()
+
+    floor, ceiling = parameters["pitch_floor"], parameters["pitch_ceiling"]
+    period = (parameters["period_floor"], parameters["period_ceiling"], parameters["max_period_factor"])
+
+    points = call(sound, "To PointProcess (periodic, cc)", floor, ceiling)
+    harmonicity = call(
+        sound, "To Harmonicity (cc)",
+        parameters["hnr_time_step"], floor, parameters["silence_threshold"], parameters["periods_per_window"],
+    )
+
+    out = {key: [] for key in ("start", "end", "jitter_local", "jitter_rap", "jitter_ppq5",
+                              "shimmer_local", "shimmer_apq3", "shimmer_apq5", "shimmer_apq11", "hnr", "cpps")}
+
+    for start, end in [(sound.xmin, sound.xmax)] + list(spans):
+        start, end = max(start, sound.xmin), min(end, sound.xmax)
+        out["start"].append(start)
+        out["end"].append(end)
+        if end <= start:
+            for key, values in out.items():
+                if key not in ("start", "end"):
+                    values.append(math.nan)
+            continue
+
+        for kind in ("local", "rap", "ppq5"):
+            out[f"jitter_{kind}"].append(_measure(lambda: call(points, f"Get jitter ({kind})", start, end, *period)))
+        for kind in ("local", "apq3", "apq5", "apq11"):
+            out[f"shimmer_{kind}"].append(_measure(lambda: call(
+                [sound, points], f"Get shimmer ({kind})", start, end, *period, parameters["max_amplitude_factor"]
+            )))
+        out["hnr"].append(_measure(lambda: call(harmonicity, "Get mean", start, end)))
+
+        part = sound if (start, end) == (sound.xmin, sound.xmax) else sound.extract_part(from_time=start, to_time=end)
+        out["cpps"].append(_measure(lambda: _cpps(part, parameters)))
+
+    return out
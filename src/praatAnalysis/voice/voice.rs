@@ -0,0 +1,124 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Voice quality (parselmouth)
+
+    Runs voice.py through the pyo3 bridge: jitter (local, rap, ppq5), shimmer (local, apq3, apq5, apq11), mean
+    HNR (cc) and CPPS from Praat's own commands, for the whole file and optionally each annotated segment.
+
+    Segments are on the kinematic timeline, so they are taken back to the audio clock with the sync correction
+    before the WAV is analysed (the table keeps the segment times as given).
+*/
+
+use crate::annotation::segments::segments::{Segments};
+use crate::errors::{AudioError, MosaicError};
+use crate::praatAnalysis::setup::PythonEnvironment;
+use crate::praatAnalysis::speechUMD::speechUMD::{VoiceQuality, VoiceMeasures, VoiceParameters};
+use crate::praatAnalysis::sync::sync::{SyncCorrection};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyModule};
+use std::ffi::CString;
+
+pub struct VoiceQualityExtractor;
+
+impl VoiceQualityExtractor {
+    // `tiers` limits the segments measured (i.e. only "phones"), None for every tier
+    pub fn extract(
+        wav_path: &str, parameters: &VoiceParameters, segments: Option<&Segments>, tiers: Option<&[&str]>,
+        sync: &SyncCorrection
+    ) -> Result<VoiceQuality, MosaicError> {
+        let rows: Vec<usize> = match segments {
+            Some(segments) => (0..segments.len())
+                .filter(|&k| tiers.is_none_or(|tiers| tiers.contains(&segments.tier[k].as_str())))
+                .collect(),
+            None => Vec::new(),
+        };
+        let spans: Vec<(f64, f64)> = match segments {
+            Some(segments) => rows.iter().map(|&k| (sync.inverse(segments.start[k]), sync.inverse(segments.end[k]))).collect(),
+            None => Vec::new(),
+        };
+
+        PythonEnvironment::ensure_python_bridge()?;
+        let measures = Python::attach(|py| Self::measure(py, wav_path, parameters, &spans))
+            .map_err(|e| MosaicError::from(AudioError::Python(e.to_string())))?;
+
+        // first row is the whole file (its span comes back on the audio clock)
+        let mut voice = VoiceQuality::construction(rows.len() + 1);
+        for (i, row) in measures.iter().enumerate() {
+            let (segment_id, tier, label, start, end) = match (i, segments) {
+                (0, _) | (_, None) => (0, String::new(), String::new(), sync.map(row[0]), sync.map(row[1])),
+                (_, Some(segments)) => {
+                    let k = rows[i - 1];
+                    (segments.segment_id[k], segments.tier[k].clone(), segments.label[k].clone(), segments.start[k], segments.end[k])
+                }
+            };
+            voice.add_point(segment_id, tier, label, start, end, VoiceMeasures {
+                jitter: [row[2], row[3], row[4]],
+                shimmer: [row[5], row[6], row[7], row[8]],
+                hnr: row[9],
+                cpps: row[10],
+            });
+        }
+        Ok(voice)
+    }
+
+    // per span: [start, end, jitter local, rap, ppq5, shimmer local, apq3, apq5, apq11, hnr, cpps]
+    fn measure(py: Python<'_>, wav_path: &str, parameters: &VoiceParameters, spans: &[(f64, f64)]) -> PyResult<Vec<[f64; 11]>> {
+        let code_rust_str = include_str!("voice.py");
+
+        let code_c_string = CString::new(code_rust_str)
+            .expect("Python script contained a null byte!");
+
+        let module = PyModule::from_code(
+            py,
+            &code_c_string,
+            c"voice.py",
+            c"voice"
+        )?;
+
+        let settings = PyDict::new(py);
+        settings.set_item("pitch_floor", parameters.pitch_floor)?;
+        settings.set_item("pitch_ceiling", parameters.pitch_ceiling)?;
+        settings.set_item("period_floor", parameters.period_floor)?;
+        settings.set_item("period_ceiling", parameters.period_ceiling)?;
+        settings.set_item("max_period_factor", parameters.max_period_factor)?;
+        settings.set_item("max_amplitude_factor", parameters.max_amplitude_factor)?;
+        settings.set_item("hnr_time_step", parameters.hnr_time_step)?;
+        settings.set_item("silence_threshold", parameters.silence_threshold)?;
+        settings.set_item("periods_per_window", parameters.periods_per_window)?;
+        settings.set_item("cepstrum_time_step", parameters.cepstrum_time_step)?;
+        settings.set_item("cepstrum_max_frequency", parameters.cepstrum_max_frequency)?;
+        settings.set_item("cepstrum_pre_emphasis", parameters.cepstrum_pre_emphasis)?;
+        settings.set_item("cpps_time_averaging", parameters.cpps_time_averaging)?;
+        settings.set_item("cpps_quefrency_averaging", parameters.cpps_quefrency_averaging)?;
+
+        let func = module.getattr("voice_quality")?;
+        let result = func.call1((wav_path, spans.to_vec(), settings))?;
+
+        let mut columns: Vec<Vec<f64>> = Vec::with_capacity(11);
+        for key in [
+            "start", "end",
+            "jitter_local", "jitter_rap", "jitter_ppq5",
+            "shimmer_local", "shimmer_apq3", "shimmer_apq5", "shimmer_apq11",
+            "hnr", "cpps",
+        ] {
+            columns.push(result.get_item(key)?.extract()?);
+        }
+
+        let rows = columns[0].len();
+        Ok((0..rows).map(|r| std::array::from_fn(|c| columns[c][r])).collect())
+    }
+}
@@ -0,0 +1,312 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Vowel space
+
+    Acoustic counterpart of the articulatory working space: F1 / F2 of every vowel segment (at the segment
+    midpoint, as in the segment summaries) and per speaker
+
+        - triangle area: /i/, /a/, /u/ category means
+        - quadrilateral area: /i/, /æ/, /a/, /u/ category means
+        - hull area: convex hull of every vowel token (any vowel the label map knows)
+        - FCR = (F2u + F2a + F1i + F1u) / (F2i + F1a) (Sapir et al., 2010), VAI = 1 / FCR
+        - F2i / F2u
+
+    Areas are in Hz^2 in the (F2, F1) plane. Anything needing a corner vowel that wasn't annotated is NaN.
+
+    Labels are mapped to vowels with a `VowelMap`. The default knows IPA, X-SAMPA / BAS (MAUS) and ARPAbet (stress
+    digits ignored, so MFA's "IY1" is /i/); extra labels can be added for other alphabets.
+*/
+
+use crate::annotation::segments::segments::{Segments};
+use crate::complexMeasurements::signal::signal::{Signal};
+use crate::coreMeasurements::area::polygon::{PolygonCalculator};
+use crate::praatAnalysis::speechUMD::speechUMD::{SpeechUMD};
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Corner {
+    I, // high front
+    Ae, // low front
+    A, // low back / central
+    U, // high back
+    Other, // any other vowel (hull only)
+}
+
+impl Corner {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Corner::I => "i",
+            Corner::Ae => "ae",
+            Corner::A => "a",
+            Corner::U => "u",
+            Corner::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VowelMap {
+    pub labels: HashMap<String, Corner>,
+}
+
+impl Default for VowelMap {
+    fn default() -> Self {
+        let mut map = Self { labels: HashMap::new() };
+        // IPA
+        map.insert_all(&["i", "iː", "i:"], Corner::I);
+        map.insert_all(&["æ"], Corner::Ae);
+        map.insert_all(&["a", "aː", "a:", "ɑ", "ɑː", "ɑ:"], Corner::A);
+        map.insert_all(&["u", "uː", "u:"], Corner::U);
+        map.insert_all(&["ɪ", "e", "eː", "ɛ", "ə", "ɐ", "ʌ", "ɔ", "ɔː", "o", "oː", "ʊ", "ɒ", "ɜ", "ɜː", "y", "yː", "ø", "øː", "œ", "ʏ"], Corner::Other);
+        // X-SAMPA / BAS
+        map.insert_all(&["{"], Corner::Ae);
+        map.insert_all(&["A", "A:"], Corner::A);
+        map.insert_all(&["I", "E", "E:", "@", "6", "V", "O", "O:", "U", "Q", "3", "3:", "2:", "9", "Y"], Corner::Other);
+        // ARPAbet
+        map.insert_all(&["IY"], Corner::I);
+        map.insert_all(&["AE"], Corner::Ae);
+        map.insert_all(&["AA"], Corner::A);
+        map.insert_all(&["UW"], Corner::U);
+        map.insert_all(&["IH", "EH", "AH", "AO", "UH", "ER", "AX", "EY", "OW"], Corner::Other);
+        map
+    }
+}
+
+impl VowelMap {
+    pub fn insert(&mut self, label: &str, corner: Corner) {
+        self.labels.insert(label.to_string(), corner);
+    }
+
+    fn insert_all(&mut self, labels: &[&str], corner: Corner) {
+        for label in labels { self.insert(label, corner); }
+    }
+
+    // exact label first, then without ARPAbet stress digits. Non vowels are None
+    pub fn corner(&self, label: &str) -> Option<Corner> {
+        let label = label.trim();
+        self.labels.get(label).copied()
+            .or_else(|| self.labels.get(label.trim_end_matches(|c: char| c.is_ascii_digit())).copied())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VowelTokens {
+    pub speaker: Vec<String>,
+    pub segment_id: Vec<u32>,
+    pub label: Vec<String>,
+    pub vowel: Vec<String>, // corner name
+    pub midpoint: Vec<f64>, // s
+    pub f1: Vec<f64>, // Hz
+    pub f2: Vec<f64>,
+}
+
+impl VowelTokens {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            speaker: Vec::with_capacity(estimated_entries),
+            segment_id: Vec::with_capacity(estimated_entries),
+            label: Vec::with_capacity(estimated_entries),
+            vowel: Vec::with_capacity(estimated_entries),
+            midpoint: Vec::with_capacity(estimated_entries),
+            f1: Vec::with_capacity(estimated_entries),
+            f2: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn add_point(
+        &mut self, speaker: String, segment_id: u32, label: String, vowel: Corner, midpoint: f64,
+        (f1, f2): (f64, f64)
+    ) {
+        self.speaker.push(speaker);
+        self.segment_id.push(segment_id);
+        self.label.push(label);
+        self.vowel.push(vowel.name().to_string());
+        self.midpoint.push(midpoint);
+        self.f1.push(f1);
+        self.f2.push(f2);
+    }
+
+    // tokens of several trials of the same speaker
+    pub fn extend(&mut self, other: &VowelTokens) {
+        self.speaker.extend(other.speaker.iter().cloned());
+        self.segment_id.extend(&other.segment_id);
+        self.label.extend(other.label.iter().cloned());
+        self.vowel.extend(other.vowel.iter().cloned());
+        self.midpoint.extend(&other.midpoint);
+        self.f1.extend(&other.f1);
+        self.f2.extend(&other.f2);
+    }
+
+    pub fn save_vowel_tokens_to_parquet(data: &VowelTokens, file_path: &str) -> PolarsResult<()> {
+        let s_speaker = Series::new("speaker", &data.speaker);
+        let s_id = Series::new("segment_id", &data.segment_id);
+        let s_label = Series::new("label", &data.label);
+        let s_vowel = Series::new("vowel", &data.vowel);
+        let s_midpoint = Series::new("midpoint", &data.midpoint);
+        let s_f1 = Series::new("f1", &data.f1);
+        let s_f2 = Series::new("f2", &data.f2);
+
+        let mut df = DataFrame::new(vec![
+            s_speaker, s_id, s_label, s_vowel, s_midpoint, s_f1, s_f2,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported vowel token data to: {}", file_path);
+        Ok(())
+    }
+}
+
+// one row per speaker
+#[derive(Debug, Clone)]
+pub struct VowelSpace {
+    pub speaker: Vec<String>,
+    pub tokens: Vec<u32>,
+    pub triangle_area: Vec<f64>, // Hz^2
+    pub quadrilateral_area: Vec<f64>,
+    pub hull_area: Vec<f64>,
+    pub fcr: Vec<f64>,
+    pub vai: Vec<f64>,
+    pub f2i_f2u: Vec<f64>,
+}
+
+impl VowelSpace {
+    pub fn construction(estimated_entries: usize) -> Self {
+        Self {
+            speaker: Vec::with_capacity(estimated_entries),
+            tokens: Vec::with_capacity(estimated_entries),
+            triangle_area: Vec::with_capacity(estimated_entries),
+            quadrilateral_area: Vec::with_capacity(estimated_entries),
+            hull_area: Vec::with_capacity(estimated_entries),
+            fcr: Vec::with_capacity(estimated_entries),
+            vai: Vec::with_capacity(estimated_entries),
+            f2i_f2u: Vec::with_capacity(estimated_entries),
+        }
+    }
+
+    pub fn save_vowel_space_to_parquet(data: &VowelSpace, file_path: &str) -> PolarsResult<()> {
+        let s_speaker = Series::new("speaker", &data.speaker);
+        let s_tokens = Series::new("tokens", &data.tokens);
+        let s_triangle = Series::new("triangle_area", &data.triangle_area);
+        let s_quadrilateral = Series::new("quadrilateral_area", &data.quadrilateral_area);
+        let s_hull = Series::new("hull_area", &data.hull_area);
+        let s_fcr = Series::new("fcr", &data.fcr);
+        let s_vai = Series::new("vai", &data.vai);
+        let s_f2 = Series::new("f2i_f2u", &data.f2i_f2u);
+
+        let mut df = DataFrame::new(vec![
+            s_speaker, s_tokens, s_triangle, s_quadrilateral, s_hull, s_fcr, s_vai, s_f2,
+        ])?;
+
+        let file = File::create(file_path).map_err(PolarsError::from)?;
+        ParquetWriter::new(file).finish(&mut df)?;
+        println!("Successfully exported vowel space data to: {}", file_path);
+        Ok(())
+    }
+}
+
+pub struct VowelSpaceCalculator;
+
+impl VowelSpaceCalculator {
+    // F1 / F2 at the midpoint of every vowel segment. `tiers` limits the segments (i.e. only "phones" so a word
+    // like "a" isn't read as a vowel). Tokens without both formants at the midpoint, or with a formant undefined
+    // in a frame next to it, are left out
+    pub fn tokens(
+        speaker: &str, segments: &Segments, speech: &SpeechUMD, tiers: Option<&[&str]>, map: &VowelMap
+    ) -> VowelTokens {
+        let f1 = Signal::from_speech(speech, "f1");
+        let f2 = Signal::from_speech(speech, "f2");
+        // the SpeechUMD frame step (1.5 steps so timestamp rounding isn't read as a gap)
+        let max_gap = 1.5 * speech.timestamp.windows(2).map(|w| (w[1] - w[0]) as f64).filter(|d| *d > 0.0).fold(f64::NAN, f64::min);
+
+        let mut tokens = VowelTokens::construction(0);
+        for k in 0..segments.len() {
+            if tiers.is_some_and(|tiers| !tiers.contains(&segments.tier[k].as_str())) { continue; }
+            let Some(corner) = map.corner(&segments.label[k]) else { continue; };
+
+            let midpoint = 0.5 * (segments.start[k] + segments.end[k]);
+            let (f1, f2) = (f1.interpolate_within(midpoint, max_gap), f2.interpolate_within(midpoint, max_gap));
+            if f1.is_nan() || f2.is_nan() { continue; }
+            tokens.add_point(speaker.to_string(), segments.segment_id[k], segments.label[k].clone(), corner, midpoint, (f1, f2));
+        }
+        tokens
+    }
+
+    // every speaker in the tokens gets a row (in order of first appearance)
+    pub fn calculate(tokens: &VowelTokens) -> VowelSpace {
+        let mut speakers: Vec<&str> = Vec::new();
+        for speaker in &tokens.speaker {
+            if !speakers.contains(&speaker.as_str()) { speakers.push(speaker); }
+        }
+
+        let mut space = VowelSpace::construction(speakers.len());
+        for speaker in speakers {
+            let rows: Vec<usize> = (0..tokens.speaker.len()).filter(|&k| tokens.speaker[k] == speaker).collect();
+
+            // category means as (F2, F1)
+            let mean = |corner: Corner| -> Option<(f64, f64)> {
+                let members: Vec<usize> = rows.iter().copied().filter(|&k| tokens.vowel[k] == corner.name()).collect();
+                if members.is_empty() { return None; }
+                let n = members.len() as f64;
+                Some((
+                    members.iter().map(|&k| tokens.f2[k]).sum::<f64>() / n,
+                    members.iter().map(|&k| tokens.f1[k]).sum::<f64>() / n,
+                ))
+            };
+            let (i, ae, a, u) = (mean(Corner::I), mean(Corner::Ae), mean(Corner::A), mean(Corner::U));
+
+            let triangle_area = match (i, a, u) {
+                (Some(i), Some(a), Some(u)) => PolygonCalculator::shoelace(&[i, a, u]).abs(),
+                _ => f64::NAN,
+            };
+            let quadrilateral_area = match (i, ae, a, u) {
+                (Some(i), Some(ae), Some(a), Some(u)) => PolygonCalculator::shoelace(&[i, ae, a, u]).abs(),
+                _ => f64::NAN,
+            };
+
+            let points: Vec<(f64, f64)> = rows.iter().map(|&k| (tokens.f2[k], tokens.f1[k])).collect();
+            let hull = PolygonCalculator::convex_hull_2d(&points);
+            let hull_area = if hull.len() >= 3 {
+                PolygonCalculator::shoelace(&hull.iter().map(|&k| points[k]).collect::<Vec<_>>()).abs()
+            } else {
+                f64::NAN
+            };
+
+            // (F2, F1) pairs: .0 is F2, .1 is F1
+            let fcr = match (i, a, u) {
+                (Some(i), Some(a), Some(u)) => (u.0 + a.0 + i.1 + u.1) / (i.0 + a.1),
+                _ => f64::NAN,
+            };
+            let f2i_f2u = match (i, u) {
+                (Some(i), Some(u)) => i.0 / u.0,
+                _ => f64::NAN,
+            };
+
+            space.speaker.push(speaker.to_string());
+            space.tokens.push(rows.len() as u32);
+            space.triangle_area.push(triangle_area);
+            space.quadrilateral_area.push(quadrilateral_area);
+            space.hull_area.push(hull_area);
+            space.fcr.push(fcr);
+            space.vai.push(1.0 / fcr);
+            space.f2i_f2u.push(f2i_f2u);
+        }
+        space
+    }
+}
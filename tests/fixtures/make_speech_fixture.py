@@ -0,0 +1,97 @@
+"""
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+"""
+
+
+"""
+    Speech backend fixture
+
+    Writes speech_fixture.wav (0.5 s, 16 kHz, 16 bit mono: a 150 Hz harmonic complex plus seeded white noise at
+    20 dB below the harmonics) and speech_fixture_reference.csv with the parselmouth reference of every SpeechUMD
+    feature (f0, intensity, f1-f4, hnr and the spectral moments) on the SpeechUMD frame grid (t = k * 0.01,
+    frame = k + 1, Praat's defaults for the rest).
+
+    The reference is made with features.py itself, so it needs parselmouth and exits with an error without it.
+    The first line of the reference records the parselmouth and Praat versions it was made with.
+
+        python3 tests/fixtures/make_speech_fixture.py
+"""
+
+import math
+import os
+import random
+import struct
+import sys
+import wave
+
+SAMPLE_RATE = 16000
+DURATION = 0.5
+F0 = 150.0
+HARMONICS = 5
+HNR = 20.0  # dB
+
+# SpeechParameters::default()
+TIME_STEP = 0.01
+PITCH_FLOOR = 75.0
+PITCH_CEILING = 600.0
+MAX_FORMANT = 5500.0
+NUMBER_OF_FORMANTS = 5.0
+FORMANT_WINDOW = 0.025
+SPECTRAL_WINDOW = 0.025
+
+FEATURES = ("f0", "intensity", "f1", "f2", "f3", "f4", "hnr",
+            "spectral_cog", "spectral_sd", "spectral_skewness", "spectral_kurtosis")
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+WAV = os.path.join(HERE, "speech_fixture.wav")
+REFERENCE = os.path.join(HERE, "speech_fixture_reference.csv")
+FEATURES_PY = os.path.join(HERE, "..", "..", "src", "praatAnalysis", "features")
+
+
+def synthesise():
+    n = int(round(SAMPLE_RATE * DURATION))
+    harmonic = [sum(math.sin(2 * math.pi * h * F0 * i / SAMPLE_RATE) / h for h in range(1, HARMONICS + 1)) for i in range(n)]
+    harmonic_power = sum(1 / (2 * h * h) for h in range(1, HARMONICS + 1))
+    noise_sd = math.sqrt(harmonic_power / 10 ** (HNR / 10))
+    rng = random.Random(1993)
+    noise = [rng.gauss(0.0, noise_sd) for _ in range(n)]
+
+    scale = 0.5 / max(abs(h + e) for h, e in zip(harmonic, noise))
+    samples = [int(round((h + e) * scale * 32767)) for h, e in zip(harmonic, noise)]
+    with wave.open(WAV, "wb") as out:
+        out.setnchannels(1)
+        out.setsampwidth(2)
+        out.setframerate(SAMPLE_RATE)
+        out.writeframes(struct.pack("<%dh" % n, *samples))
+
+
+if __name__ == "__main__":
+    try:
+        import parselmouth
+        sys.path.insert(0, FEATURES_PY)
+        from features import extract_features
+    except ImportError as error:
+        sys.exit("make_speech_fixture.py needs parselmouth (pip install praat-parselmouth): %s" % error)
+
+    synthesise()
+    reference = extract_features(WAV, TIME_STEP, PITCH_FLOOR, PITCH_CEILING, MAX_FORMANT, NUMBER_OF_FORMANTS,
+                                 FORMANT_WINDOW, SPECTRAL_WINDOW)
+
+    with open(REFERENCE, "w") as out:
+        out.write("# parselmouth %s (Praat %s)\n" % (parselmouth.VERSION, parselmouth.PRAAT_VERSION))
+        out.write("frame,timestamp,%s\n" % ",".join(FEATURES))
+        for k, t in enumerate(reference["timestamp"]):
+            values = ["" if math.isnan(reference[f][k]) else "%.4f" % reference[f][k] for f in FEATURES]
+            out.write("%d,%.2f,%s\n" % (k + 1, t, ",".join(values)))
+    print("wrote %s and %s (parselmouth %s)" % (WAV, REFERENCE, parselmouth.VERSION))
@@ -0,0 +1,92 @@
+/*
+This file is part of MOSAIC.
+
+MOSAIC is free software: you can redistribute it and/or modify it under 
+the terms of the GNU General Public License as published by the Free 
+Software Foundation, either version 3 of the License, or any later version.
+
+MOSAIC is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; 
+without even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR 
+PURPOSE. See the GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License along with 
+MOSAIC. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/*
+    Native speech backend against the parselmouth reference of tests/fixtures/speech_fixture.wav
+    (see tests/fixtures/make_speech_fixture.py for how the fixture and its reference are made)
+*/
+
+use mosaic_core::praatAnalysis::backend::backend::{BackendComparison, SpeechBackend, SpeechExtractor};
+use mosaic_core::praatAnalysis::speechUMD::speechUMD::{SpeechFrame, SpeechParameters, SpeechUMD};
+use std::fs;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/speech_fixture.wav");
+const REFERENCE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/speech_fixture_reference.csv");
+
+// (feature, max mean absolute difference, min definedness agreement)
+const TOLERANCES: [(&str, f64, f64); 11] = [
+    ("f0", 1.0, 0.95), // Hz
+    ("intensity", 1.0, 0.95), // dB
+    ("f1", 50.0, 0.9), // Hz
+    ("f2", 75.0, 0.9),
+    ("f3", 100.0, 0.9),
+    ("f4", 150.0, 0.9),
+    ("hnr", 2.0, 0.95), // dB
+    ("spectral_cog", 25.0, 0.95), // Hz
+    ("spectral_sd", 25.0, 0.95), // Hz
+    ("spectral_skewness", 0.2, 0.95),
+    ("spectral_kurtosis", 1.0, 0.95),
+];
+
+// the first line records the parselmouth version the reference was made with
+fn read_reference() -> SpeechUMD {
+    let text = fs::read_to_string(REFERENCE).expect("Failed to read the fixture reference (run make_speech_fixture.py)");
+    let version = text.lines().next().unwrap_or_default();
+    assert!(version.starts_with("# parselmouth "), "The fixture reference isn't a parselmouth reference: '{version}'");
+
+    let mut reader = csv::ReaderBuilder::new().comment(Some(b'#')).from_reader(text.as_bytes());
+    let header = reader.headers().expect("Fixture reference without a header").clone();
+    let column = |name: &str| header.iter().position(|h| h == name)
+        .unwrap_or_else(|| panic!("Fixture reference without a '{name}' column"));
+
+    let mut reference = SpeechUMD::construction(64);
+    for record in reader.records() {
+        let record = record.expect("Malformed fixture reference row");
+        let value = |name: &str| record[column(name)].parse::<f64>().unwrap_or(f64::NAN);
+        reference.add_point(record[column("frame")].parse().unwrap(), record[column("timestamp")].parse().unwrap(), SpeechFrame {
+            f0: value("f0"),
+            intensity: value("intensity"),
+            formants: [value("f1"), value("f2"), value("f3"), value("f4")],
+            hnr: value("hnr"),
+            moments: [value("spectral_cog"), value("spectral_sd"), value("spectral_skewness"), value("spectral_kurtosis")],
+        });
+    }
+    reference
+}
+fn row(comparison: &BackendComparison, feature: &str) -> usize {
+    comparison.feature.iter().position(|f| f == feature).expect("Feature missing from the comparison")
+}
+
+#[test]
+fn native_backend_matches_reference() {
+    let reference = read_reference();
+    let native = SpeechExtractor::extract(FIXTURE, SpeechBackend::Native, &SpeechParameters::default())
+        .expect("Native extraction failed");
+    assert_eq!(native.len(), reference.len());
+
+    let comparison = SpeechExtractor::compare(&reference, &native);
+    for (feature, max_difference, min_agreement) in TOLERANCES {
+        let i = row(&comparison, feature);
+        assert!(comparison.frames[i] > 0, "{feature}: no frames defined in both");
+        assert!(
+            comparison.mean_absolute_difference[i] <= max_difference,
+            "{feature}: mean absolute difference {} above {max_difference}", comparison.mean_absolute_difference[i]
+        );
+        assert!(
+            comparison.definedness_agreement[i] >= min_agreement,
+            "{feature}: definedness agreement {} below {min_agreement}", comparison.definedness_agreement[i]
+        );
+    }
+}